@@ -0,0 +1,82 @@
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+
+use cargo_autodd::dependency_manager::DependencyAnalyzer;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use tempfile::TempDir;
+
+const CRATES: [&str; 8] = [
+    "serde",
+    "serde_json",
+    "regex",
+    "tokio",
+    "anyhow",
+    "walkdir",
+    "semver",
+    "toml_edit",
+];
+
+/// Generate a project with `files` source files, each importing a few crates
+fn generate_project(files: usize) -> TempDir {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"bench-project\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+    )
+    .expect("failed to write Cargo.toml");
+    fs::create_dir_all(root.join("src")).expect("failed to create src");
+
+    for i in 0..files {
+        write_module(&root.join("src"), i);
+    }
+    temp_dir
+}
+
+fn write_module(dir: &Path, index: usize) {
+    let first = CRATES[index % CRATES.len()];
+    let second = CRATES[(index + 3) % CRATES.len()];
+    let content = format!(
+        r#"use {first}::Item;
+use {{
+    {second}::Other,
+    std::collections::HashMap,
+}};
+
+pub fn function_{index}() -> usize {{
+    let map: HashMap<usize, usize> = HashMap::new();
+    let _ = {first}::helper();
+    map.len()
+}}
+"#
+    );
+    fs::write(dir.join(format!("module_{index}.rs")), content).expect("failed to write module");
+}
+
+fn bench_analyze_dependencies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("analyze_dependencies");
+    for files in [10, 100, 500] {
+        let project = generate_project(files);
+        let analyzer = DependencyAnalyzer::new(project.path().to_path_buf());
+        group.bench_with_input(BenchmarkId::from_parameter(files), &files, |b, _| {
+            b.iter(|| black_box(analyzer.analyze_dependencies().expect("analysis failed")))
+        });
+    }
+    group.finish();
+}
+
+fn bench_import_fingerprint(c: &mut Criterion) {
+    let project = generate_project(100);
+    let analyzer = DependencyAnalyzer::new(project.path().to_path_buf());
+    c.bench_function("import_fingerprint/100", |b| {
+        b.iter(|| black_box(analyzer.import_fingerprint().expect("fingerprint failed")))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_analyze_dependencies,
+    bench_import_fingerprint
+);
+criterion_main!(benches);
@@ -0,0 +1,116 @@
+use std::env;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::write_atomic;
+
+/// Prefix of the cache file name, stored inside the project's target directory and
+/// followed by a hash of the project's manifest path
+const CACHE_FILE_PREFIX: &str = ".cargo-autodd-cache";
+
+/// State persisted between runs to make repeated invocations cheap
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cache {
+    /// Hash of the collected `use`/`extern crate` lines and the manifest after the last
+    /// successful run
+    #[serde(default)]
+    pub imports_hash: Option<u64>,
+}
+
+impl Cache {
+    /// Path of the cache file for a project (`$CARGO_TARGET_DIR` or `<root>/target`)
+    pub fn path_for(project_root: &Path) -> PathBuf {
+        let target_dir = env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| project_root.join("target"));
+        Self::path_in(&target_dir, project_root)
+    }
+
+    /// Path of the cache file for a project in `target_dir`. The name is keyed by the
+    /// canonical manifest path, so projects sharing a target directory keep separate caches.
+    fn path_in(target_dir: &Path, project_root: &Path) -> PathBuf {
+        let root = fs::canonicalize(project_root).unwrap_or_else(|_| project_root.to_path_buf());
+        let mut hasher = DefaultHasher::new();
+        root.join("Cargo.toml").hash(&mut hasher);
+        target_dir.join(format!(
+            "{}-{:016x}.json",
+            CACHE_FILE_PREFIX,
+            hasher.finish()
+        ))
+    }
+
+    /// Load the cache, returning an empty cache if it is missing or unreadable
+    pub fn load(project_root: &Path) -> Self {
+        Self::load_from(&Self::path_for(project_root))
+    }
+
+    fn load_from(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the cache
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        self.save_to(&Self::path_for(project_root))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create cache directory {:?}", parent))?;
+        }
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = Cache::path_in(&temp_dir.path().join("target"), temp_dir.path());
+        assert_eq!(Cache::load_from(&path), Cache::default());
+    }
+
+    #[test]
+    fn test_cache_round_trip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = Cache::path_in(&temp_dir.path().join("target"), temp_dir.path());
+        let cache = Cache {
+            imports_hash: Some(42),
+        };
+        cache.save_to(&path)?;
+        assert_eq!(Cache::load_from(&path), cache);
+        Ok(())
+    }
+
+    #[test]
+    fn test_projects_sharing_a_target_dir_keep_separate_caches() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let target_dir = temp_dir.path().join("shared-target");
+        let (app, tool) = (temp_dir.path().join("app"), temp_dir.path().join("tool"));
+        fs::create_dir_all(&app)?;
+        fs::create_dir_all(&tool)?;
+
+        let app_path = Cache::path_in(&target_dir, &app);
+        let tool_path = Cache::path_in(&target_dir, &tool);
+        assert_ne!(app_path, tool_path);
+        assert_eq!(app_path.parent(), Some(target_dir.as_path()));
+
+        Cache {
+            imports_hash: Some(42),
+        }
+        .save_to(&app_path)?;
+        assert_eq!(Cache::load_from(&tool_path), Cache::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::models::{PlannedChange, UpdatePlan, VersionUpdate};
+use crate::utils::rfc3339_utc;
+
+/// One run's changes to Cargo.toml, as appended to the `--changelog` file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangelogEntry {
+    /// When the changes were written (RFC 3339, UTC)
+    pub timestamp: String,
+    /// `HEAD` of the project's git repository, if it is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub added: Vec<PlannedChange>,
+    pub removed: Vec<PlannedChange>,
+    pub updated: Vec<VersionUpdate>,
+}
+
+impl ChangelogEntry {
+    /// Record the changes of an applied plan, made now in `project_root`
+    pub fn new(project_root: &Path, applied: &UpdatePlan) -> Self {
+        Self {
+            timestamp: rfc3339_utc(SystemTime::now()),
+            commit: git_commit(project_root),
+            added: applied.additions.clone(),
+            removed: applied.removals.clone(),
+            updated: applied.updates.clone(),
+        }
+    }
+
+    /// Append the entry to `path`: one JSON object per line for `.json`/`.jsonl` files,
+    /// a markdown section otherwise
+    pub fn append(&self, path: &Path) -> Result<()> {
+        let json_lines = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("jsonl")
+            });
+        let record = if json_lines {
+            format!("{}\n", serde_json::to_string(self)?)
+        } else {
+            self.to_markdown()
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open changelog {:?}", path))?;
+        file.write_all(record.as_bytes())
+            .with_context(|| format!("Failed to write changelog {:?}", path))
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!("## {}", self.timestamp);
+        if let Some(commit) = &self.commit {
+            out.push_str(&format!(" ({})", commit));
+        }
+        out.push_str("\n\n");
+        for change in &self.added {
+            let version = change
+                .version
+                .as_ref()
+                .map(|version| format!("@{}", version))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "- added `{}{}` to [{}]\n",
+                change.name, version, change.table
+            ));
+        }
+        for change in &self.removed {
+            out.push_str(&format!(
+                "- removed `{}` from [{}]\n",
+                change.name, change.table
+            ));
+        }
+        for update in &self.updated {
+            out.push_str(&format!(
+                "- bumped `{}` from {} to {} in [{}]\n",
+                update.name, update.current, update.latest, update.table
+            ));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Commit hash of `HEAD` in the git repository containing `project_root`
+fn git_commit(project_root: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(project_root)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|commit| !commit.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn entry() -> ChangelogEntry {
+        ChangelogEntry {
+            timestamp: "2024-02-29T10:00:00Z".to_string(),
+            commit: Some("0123abc".to_string()),
+            added: vec![PlannedChange {
+                table: "dependencies".to_string(),
+                name: "serde".to_string(),
+                version: Some("1.2".to_string()),
+                path: None,
+            }],
+            removed: vec![PlannedChange {
+                table: "dev-dependencies".to_string(),
+                name: "regex".to_string(),
+                version: None,
+                path: None,
+            }],
+            updated: vec![VersionUpdate {
+                table: "dependencies".to_string(),
+                name: "tokio".to_string(),
+                current: "1.0".to_string(),
+                latest: "1.43.0".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_append_markdown() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("DEPENDENCIES.md");
+        entry().append(&path)?;
+        entry().append(&path)?;
+
+        let expected = "## 2024-02-29T10:00:00Z (0123abc)\n\n\
+                        - added `serde@1.2` to [dependencies]\n\
+                        - removed `regex` from [dev-dependencies]\n\
+                        - bumped `tokio` from 1.0 to 1.43.0 in [dependencies]\n\n";
+        assert_eq!(fs::read_to_string(&path)?, expected.repeat(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_json_lines() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("changes.jsonl");
+        entry().append(&path)?;
+        entry().append(&path)?;
+
+        let content = fs::read_to_string(&path)?;
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let record: serde_json::Value = serde_json::from_str(lines[0])?;
+        assert_eq!(record["commit"], "0123abc");
+        assert_eq!(record["added"][0]["name"], "serde");
+        assert_eq!(record["updated"][0]["latest"], "1.43.0");
+        Ok(())
+    }
+}
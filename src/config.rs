@@ -1,60 +1,472 @@
-use anyhow::Result;
-use serde::Deserialize;
-use std::collections::HashSet;
+use anyhow::{Context, Result};
+use regex::Regex;
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::glob_regex;
+use crate::utils::{is_hidden, normalize_package_name};
+
+/// Tables `target_table` may name
+pub const DEPENDENCY_TABLES: [&str; 4] = [
+    "dependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "workspace.dependencies",
+];
+
+/// Name of the config file
+pub const CONFIG_FILE: &str = ".cargo-autodd.toml";
+
+/// Copy of Cargo.toml written before each rewrite when `backup` is enabled, next to it
+pub const BACKUP_FILE: &str = "Cargo.toml.autodd.bak";
+
+/// Find the config file for the project at `start`, like rustfmt does: the nearest
+/// `.cargo-autodd.toml` in `start` or one of its ancestors up to the workspace root (the
+/// closest ancestor whose Cargo.toml has `[workspace]`). Outside a workspace only `start`
+/// itself is searched.
+pub fn find_config_upward(start: &Path) -> Option<PathBuf> {
+    let is_workspace_root = |dir: &Path| {
+        fs::read_to_string(dir.join("Cargo.toml"))
+            .ok()
+            .and_then(|content| content.parse::<toml::Table>().ok())
+            .is_some_and(|manifest| manifest.contains_key("workspace"))
+    };
+    let searched = match start.ancestors().position(is_workspace_root) {
+        Some(root) => root + 1,
+        None => 1,
+    };
+    start
+        .ancestors()
+        .take(searched)
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Granularity of the version requirement written for a newly added dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionFormat {
+    /// `serde = "1"`
+    Major,
+    /// `serde = "1.0"`
+    Minor,
+    /// `serde = "1.0.210"`
+    #[default]
+    Full,
+}
+
+impl VersionFormat {
+    /// Render `version` as a caret requirement of this granularity.
+    ///
+    /// Components are only dropped where the requirement still means the same
+    /// semver-compatible range: `0.3.1` stays `0.3` under `major`, `0.0.4` is always
+    /// written in full, and so are pre-releases.
+    pub fn format(self, version: &Version) -> String {
+        let full = version.to_string();
+        if !version.pre.is_empty() || !version.build.is_empty() {
+            return full;
+        }
+        let components = match self {
+            Self::Full => 3,
+            Self::Minor => 2,
+            Self::Major => 1,
+        };
+        // Leading zero components are part of the compatibility boundary
+        let significant = if version.major > 0 {
+            1
+        } else if version.minor > 0 {
+            2
+        } else {
+            3
+        };
+        full.splitn(4, '.')
+            .take(components.max(significant))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// How a `[[rules]]` entry classifies the crates it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrateClass {
+    /// Never removed, like `essential`
+    Essential,
+    /// Always a dev-dependency, like `dev_only`
+    Dev,
+    /// Left out of the analysis, like `exclude`
+    #[serde(alias = "exclude")]
+    Excluded,
+    /// Its version requirement is never raised by `update` or `sync`
+    Pinned,
+}
+
+/// A `[[rules]]` entry: crates whose name matches `match` get `class`.
+///
+/// The pattern is compiled when the rule is loaded, so an invalid regex fails the config
+/// load instead of silently matching nothing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RuleSpec", into = "RuleSpec")]
+pub struct Rule {
+    pattern: String,
+    pub class: CrateClass,
+    /// The regex `pattern` stands for; `None` for an exact name
+    regex: Option<Regex>,
+}
+
+/// A `[[rules]]` entry as written in the config file
+#[derive(Serialize, Deserialize)]
+struct RuleSpec {
+    /// A glob (`*`, `?`, `[a-z]`), an exact name, or a regex between slashes
+    /// (`/^tokio(-.+)?$/`)
+    #[serde(rename = "match")]
+    pattern: String,
+    class: CrateClass,
+}
+
+impl TryFrom<RuleSpec> for Rule {
+    type Error = String;
+
+    fn try_from(spec: RuleSpec) -> Result<Self, Self::Error> {
+        Rule::new(spec.pattern, spec.class).map_err(|e| format!("{:#}", e))
+    }
+}
+
+impl From<Rule> for RuleSpec {
+    fn from(rule: Rule) -> Self {
+        Self {
+            pattern: rule.pattern,
+            class: rule.class,
+        }
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.class == other.class
+    }
+}
+
+impl Eq for Rule {}
+
+impl Rule {
+    /// Compile a rule; `pattern` is a glob, an exact name or a `/.../` regex
+    pub fn new(pattern: String, class: CrateClass) -> Result<Self> {
+        let regex = match pattern
+            .strip_prefix('/')
+            .and_then(|regex| regex.strip_suffix('/'))
+        {
+            Some(regex) => Some(
+                Regex::new(regex)
+                    .with_context(|| format!("Invalid regex in rule match {:?}", pattern))?,
+            ),
+            None => glob_regex(&pattern),
+        };
+        Ok(Self {
+            pattern,
+            class,
+            regex,
+        })
+    }
+
+    /// The `match` pattern as written
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn matches(&self, crate_name: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(crate_name),
+            None => self.pattern == crate_name,
+        }
+    }
+}
 
 /// Configuration for cargo-autodd
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Crates to exclude from analysis
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted")]
     pub exclude: HashSet<String>,
 
     /// Additional essential dependencies (never removed)
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted")]
     pub essential: HashSet<String>,
 
     /// Crates to always treat as dev-dependencies
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted")]
     pub dev_only: HashSet<String>,
 
     /// Whether to skip tests/ directory analysis
     #[serde(default)]
     pub skip_tests: bool,
+
+    /// Directories (relative to the project root) whose files are not analyzed
+    #[serde(default)]
+    pub exclude_dirs: Vec<PathBuf>,
+
+    /// Whether to analyze hidden files and directories (`.git`, `.cargo`, ...)
+    #[serde(default)]
+    pub include_hidden: bool,
+
+    /// Whether to analyze files marked `@generated` in their first lines (skipped by default)
+    #[serde(default)]
+    pub include_generated: bool,
+
+    /// Table every dependency is managed in, overriding workspace detection
+    #[serde(default)]
+    pub target_table: Option<String>,
+
+    /// Whether to copy Cargo.toml to [`BACKUP_FILE`] before rewriting it
+    #[serde(default)]
+    pub backup: bool,
+
+    /// Granularity of the version written for new dependencies (`major`, `minor`, `full`)
+    #[serde(default)]
+    pub version_format: VersionFormat,
+
+    /// Crates added with `default-features = false`
+    #[serde(default, serialize_with = "sorted")]
+    pub no_default_features: HashSet<String>,
+
+    /// Whether crates used only from test code (`tests/`, `#[cfg(test)]` items) go to
+    /// `[dev-dependencies]` (default) rather than `[dependencies]`
+    #[serde(default = "default_true")]
+    pub test_deps_as_dev: bool,
+
+    /// Whether well-known test and benchmark crates (`criterion`, `proptest`, `tempfile`,
+    /// ...) that the code under `src/` doesn't use are added to `[dev-dependencies]`, e.g.
+    /// when only `benches/` or `examples/` use them
+    #[serde(default = "default_true")]
+    pub likely_dev_deps: bool,
+
+    /// Detected crate names to ignore, as glob patterns (`*`, `?`), unless Cargo.toml
+    /// declares them. Defaults to `*_test` and `*_tests`, which are usually local test
+    /// helper modules.
+    #[serde(default = "default_ignore_patterns")]
+    pub ignore_patterns: Vec<String>,
+
+    /// `[aliases]`: crates.io package for an import name that doesn't follow from it
+    /// (`import_name = "package-name"`), used for lookups and written to Cargo.toml
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Ordered `[[rules]]` classifying crates by pattern; the first matching rule decides.
+    /// `essential`, `dev_only` and `exclude` apply on top, by exact name.
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Serialize a set of names in sorted order, so printed configs are stable
+fn sorted<S: Serializer>(names: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    names.iter().collect::<BTreeSet<_>>().serialize(serializer)
+}
+
+fn default_ignore_patterns() -> Vec<String> {
+    vec!["*_test".to_string(), "*_tests".to_string()]
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            exclude: HashSet::new(),
+            essential: HashSet::new(),
+            dev_only: HashSet::new(),
+            skip_tests: false,
+            exclude_dirs: Vec::new(),
+            include_hidden: false,
+            include_generated: false,
+            target_table: None,
+            backup: false,
+            version_format: VersionFormat::default(),
+            no_default_features: HashSet::new(),
+            test_deps_as_dev: true,
+            likely_dev_deps: true,
+            ignore_patterns: default_ignore_patterns(),
+            aliases: BTreeMap::new(),
+            rules: Vec::new(),
+        }
+    }
 }
 
 impl Config {
-    /// Load config from a file path
+    /// Load config from a file path: JSON for `.json`, YAML for `.yaml`/`.yml`, TOML
+    /// otherwise
     pub fn load(path: &Path) -> Result<Self> {
         if path.exists() {
-            let content = fs::read_to_string(path)?;
-            let config: Config = toml::from_str(&content)?;
+            let config: Config = Self::read_file(path)?;
+            config.validate()?;
             Ok(config)
         } else {
             Ok(Self::default())
         }
     }
 
-    /// Load config from the default path (.cargo-autodd.toml)
+    /// Load config from a file path (if it exists) with the keys of `inline`, a TOML
+    /// config passed on the command line, taking precedence over the file's
+    pub fn load_with_inline(path: &Path, inline: &str) -> Result<Self> {
+        let mut table = if path.exists() {
+            Self::read_file::<toml::Table>(path)?
+        } else {
+            toml::Table::new()
+        };
+        let overrides = toml::from_str::<toml::Table>(inline).context("Invalid inline config")?;
+        table.extend(overrides);
+
+        let config =
+            Config::deserialize(toml::Value::Table(table)).context("Invalid inline config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse a config file in the format its extension names
+    fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let parsed = match extension.as_deref() {
+            Some("json") => serde_json::from_str(&content).map_err(anyhow::Error::from),
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(anyhow::Error::from),
+            _ => toml::from_str(&content).map_err(anyhow::Error::from),
+        };
+        parsed.with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Load config from the default path: the `.cargo-autodd.toml` found by
+    /// [`find_config_upward`] from `project_root`
     pub fn load_default(project_root: &Path) -> Result<Self> {
-        let config_path = project_root.join(".cargo-autodd.toml");
+        let config_path =
+            find_config_upward(project_root).unwrap_or_else(|| project_root.join(CONFIG_FILE));
         Self::load(&config_path)
     }
 
+    /// Reject settings that can't be applied
+    pub fn validate(&self) -> Result<()> {
+        if let Some(table) = &self.target_table
+            && !DEPENDENCY_TABLES.contains(&table.as_str())
+        {
+            anyhow::bail!(
+                "Invalid target_table {:?}: expected one of {}",
+                table,
+                DEPENDENCY_TABLES.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// The class of the first `[[rules]]` entry matching the crate
+    pub fn classify(&self, crate_name: &str) -> Option<CrateClass> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(crate_name))
+            .map(|rule| rule.class)
+    }
+
+    /// The package an import name is aliased to in `[aliases]`; `-` and `_` are
+    /// interchangeable in the import name
+    pub fn package_for(&self, import_name: &str) -> Option<&str> {
+        let import_name = normalize_package_name(import_name);
+        self.aliases
+            .iter()
+            .find(|(alias, _)| normalize_package_name(alias) == import_name)
+            .map(|(_, package)| package.as_str())
+    }
+
     /// Check if a crate should be excluded
     pub fn should_exclude(&self, crate_name: &str) -> bool {
-        self.exclude.contains(crate_name)
+        self.exclude.contains(crate_name) || self.classify(crate_name) == Some(CrateClass::Excluded)
+    }
+
+    /// Check if a detected crate name matches one of the `ignore_patterns`
+    pub fn is_ignored_name(&self, crate_name: &str) -> bool {
+        self.ignore_patterns
+            .iter()
+            .any(|pattern| match glob_regex(pattern) {
+                Some(regex) => regex.is_match(crate_name),
+                None => pattern == crate_name,
+            })
     }
 
     /// Check if a crate is essential (should never be removed)
     pub fn is_essential(&self, crate_name: &str) -> bool {
         self.essential.contains(crate_name)
+            || self.classify(crate_name) == Some(CrateClass::Essential)
+    }
+
+    /// Check if a path (relative to the project root) lies in an excluded directory
+    pub fn is_excluded_dir(&self, relative: &Path) -> bool {
+        (!self.include_hidden && is_hidden(relative))
+            || self
+                .exclude_dirs
+                .iter()
+                .any(|dir| relative.starts_with(dir))
+    }
+
+    /// Check if a crate should be added without its default features
+    pub fn disables_default_features(&self, crate_name: &str) -> bool {
+        self.no_default_features.contains(crate_name)
     }
 
     /// Check if a crate should always be a dev-dependency
     pub fn is_dev_only(&self, crate_name: &str) -> bool {
-        self.dev_only.contains(crate_name)
+        self.dev_only.contains(crate_name) || self.classify(crate_name) == Some(CrateClass::Dev)
+    }
+
+    /// Check if a crate's version requirement must be left as declared
+    pub fn is_pinned(&self, crate_name: &str) -> bool {
+        self.classify(crate_name) == Some(CrateClass::Pinned)
+    }
+
+    /// Render a commented starter `.cargo-autodd.toml` with this config's `essential` and
+    /// `dev_only` entries (`cargo autodd --init`)
+    pub fn starter_toml(&self) -> String {
+        format!(
+            "# cargo-autodd configuration\n\
+             \n\
+             # Crates to exclude from analysis (e.g., internal crates)\n\
+             exclude = []\n\
+             \n\
+             # Additional essential dependencies (never removed)\n\
+             essential = {}\n\
+             \n\
+             # Crates to always treat as dev-dependencies\n\
+             dev_only = {}\n\
+             \n\
+             # Skip tests/ directory analysis entirely\n\
+             skip_tests = false\n\
+             \n\
+             # Directories (relative to the project root) whose files are not analyzed\n\
+             exclude_dirs = []\n",
+            Self::toml_list(&self.essential),
+            Self::toml_list(&self.dev_only),
+        )
+    }
+
+    /// Render the effective configuration as TOML, with every key set
+    /// (`cargo autodd --print-config`)
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Format crate names as a sorted TOML array
+    fn toml_list(names: &HashSet<String>) -> String {
+        let mut names = names
+            .iter()
+            .map(|name| format!("{:?}", name))
+            .collect::<Vec<_>>();
+        names.sort();
+        format!("[{}]", names.join(", "))
     }
 }
 
@@ -72,9 +484,23 @@ mod tests {
         assert!(config.essential.is_empty());
         assert!(config.dev_only.is_empty());
         assert!(!config.skip_tests);
+        assert!(!config.include_hidden);
+        assert!(!config.include_generated);
+        assert!(config.test_deps_as_dev);
         Ok(())
     }
 
+    #[test]
+    fn test_hidden_dirs_excluded_unless_included() {
+        let mut config = Config::default();
+        assert!(config.is_excluded_dir(Path::new(".git/hooks/pre-commit.rs")));
+        assert!(config.is_excluded_dir(Path::new("src/.scratch.rs")));
+        assert!(!config.is_excluded_dir(Path::new("src/main.rs")));
+
+        config.include_hidden = true;
+        assert!(!config.is_excluded_dir(Path::new(".git/hooks/pre-commit.rs")));
+    }
+
     #[test]
     fn test_load_config_file() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -85,6 +511,8 @@ exclude = ["internal_crate", "another_crate"]
 essential = ["custom_essential"]
 dev_only = ["proptest", "criterion"]
 skip_tests = true
+exclude_dirs = ["generated", "vendor/third_party"]
+no_default_features = ["reqwest"]
 "#;
 
         let mut file = fs::File::create(&config_path)?;
@@ -98,7 +526,197 @@ skip_tests = true
         assert!(config.is_dev_only("proptest"));
         assert!(config.is_dev_only("criterion"));
         assert!(config.skip_tests);
+        assert!(config.is_excluded_dir(Path::new("generated/api.rs")));
+        assert!(config.is_excluded_dir(Path::new("vendor/third_party")));
+        assert!(!config.is_excluded_dir(Path::new("vendor/ours.rs")));
+        assert!(!config.is_excluded_dir(Path::new("generated_docs/lib.rs")));
+        assert!(config.disables_default_features("reqwest"));
+        assert!(!config.disables_default_features("tokio"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_starter_toml_round_trips() -> Result<()> {
+        let config = Config {
+            essential: ["serde_derive".to_string()].into(),
+            dev_only: ["rstest".to_string(), "criterion".to_string()].into(),
+            ..Default::default()
+        };
+        let rendered = config.starter_toml();
+        assert!(rendered.contains("essential = [\"serde_derive\"]"));
+        assert!(rendered.contains("dev_only = [\"criterion\", \"rstest\"]"));
+
+        let parsed: Config = toml::from_str(&rendered)?;
+        assert_eq!(parsed.essential, config.essential);
+        assert_eq!(parsed.dev_only, config.dev_only);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_target_table() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config_path = temp_dir.path().join(".cargo-autodd.toml");
+        fs::write(&config_path, "target_table = \"dev-dependencies\"\n")?;
+        assert_eq!(
+            Config::load(&config_path)?.target_table.as_deref(),
+            Some("dev-dependencies")
+        );
+
+        fs::write(&config_path, "target_table = \"deps\"\n")?;
+        let err = Config::load(&config_path).unwrap_err();
+        assert!(err.to_string().contains("Invalid target_table"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_format() -> Result<()> {
+        let cases = [
+            ("1.0.210", ["1", "1.0", "1.0.210"]),
+            ("0.3.1", ["0.3", "0.3", "0.3.1"]),
+            ("0.0.4", ["0.0.4", "0.0.4", "0.0.4"]),
+            ("2.0.0-rc.1", ["2.0.0-rc.1", "2.0.0-rc.1", "2.0.0-rc.1"]),
+        ];
+        for (version, [major, minor, full]) in cases {
+            let version = Version::parse(version)?;
+            assert_eq!(VersionFormat::Major.format(&version), major);
+            assert_eq!(VersionFormat::Minor.format(&version), minor);
+            assert_eq!(VersionFormat::Full.format(&version), full);
+        }
+
+        let temp_dir = TempDir::new()?;
+        let config_path = temp_dir.path().join(".cargo-autodd.toml");
+        assert_eq!(
+            Config::load(&config_path)?.version_format,
+            VersionFormat::Full
+        );
+        fs::write(&config_path, "version_format = \"minor\"\n")?;
+        assert_eq!(
+            Config::load(&config_path)?.version_format,
+            VersionFormat::Minor
+        );
+        fs::write(&config_path, "version_format = \"patch\"\n")?;
+        assert!(Config::load(&config_path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_json_and_yaml_config() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let files = [
+            (
+                "config.toml",
+                "exclude = [\"internal\"]\nskip_tests = true\nversion_format = \"minor\"\n",
+            ),
+            (
+                "config.json",
+                r#"{"exclude": ["internal"], "skip_tests": true, "version_format": "minor"}"#,
+            ),
+            (
+                "config.yaml",
+                "exclude:\n  - internal\nskip_tests: true\nversion_format: minor\n",
+            ),
+            (
+                "config.yml",
+                "exclude: [internal]\nskip_tests: true\nversion_format: minor\n",
+            ),
+        ];
+        for (name, content) in files {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, content)?;
+            let config = Config::load(&path)?;
+            assert!(config.should_exclude("internal"), "{}", name);
+            assert!(config.skip_tests, "{}", name);
+            assert_eq!(config.version_format, VersionFormat::Minor, "{}", name);
+            assert!(config.test_deps_as_dev, "{}", name);
+        }
+
+        // Inline TOML still overrides a YAML file's keys
+        let path = temp_dir.path().join("config.yaml");
+        let config = Config::load_with_inline(&path, "version_format = \"major\"")?;
+        assert!(config.should_exclude("internal"));
+        assert_eq!(config.version_format, VersionFormat::Major);
+
+        // The extension decides the parser: TOML in a .json file is an error
+        let path = temp_dir.path().join("toml.json");
+        fs::write(&path, "skip_tests = true\n")?;
+        let err = Config::load(&path).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("Invalid config file"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_with_inline() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config_path = temp_dir.path().join(".cargo-autodd.toml");
+
+        // Without a file, the inline config is the whole config
+        let config = Config::load_with_inline(&config_path, "exclude = [\"foo\"]")?;
+        assert!(config.should_exclude("foo"));
+        assert!(config.test_deps_as_dev);
+
+        // Inline keys replace the file's, other file keys are kept
+        fs::write(
+            &config_path,
+            "exclude = [\"internal\"]\nessential = [\"serde_derive\"]\n",
+        )?;
+        let config = Config::load_with_inline(
+            &config_path,
+            "exclude = [\"foo\"]\nversion_format = \"minor\"",
+        )?;
+        assert!(config.should_exclude("foo"));
+        assert!(!config.should_exclude("internal"));
+        assert!(config.is_essential("serde_derive"));
+        assert_eq!(config.version_format, VersionFormat::Minor);
+
+        // Malformed TOML, unknown values and invalid settings are rejected
+        for inline in [
+            "exclude = [\"foo\"",
+            "skip_tests = \"yes\"",
+            "target_table = \"deps\"",
+        ] {
+            assert!(Config::load_with_inline(&config_path, inline).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_config_upward_stops_at_workspace_root() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("repo");
+        let member = root.join("crates/app");
+        fs::create_dir_all(&member)?;
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/app\"]\n",
+        )?;
+        fs::write(member.join("Cargo.toml"), "[package]\nname = \"app\"\n")?;
+
+        // Above the workspace root is never searched
+        fs::write(temp_dir.path().join(CONFIG_FILE), "skip_tests = true\n")?;
+        assert_eq!(find_config_upward(&member), None);
+
+        fs::write(root.join(CONFIG_FILE), "exclude = [\"internal\"]\n")?;
+        assert_eq!(find_config_upward(&member), Some(root.join(CONFIG_FILE)));
+        assert!(Config::load_default(&member)?.should_exclude("internal"));
 
+        // The nearest config wins
+        fs::write(member.join(CONFIG_FILE), "essential = [\"serde_derive\"]\n")?;
+        assert_eq!(find_config_upward(&member), Some(member.join(CONFIG_FILE)));
+        assert!(!Config::load_default(&member)?.should_exclude("internal"));
+
+        // Outside a workspace, only the project directory itself counts
+        let standalone = temp_dir.path().join("standalone");
+        fs::create_dir_all(&standalone)?;
+        fs::write(
+            standalone.join("Cargo.toml"),
+            "[package]\nname = \"solo\"\n",
+        )?;
+        assert_eq!(find_config_upward(&standalone), None);
         Ok(())
     }
 
@@ -120,7 +738,79 @@ exclude = ["internal_crate"]
         assert!(config.essential.is_empty());
         assert!(config.dev_only.is_empty());
         assert!(!config.skip_tests);
+        assert!(config.test_deps_as_dev);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_patterns() -> Result<()> {
+        let config = Config::default();
+        assert!(config.is_ignored_name("integration_tests"));
+        assert!(config.is_ignored_name("helper_test"));
+        assert!(!config.is_ignored_name("tempfile"));
+        assert!(!config.is_ignored_name("crates_io_api"));
+
+        let config: Config = toml::from_str("ignore_patterns = [\"fixture_*\", \"scratch\"]")?;
+        assert!(config.is_ignored_name("fixture_data"));
+        assert!(config.is_ignored_name("scratch"));
+        assert!(!config.is_ignored_name("integration_tests"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rules_first_match_wins() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+essential = ["test-helpers"]
+
+[[rules]]
+match = "test-*"
+class = "dev"
+
+[[rules]]
+match = "test-utils"
+class = "essential"
+
+[[rules]]
+match = "/^internal[-_]/"
+class = "exclude"
+
+[[rules]]
+match = "openssl?sys"
+class = "pinned"
+"#,
+        )?;
+        config.validate()?;
+
+        // The glob comes first, so test-utils is dev-only rather than essential
+        assert_eq!(config.classify("test-utils"), Some(CrateClass::Dev));
+        assert!(config.is_dev_only("test-utils"));
+        assert!(!config.is_essential("test-utils"));
+        // The plain sets still apply on top of the rules
+        assert!(config.is_dev_only("test-helpers") && config.is_essential("test-helpers"));
+
+        assert!(config.should_exclude("internal-api"));
+        assert!(config.should_exclude("internal_db"));
+        assert!(!config.should_exclude("internals"));
+        assert!(config.is_pinned("openssl-sys"));
+        assert!(!config.is_pinned("openssl"));
+        assert_eq!(config.classify("serde"), None);
+
+        // Rules survive --print-config
+        let printed: Config = toml::from_str(&config.to_toml()?)?;
+        assert_eq!(printed.rules, config.rules);
 
+        let invalid =
+            toml::from_str::<Config>("[[rules]]\nmatch = \"/(/\"\nclass = \"dev\"\n").unwrap_err();
+        assert!(
+            invalid
+                .to_string()
+                .contains("Invalid regex in rule match \"/(/\""),
+            "{}",
+            invalid
+        );
+        assert!(toml::from_str::<Config>("[[rules]]\nmatch = \"a\"\nclass = \"core\"\n").is_err());
         Ok(())
     }
 }
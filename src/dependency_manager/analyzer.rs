@@ -1,18 +1,42 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use anyhow::{Context, Result};
+use rayon::prelude::*;
 use regex::Regex;
-use toml_edit::{DocumentMut, Item};
+use toml_edit::{DocumentMut, Item, TableLike};
 use walkdir::WalkDir;
 
-use crate::models::CrateReference;
-use crate::utils::is_std_crate;
+use crate::config::{Config, DEPENDENCY_TABLES};
+use crate::metadata;
+use crate::models::{BrokenPathDependency, CrateReference, FeatureUsage};
+use crate::utils::{
+    has_feature_hints, has_item_hint, hinted_crates, implied_features, import_name,
+    is_generated_file, is_std_crate, normalize_package_name, package_edition, package_publish,
+    patched_crates, relative_path, slash_path,
+};
+
+/// `extern crate name;` or `extern crate name as alias;`, possibly preceded on the same
+/// line by attributes such as `#[macro_use]`
+const EXTERN_CRATE_PATTERN: &str = r"^\s*(?:#\[[^\]]*\]\s*)*(?:pub(?:\([^)]*\))?\s+)?extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)(?:\s+as\s+([a-zA-Z_][a-zA-Z0-9_]*))?";
+
+/// A single-predicate platform `cfg` attribute: `#[cfg(windows)]`, `#[cfg(unix)]` or
+/// `#[cfg(target_os = "linux")]` and the other `target_*` keys
+const PLATFORM_CFG_PATTERN: &str = r#"^#\[\s*cfg\s*\(\s*(?:(windows|unix)|(target_(?:os|family|arch|env|vendor|pointer_width|endian))\s*=\s*"([^"]+)")\s*\)\s*\]"#;
 
 pub struct DependencyAnalyzer {
     project_root: PathBuf,
     debug: bool,
+    config: Config,
+    /// Workspace member manifests the last analysis couldn't read, with the error
+    broken_members: Mutex<Vec<(PathBuf, String)>>,
+    /// Declared path dependencies the last analysis found pointing nowhere
+    broken_paths: Mutex<Vec<BrokenPathDependency>>,
+    /// Files to analyze instead of walking the project (`--files-from`)
+    files: Option<Vec<PathBuf>>,
 }
 
 impl DependencyAnalyzer {
@@ -20,6 +44,10 @@ impl DependencyAnalyzer {
         Self {
             project_root,
             debug: false,
+            config: Config::default(),
+            broken_members: Mutex::new(Vec::new()),
+            broken_paths: Mutex::new(Vec::new()),
+            files: None,
         }
     }
 
@@ -27,72 +55,222 @@ impl DependencyAnalyzer {
         Self {
             project_root,
             debug,
+            config: Config::default(),
+            broken_members: Mutex::new(Vec::new()),
+            broken_paths: Mutex::new(Vec::new()),
+            files: None,
         }
     }
 
-    pub fn analyze_dependencies(&self) -> Result<HashMap<String, CrateReference>> {
-        let mut crate_refs = HashMap::new();
-        let mut dev_crate_refs = HashMap::new();
-        let extern_regex = Regex::new(r"^\s*extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)")?;
+    /// Print analysis details (`-vv`)
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
 
-        // Load internal crate information from existing Cargo.toml
-        self.load_existing_dependencies(&mut crate_refs)?;
+    /// Analyze the project at `project_root` from now on
+    pub fn set_project_root(&mut self, project_root: PathBuf) {
+        self.project_root = project_root;
+    }
 
-        // Walk through all Rust files in the project
-        for entry in WalkDir::new(&self.project_root) {
-            let entry = entry?;
-            let path = entry.path();
+    /// Use `config` for analysis settings such as excluded directories
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
 
-            // Skip build scripts
-            if path.file_name().is_some_and(|f| f == "build.rs") {
+    /// Analyze exactly `files` instead of the files found by walking the project; `mod`
+    /// declarations and includes are not followed either
+    pub fn set_files(&mut self, files: Option<Vec<PathBuf>>) {
+        self.files = files;
+    }
+
+    /// Path dependencies declared in Cargo.toml whose directory or manifest was missing
+    /// during the last `analyze_dependencies`
+    pub fn broken_paths(&self) -> Vec<BrokenPathDependency> {
+        self.broken_paths
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Path dependencies in the manifest's dependency tables whose directory, resolved
+    /// relative to `manifest_dir`, or its Cargo.toml doesn't exist
+    pub(crate) fn find_broken_paths(
+        manifest_dir: &Path,
+        doc: &DocumentMut,
+    ) -> Vec<BrokenPathDependency> {
+        let mut broken = Vec::new();
+        for table in DEPENDENCY_TABLES {
+            let mut item = doc.as_item();
+            for key in table.split('.') {
+                item = item.get(key).unwrap_or(&Item::None);
+            }
+            let Some(deps) = item.as_table_like() else {
                 continue;
+            };
+            for (name, dep) in deps.iter() {
+                let Some(path) = dep.get("path").and_then(|path| path.as_str()) else {
+                    continue;
+                };
+                let dir = manifest_dir.join(path);
+                let problem = if !dir.is_dir() {
+                    "directory does not exist"
+                } else if !dir.join("Cargo.toml").is_file() {
+                    "directory has no Cargo.toml"
+                } else {
+                    continue;
+                };
+                broken.push(BrokenPathDependency {
+                    table: table.to_string(),
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    problem: problem.to_string(),
+                });
             }
+        }
+        broken.sort_by(|a, b| (&a.table, &a.name).cmp(&(&b.table, &b.name)));
+        broken
+    }
+
+    /// Workspace member manifests that couldn't be read or parsed during the last
+    /// `analyze_dependencies`; imports of those members can't be resolved
+    pub fn broken_members(&self) -> Vec<(PathBuf, String)> {
+        self.broken_members
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn analyze_dependencies(&self) -> Result<HashMap<String, CrateReference>> {
+        let mut crate_refs = HashMap::new();
+        let mut dev_crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
 
-            // Check if this is a test file (in tests/ directory or ends with _test.rs)
-            let is_test_file = path.to_string_lossy().contains("tests/")
-                || path
-                    .file_name()
-                    .is_some_and(|f| f.to_string_lossy().ends_with("_test.rs"));
+        // Load internal crate information from existing Cargo.toml
+        let patched = self.load_existing_dependencies(&mut crate_refs)?;
+
+        // Analyze all Rust files in the project, in parallel on the global rayon pool
+        // (`--threads`); results are merged in file order so the outcome doesn't depend
+        // on scheduling
+        let files = self.collect_rust_files()?;
+        let (feature_gates, platform_gates) = self.gated_modules(&files);
+        let dev_targets = self.dev_target_files();
+        let results = files
+            .par_iter()
+            .map(|file_path| -> Result<_> {
+                let content = fs::read_to_string(file_path)?;
+                let mut file_refs = HashMap::new();
+                let mut externs = HashMap::new();
+                self.analyze_file(FileAnalysisContext {
+                    content: content.trim().to_string(),
+                    file_path,
+                    extern_regex: &extern_regex,
+                    crate_refs: &mut file_refs,
+                    externs: &mut externs,
+                })?;
+                // Files of declared example, test and bench targets only need dev-dependencies
+                let is_dev_file = self.is_test_file(file_path)
+                    || dev_targets.contains(&canonical_path(file_path));
+                Ok((file_path, is_dev_file, file_refs, externs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // In edition 2015, `use foo::...` is relative to the crate root and may name a
+        // local module, so only crates brought in with `extern crate` or declared in
+        // Cargo.toml count as references
+        let known_crates = self.edition_2015_crates().map(|mut known| {
+            known.extend(results.iter().flat_map(|(_, _, _, externs)| {
+                externs.values().map(|e| normalize_package_name(e))
+            }));
+            known
+        });
 
-            if path.extension().is_some_and(|ext| ext == "rs") {
-                let content = fs::read_to_string(path)?;
-                let file_path = path.to_path_buf();
-
-                if is_test_file {
-                    // Analyze as dev-dependency
-                    self.analyze_file(FileAnalysisContext {
-                        content: content.trim().to_string(),
-                        file_path: &file_path,
-                        extern_regex: &extern_regex,
-                        crate_refs: &mut dev_crate_refs,
-                    })?;
+        // `extern crate foo as bar;` binds `bar` for the whole crate (the crate root's
+        // aliases join the extern prelude, and edition 2015 `use` paths start at the crate
+        // root), so `bar::...` in any file is a use of `foo`
+        let aliases = results
+            .iter()
+            .flat_map(|(_, _, _, externs)| externs.iter())
+            .filter(|(name, crate_name)| name != crate_name)
+            .map(|(alias, crate_name)| (alias.clone(), crate_name.clone()))
+            .collect::<HashMap<_, _>>();
+
+        for (file_path, is_test_file, file_refs, _) in results {
+            for (name, mut file_ref) in file_refs {
+                let name = aliases.get(&name).cloned().unwrap_or(name);
+                let name = self.canonical_name(name);
+                // `extern crate self as alias;` refers to the current crate
+                if name == "self" || is_std_crate(&name) {
+                    continue;
+                }
+                // Everything in a platform-gated module is platform-specific
+                if let Some(platform) = platform_gates.get(file_path) {
+                    file_ref.target_cfg = Some(platform.clone());
+                }
+                // Test files and `#[cfg(test)]` items contribute dev-dependencies, everything
+                // else regular ones
+                let target = if is_test_file || file_ref.is_dev_dependency {
+                    &mut dev_crate_refs
                 } else {
-                    // Analyze as regular dependency
-                    self.analyze_file(FileAnalysisContext {
-                        content: content.trim().to_string(),
-                        file_path: &file_path,
-                        extern_regex: &extern_regex,
-                        crate_refs: &mut crate_refs,
-                    })?;
+                    &mut crate_refs
+                };
+                if let Some(known) = &known_crates
+                    && !known.contains(&normalize_package_name(&name))
+                {
+                    if self.debug {
+                        println!("Skipping {}: not an extern crate in edition 2015", name);
+                    }
+                    continue;
                 }
+                target
+                    .entry(name.clone())
+                    .or_insert_with(|| CrateReference::new(name))
+                    .merge_usages(file_ref);
             }
         }
 
-        // Filter out test-only crates from regular dependencies
-        crate_refs.retain(|name, _| {
-            !name.ends_with("_test")
-                && !name.ends_with("_tests")
-                && name != "test"
-                && !name.starts_with("crate")
-        });
+        // Declared registry crates only lend their manifest entry (version, features) to
+        // the usages found above; unused ones must not look used
+        let declared_only = crate_refs
+            .iter()
+            .filter(|(_, crate_ref)| !crate_ref.is_path_dependency && crate_ref.used_in.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let import_names = declared_only
+            .iter()
+            .map(|name| import_name(name))
+            .collect::<Vec<_>>();
+        let mentions = self.find_mentions(&files, &import_names)?;
+        for name in declared_only {
+            let import = import_name(&name);
+            let Some(declared) = crate_refs.remove(&name) else {
+                continue;
+            };
+            if let Some(used) = crate_refs
+                .iter_mut()
+                .chain(dev_crate_refs.iter_mut())
+                .find(|(used, _)| normalize_package_name(used) == import)
+                .map(|(_, crate_ref)| crate_ref)
+            {
+                used.inherit_declaration(&declared);
+            } else if let Some(mentioned_in) = mentions.get(&import) {
+                // Removal needs every strategy to come up empty: a crate still named
+                // somewhere (a derive's helper attribute, a bare `lazy_static!`) is kept
+                let mut declared = declared;
+                declared.mentioned_in = mentioned_in.clone();
+                crate_refs.insert(name, declared);
+            }
+        }
 
-        // Filter out test-only crates from dev-dependencies and mark them
-        dev_crate_refs.retain(|name, _| {
-            !name.ends_with("_test")
-                && !name.ends_with("_tests")
-                && name != "test"
-                && !name.starts_with("crate")
-        });
+        // Names matching `ignore_patterns` (local test helper modules such as
+        // `integration_tests` by default) and capitalized names (types and local items,
+        // like `MyType::new()`) are dropped, unless Cargo.toml declares them
+        let declared = self.declared_crates().unwrap_or_default();
+        let keep = |name: &String| {
+            !(self.config.is_ignored_name(name) || Self::is_capitalized(name))
+                || declared.contains(&normalize_package_name(name))
+        };
+        crate_refs.retain(|name, _| keep(name));
+        dev_crate_refs.retain(|name, _| keep(name));
 
         // Mark dev dependencies and merge into crate_refs
         for (name, mut crate_ref) in dev_crate_refs {
@@ -104,9 +282,34 @@ impl DependencyAnalyzer {
             crate_refs.insert(name, crate_ref);
         }
 
+        // Imports of workspace members refer to local packages, never to the registry
+        self.resolve_workspace_members(&mut crate_refs);
+
+        // Crates used only inside `#[cfg(feature = "...")]` modules are optional
+        for crate_ref in crate_refs.values_mut() {
+            let features = crate_ref
+                .used_in
+                .iter()
+                .map(|path| feature_gates.get(path))
+                .collect::<Option<HashSet<_>>>();
+            if let Some(features) = features {
+                crate_ref.gated_by_features = features.into_iter().cloned().collect();
+            }
+        }
+
+        // Crates redirected by [patch]/[replace] must not be resolved against the registry
+        for (name, crate_ref) in crate_refs.iter_mut() {
+            if patched.contains(&normalize_package_name(name)) {
+                crate_ref.set_patched(true);
+            }
+        }
+
         if self.debug {
             println!("\nFinal crate references:");
-            for (name, crate_ref) in &crate_refs {
+            let mut names = crate_refs.keys().collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                let crate_ref = &crate_refs[name];
                 println!("- {} (used in {} files)", name, crate_ref.usage_count());
                 if crate_ref.is_path_dependency {
                     println!(
@@ -121,7 +324,9 @@ impl DependencyAnalyzer {
                     println!("  Dev dependency: true");
                 }
                 println!("  Used in:");
-                for path in &crate_ref.used_in {
+                let mut paths = crate_ref.used_in.iter().collect::<Vec<_>>();
+                paths.sort();
+                for path in paths {
                     println!("    - {:?}", path);
                 }
             }
@@ -130,14 +335,489 @@ impl DependencyAnalyzer {
         Ok(crate_refs)
     }
 
-    /// Load existing dependency information from Cargo.toml
+    /// Detect the crates used by a single piece of Rust source, without reading the
+    /// manifest or any other file. `file_path` is recorded as the usage location and decides
+    /// whether the source counts as test code (e.g. a path under `tests/`).
+    pub fn analyze_source(
+        &self,
+        content: &str,
+        file_path: &Path,
+    ) -> Result<HashMap<String, CrateReference>> {
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+        let file_path = file_path.to_path_buf();
+        let mut file_refs = HashMap::new();
+        self.analyze_file(FileAnalysisContext {
+            content: content.trim().to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut file_refs,
+            externs: &mut HashMap::new(),
+        })?;
+        let mut crate_refs = HashMap::<String, CrateReference>::new();
+        for (name, mut file_ref) in file_refs {
+            if Self::is_capitalized(&name) {
+                continue;
+            }
+            let name = self.canonical_name(name);
+            match crate_refs.get_mut(&name) {
+                Some(crate_ref) => crate_ref.merge_usages(file_ref),
+                None => {
+                    file_ref.name = name.clone();
+                    crate_refs.insert(name, file_ref);
+                }
+            }
+        }
+        if self.is_test_file(&file_path) {
+            crate_refs
+                .values_mut()
+                .for_each(|crate_ref| crate_ref.set_dev_dependency(true));
+        }
+        Ok(crate_refs)
+    }
+
+    /// The package an imported crate name stands for: its `[aliases]` entry, or the
+    /// name itself
+    fn canonical_name(&self, name: String) -> String {
+        match self.config.package_for(&name) {
+            Some(package) => package.to_string(),
+            None => name,
+        }
+    }
+
+    /// Collect the Rust source files that take part in the analysis.
+    ///
+    /// Files pulled in through `#[path = "..."]` attributes or `include!("...")` are followed
+    /// as well, since they may live outside the project directory or lack an `.rs`
+    /// extension. Included files are compiled into the includer, so they are analyzed even
+    /// if marked `@generated`.
+    fn collect_rust_files(&self) -> Result<Vec<PathBuf>> {
+        if let Some(files) = &self.files {
+            return Ok(files.clone());
+        }
+        let mut files = Vec::new();
+        let walker = WalkDir::new(&self.project_root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !self.is_excluded(entry.path()));
+        for entry in walker {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Skip build scripts
+            if path.file_name().is_some_and(|f| f == "build.rs") {
+                continue;
+            }
+
+            if path.extension().is_some_and(|ext| ext == "rs") {
+                // Generated code imports what its generator needs, not what the user wrote
+                if !self.config.include_generated && is_generated_file(path) {
+                    if self.debug {
+                        println!("Skipping generated file: {:?}", path);
+                    }
+                    continue;
+                }
+                files.push(path.to_path_buf());
+            }
+        }
+
+        let mut visited: HashSet<PathBuf> = files.iter().map(|f| canonical_path(f)).collect();
+        // Targets declared with a custom `path` may live outside the project root; their
+        // modules are found by following `mod` declarations below
+        let roots = self.target_roots();
+        for (target, _) in self.manifest_targets() {
+            if target.is_file()
+                && !self.is_excluded(&target)
+                && visited.insert(canonical_path(&target))
+            {
+                if self.debug {
+                    println!("Adding target outside the walked tree: {:?}", target);
+                }
+                files.push(target);
+            }
+        }
+        let mut index = 0;
+        while index < files.len() {
+            let content = fs::read_to_string(&files[index]).unwrap_or_default();
+            let included_files = Self::path_attribute_targets(&files[index], &content)
+                .into_iter()
+                .chain(Self::include_macro_targets(&files[index], &content))
+                .chain(
+                    // Generated modules stay skipped, as in the walk above
+                    Self::module_declarations(
+                        &files[index],
+                        &content,
+                        roots.contains(&canonical_path(&files[index])),
+                    )
+                    .into_iter()
+                    .map(|declaration| declaration.path)
+                    .filter(|path| self.config.include_generated || !is_generated_file(path)),
+                );
+            for included in included_files {
+                if included.is_file()
+                    && !self.is_excluded(&included)
+                    && visited.insert(canonical_path(&included))
+                {
+                    if self.debug {
+                        println!("Following include: {:?}", included);
+                    }
+                    files.push(included);
+                }
+            }
+            index += 1;
+        }
+
+        Ok(files)
+    }
+
+    /// Source files of the targets Cargo.toml declares with an explicit `path` (`[lib]`,
+    /// `[[bin]]`, `[[example]]`, `[[test]]`, `[[bench]]`), each with whether the target
+    /// only builds for development (examples, tests and benches)
+    fn manifest_targets(&self) -> Vec<(PathBuf, bool)> {
+        let Some(doc) = fs::read_to_string(self.project_root.join("Cargo.toml"))
+            .ok()
+            .and_then(|content| content.parse::<DocumentMut>().ok())
+        else {
+            return Vec::new();
+        };
+        let target_path = |target: &dyn TableLike| {
+            target
+                .get("path")
+                .and_then(|path| path.as_str())
+                .map(|path| self.project_root.join(path))
+        };
+
+        let mut targets = Vec::new();
+        if let Some(path) = doc
+            .get("lib")
+            .and_then(|lib| lib.as_table_like())
+            .and_then(target_path)
+        {
+            targets.push((path, false));
+        }
+        for (kind, dev) in [
+            ("bin", false),
+            ("example", true),
+            ("test", true),
+            ("bench", true),
+        ] {
+            let Some(declared) = doc.get(kind).and_then(|item| item.as_array_of_tables()) else {
+                continue;
+            };
+            targets.extend(
+                declared
+                    .iter()
+                    .filter_map(|target| target_path(target))
+                    .map(|path| (path, dev)),
+            );
+        }
+        targets
+    }
+
+    /// Canonical paths of the files that make up development-only targets declared in
+    /// Cargo.toml (see [`manifest_targets`](Self::manifest_targets)): their root files and
+    /// the modules those declare
+    fn dev_target_files(&self) -> HashSet<PathBuf> {
+        let mut pending = self
+            .manifest_targets()
+            .into_iter()
+            .filter(|(_, dev)| *dev)
+            .map(|(path, _)| (path, true))
+            .collect::<Vec<_>>();
+        let mut dev_files = HashSet::new();
+        while let Some((file, crate_root)) = pending.pop() {
+            if !dev_files.insert(canonical_path(&file)) {
+                continue;
+            }
+            let content = fs::read_to_string(&file).unwrap_or_default();
+            pending.extend(
+                Self::module_declarations(&file, &content, crate_root)
+                    .into_iter()
+                    .map(|declaration| (declaration.path, false)),
+            );
+        }
+        dev_files
+    }
+
+    /// Canonical paths of the root files of the targets declared with a custom `path`
+    fn target_roots(&self) -> HashSet<PathBuf> {
+        self.manifest_targets()
+            .iter()
+            .map(|(path, _)| canonical_path(path))
+            .collect()
+    }
+
+    /// Map module files to the feature and the platform `cfg` gating them, based on
+    /// `#[cfg(feature = "x")] mod foo;` and `#[cfg(windows)] mod foo;` declarations. Modules
+    /// nested inside a gated module inherit its gate.
+    fn gated_modules(
+        &self,
+        files: &[PathBuf],
+    ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
+        let roots = self.target_roots();
+        let mut declarations = Vec::new();
+        for file in files {
+            let content = fs::read_to_string(file).unwrap_or_default();
+            let crate_root = roots.contains(&canonical_path(file));
+            for declaration in Self::module_declarations(file, &content, crate_root) {
+                declarations.push((file, declaration));
+            }
+        }
+
+        let features = declarations
+            .iter()
+            .map(|(parent, d)| (*parent, &d.path, d.feature.as_ref()))
+            .collect::<Vec<_>>();
+        let platforms = declarations
+            .iter()
+            .map(|(parent, d)| (*parent, &d.path, d.platform.as_ref()))
+            .collect::<Vec<_>>();
+        (
+            self.propagate_gates(&features, "feature"),
+            self.propagate_gates(&platforms, "cfg"),
+        )
+    }
+
+    /// Propagate `(parent, child, gate)` module gates down the module tree until nothing
+    /// changes
+    fn propagate_gates(
+        &self,
+        declarations: &[(&PathBuf, &PathBuf, Option<&String>)],
+        kind: &str,
+    ) -> HashMap<PathBuf, String> {
+        let mut gates: HashMap<PathBuf, String> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (parent, child, gate) in declarations {
+                if gates.contains_key(*child) {
+                    continue;
+                }
+                if let Some(gate) = gate.cloned().or_else(|| gates.get(*parent).cloned()) {
+                    if self.debug {
+                        println!("Module {:?} is gated on {} {}", child, kind, gate);
+                    }
+                    gates.insert((*child).clone(), gate);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return gates;
+            }
+        }
+    }
+
+    /// The predicate of a single-predicate platform `cfg` attribute, such as `windows` for
+    /// `#[cfg(windows)]` or `target_os = "linux"` for `#[cfg(target_os="linux")]`.
+    /// Compound predicates (`all(...)`, `not(...)`) are not platform gates here.
+    fn platform_cfg(platform_regex: &Regex, line: &str) -> Option<String> {
+        let cap = platform_regex.captures(line.trim())?;
+        match (cap.get(1), cap.get(2), cap.get(3)) {
+            (Some(family), _, _) => Some(family.as_str().to_string()),
+            (None, Some(key), Some(value)) => {
+                Some(format!("{} = \"{}\"", key.as_str(), value.as_str()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Find `mod foo;` declarations in a file and resolve them to `foo.rs` or `foo/mod.rs`,
+    /// together with the gates of preceding `#[cfg(feature = "...")]` and platform `cfg`
+    /// attributes
+    fn module_declarations(
+        file_path: &Path,
+        content: &str,
+        crate_root: bool,
+    ) -> Vec<ModuleDeclaration> {
+        let cfg_regex = Regex::new(r#"^#\[\s*cfg\s*\(\s*feature\s*=\s*"([^"]+)"\s*\)\s*\]$"#)
+            .expect("cfg regex is valid");
+        let mod_regex = Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?mod\s+([a-zA-Z_][a-zA-Z0-9_]*)\s*;")
+            .expect("mod regex is valid");
+        let platform_regex = Regex::new(PLATFORM_CFG_PATTERN).expect("platform cfg regex is valid");
+
+        // Children of a crate root (`lib.rs`, `main.rs` or a target's custom `path`) and of
+        // `mod.rs` live next to the file, others in a directory named after the file
+        let parent = file_path.parent().unwrap_or_else(|| Path::new(""));
+        let module_dir = match file_path.file_name().and_then(|f| f.to_str()) {
+            Some("lib.rs" | "main.rs" | "mod.rs") => parent.to_path_buf(),
+            _ if crate_root => parent.to_path_buf(),
+            _ => parent.join(file_path.file_stem().unwrap_or_default()),
+        };
+
+        let mut declarations = Vec::new();
+        let mut pending_feature = None;
+        let mut pending_platform = None;
+        for line in content.lines().map(str::trim) {
+            if let Some(cap) = cfg_regex.captures(line) {
+                pending_feature = Some(cap[1].to_string());
+            } else if let Some(platform) = Self::platform_cfg(&platform_regex, line) {
+                pending_platform = Some(platform);
+            } else if line.starts_with("#[") || line.is_empty() {
+                // Other attributes don't break the association
+            } else if let Some(cap) = mod_regex.captures(line) {
+                let name = &cap[1];
+                let feature = pending_feature.take();
+                let platform = pending_platform.take();
+                let candidates = [
+                    module_dir.join(format!("{}.rs", name)),
+                    module_dir.join(name).join("mod.rs"),
+                ];
+                if let Some(path) = candidates.into_iter().find(|c| c.is_file()) {
+                    declarations.push(ModuleDeclaration {
+                        path,
+                        feature,
+                        platform,
+                    });
+                }
+            } else {
+                pending_feature = None;
+                pending_platform = None;
+            }
+        }
+        declarations
+    }
+
+    /// Whether a path lies in a directory excluded by the config
+    fn is_excluded(&self, path: &Path) -> bool {
+        let excluded = path
+            .strip_prefix(&self.project_root)
+            .is_ok_and(|relative| self.config.is_excluded_dir(relative));
+        if excluded && self.debug {
+            println!("Skipping excluded path: {:?}", path);
+        }
+        excluded
+    }
+
+    /// Check if this is a test file (in a tests/ directory or ends with _test.rs).
+    ///
+    /// Only the part of the path below the project root is considered, and both `/` and
+    /// `\` count as separators so Windows paths are classified the same way.
+    fn is_test_file(&self, path: &Path) -> bool {
+        let relative = slash_path(path.strip_prefix(&self.project_root).unwrap_or(path));
+        let mut segments = relative.split('/');
+        let file_name = segments.next_back().unwrap_or_default();
+        segments.any(|segment| segment == "tests") || file_name.ends_with("_test.rs")
+    }
+
+    /// Resolve the files referenced by `#[path = "..."]` attributes, relative to the
+    /// directory of the including file
+    fn path_attribute_targets(file_path: &Path, content: &str) -> Vec<PathBuf> {
+        let path_regex = Regex::new(r#"#\[\s*path\s*=\s*"([^"]+)"\s*\]"#)
+            .expect("path attribute regex is valid");
+        let base_dir = file_path.parent().unwrap_or_else(|| Path::new(""));
+        path_regex
+            .captures_iter(content)
+            .map(|cap| base_dir.join(&cap[1]))
+            .collect()
+    }
+
+    /// Resolve the files pulled in with `include!("...")`, relative to the directory of the
+    /// including file. `include_str!`/`include_bytes!` aren't Rust, and paths built with
+    /// `concat!(env!("OUT_DIR"), ...)` only exist after a build, so both are left out.
+    fn include_macro_targets(file_path: &Path, content: &str) -> Vec<PathBuf> {
+        let include_regex = Regex::new(r#"\binclude!\s*[(\[{]\s*"([^"]+)"\s*[)\]}]"#)
+            .expect("include macro regex is valid");
+        let base_dir = file_path.parent().unwrap_or_else(|| Path::new(""));
+        include_regex
+            .captures_iter(content)
+            .map(|cap| base_dir.join(&cap[1]))
+            .collect()
+    }
+
+    /// Compute a fingerprint of everything the analysis depends on: the import-relevant
+    /// lines (`use`, `extern crate` and path references) of every source file, including
+    /// files followed through `include!` and `#[path]`, which declared crates each file
+    /// mentions by name, the manifest, the workspace and member manifests, and the
+    /// effective config (CLI overrides included). If the fingerprint matches the previous
+    /// run, re-resolution can be skipped.
+    pub fn import_fingerprint(&self) -> Result<u64> {
+        let mut hasher = DefaultHasher::new();
+
+        serde_json::to_string(&self.config)?.hash(&mut hasher);
+        let manifest = fs::read_to_string(self.project_root.join("Cargo.toml")).ok();
+        if let Some(manifest) = &manifest {
+            manifest.hash(&mut hasher);
+        }
+        // Declared crates are kept while the code names them anywhere (`find_mentions`),
+        // e.g. only through a macro call
+        let mention_patterns = manifest
+            .and_then(|manifest| manifest.parse::<DocumentMut>().ok())
+            .map(|doc| Self::declared_import_names(&doc))
+            .unwrap_or_default()
+            .iter()
+            .map(|name| Regex::new(&format!(r"\b{}\b", regex::escape(name))))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(workspace) = self.workspace_manifest() {
+            workspace.to_string().hash(&mut hasher);
+        }
+        // Members decide which imports are local packages; read from the manifests
+        // rather than `cargo metadata`, to keep the fast path fast
+        if let Ok(workspace) = metadata::from_manifests(&self.project_root) {
+            for member in &workspace.members {
+                member.manifest_path.hash(&mut hasher);
+                fs::read_to_string(&member.manifest_path)
+                    .unwrap_or_default()
+                    .hash(&mut hasher);
+            }
+        }
+
+        for path in self.collect_rust_files()? {
+            let content = fs::read_to_string(&path)?;
+            path.strip_prefix(&self.project_root)
+                .unwrap_or(&path)
+                .hash(&mut hasher);
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| {
+                    Self::strip_visibility(line).starts_with("use ")
+                        || line.starts_with("#[cfg")
+                        || line.contains("mod ")
+                        || line.contains("extern crate")
+                        || line.contains("::")
+                })
+                .for_each(|line| line.hash(&mut hasher));
+            let code = self.remove_comments(&content);
+            for pattern in &mention_patterns {
+                pattern.is_match(&code).hash(&mut hasher);
+            }
+        }
+
+        Ok(hasher.finish())
+    }
+
+    /// Import names of the crates the manifest declares in any dependency table,
+    /// platform-specific ones included
+    fn declared_import_names(doc: &DocumentMut) -> BTreeSet<String> {
+        let platforms = doc
+            .get("target")
+            .and_then(Item::as_table_like)
+            .into_iter()
+            .flat_map(|targets| targets.iter().map(|(_, platform)| platform));
+        std::iter::once(doc.as_item())
+            .chain(platforms)
+            .flat_map(|item| {
+                ["dependencies", "dev-dependencies", "build-dependencies"]
+                    .into_iter()
+                    .filter_map(move |kind| item.get(kind))
+            })
+            .filter_map(Item::as_table_like)
+            .flat_map(|table| table.iter().map(|(name, _)| import_name(name)))
+            .collect()
+    }
+
+    /// Load existing dependency information from Cargo.toml.
+    ///
+    /// Returns the (normalized) names of crates redirected by `[patch]` or `[replace]`.
     fn load_existing_dependencies(
         &self,
         crate_refs: &mut HashMap<String, CrateReference>,
-    ) -> Result<()> {
+    ) -> Result<HashSet<String>> {
         let cargo_toml_path = self.project_root.join("Cargo.toml");
+        self.broken_paths
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
         if !cargo_toml_path.exists() {
-            return Ok(());
+            return Ok(HashSet::new());
         }
 
         if self.debug {
@@ -150,16 +830,12 @@ impl DependencyAnalyzer {
             .parse::<DocumentMut>()
             .with_context(|| format!("Failed to parse Cargo.toml at {:?}", cargo_toml_path))?;
 
-        // Check package publish settings
-        let publish = if let Some(package) = doc.get("package") {
-            if let Some(publish_value) = package.get("publish") {
-                publish_value.as_bool()
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        *self.broken_paths.lock().unwrap_or_else(|e| e.into_inner()) =
+            Self::find_broken_paths(&self.project_root, &doc);
+
+        // Check package publish settings (possibly inherited from the workspace)
+        let workspace_doc = self.workspace_manifest();
+        let publish = package_publish(&doc, workspace_doc.as_ref());
 
         if self.debug {
             println!("Package publish setting: {:?}", publish);
@@ -209,6 +885,9 @@ impl DependencyAnalyzer {
 
                                 crate_refs.insert(crate_name, crate_ref);
                             }
+                        } else {
+                            let crate_ref = Self::declared_dependency(&crate_name, value);
+                            crate_refs.insert(crate_name, crate_ref);
                         }
                     }
                     // Path dependency (inline table format)
@@ -241,14 +920,18 @@ impl DependencyAnalyzer {
 
                                 crate_refs.insert(crate_name, crate_ref);
                             }
+                        } else {
+                            let crate_ref = Self::declared_dependency(&crate_name, value);
+                            crate_refs.insert(crate_name, crate_ref);
                         }
                     }
-                    // Regular dependency
+                    // Regular dependency (`foo = "1"`)
                     _ => {
-                        // Regular dependencies are detected during analysis, so nothing to do here
                         if self.debug {
-                            println!("Skipping regular dependency: {}", crate_name);
+                            println!("Found registry dependency: {}", crate_name);
                         }
+                        let crate_ref = Self::declared_dependency(&crate_name, value);
+                        crate_refs.insert(crate_name, crate_ref);
                     }
                 }
             }
@@ -256,7 +939,172 @@ impl DependencyAnalyzer {
             println!("No dependencies section found in Cargo.toml");
         }
 
-        Ok(())
+        let patched = patched_crates(&doc);
+        if self.debug && !patched.is_empty() {
+            println!("Patched crates: {:?}", patched);
+        }
+
+        Ok(patched)
+    }
+
+    /// Map imports of workspace members to their packages.
+    ///
+    /// The project's own package (e.g. `use my_crate::...` in `tests/`) is dropped, and
+    /// sibling members become path dependencies keyed by their package name. Imports are
+    /// matched by library target name, so a `[lib] name = "engine"` rename is followed.
+    fn resolve_workspace_members(&self, crate_refs: &mut HashMap<String, CrateReference>) {
+        let workspace = match metadata::load(&self.project_root) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                if self.debug {
+                    println!("Warning: failed to resolve workspace members: {}", e);
+                }
+                self.broken_members
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                return;
+            }
+        };
+        if self.debug {
+            for (manifest, error) in &workspace.broken_members {
+                println!(
+                    "Warning: skipping workspace member {:?}: {}",
+                    manifest, error
+                );
+            }
+        }
+        *self
+            .broken_members
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = workspace.broken_members;
+        let own_dir = fs::canonicalize(&self.project_root).unwrap_or(self.project_root.clone());
+
+        for member in &workspace.members {
+            let member_dir = fs::canonicalize(member.dir()).unwrap_or(member.dir().to_path_buf());
+            let is_own_package = member_dir == own_dir;
+
+            // Declared entries are keyed by package name, imports by target name; binaries
+            // can't be imported from other packages, so only the package itself uses them
+            let mut names = vec![normalize_package_name(&member.name)];
+            if is_own_package {
+                names.extend(member.target_names());
+            } else {
+                names.push(member.import_name());
+            }
+            let keys = crate_refs
+                .keys()
+                .filter(|name| names.contains(&normalize_package_name(name)))
+                .cloned()
+                .collect::<Vec<_>>();
+            if keys.is_empty() {
+                continue;
+            }
+
+            if is_own_package {
+                if self.debug {
+                    println!("Ignoring imports of the package itself: {}", member.name);
+                }
+                for key in keys {
+                    crate_refs.remove(&key);
+                }
+                continue;
+            }
+
+            if self.debug {
+                println!("{:?} refer to workspace member {}", keys, member.name);
+            }
+            let mut merged = CrateReference::with_path(
+                member.name.clone(),
+                slash_path(&relative_path(&own_dir, &member_dir)),
+            );
+            merged.is_dev_dependency = true;
+            for key in keys {
+                let Some(crate_ref) = crate_refs.remove(&key) else {
+                    continue;
+                };
+                // Keep a path already declared in Cargo.toml
+                if crate_ref.is_path_dependency {
+                    merged.path = crate_ref.path;
+                    merged.publish = crate_ref.publish;
+                }
+                // Declared entries (no usages yet) don't decide dev-ness
+                if !crate_ref.used_in.is_empty() {
+                    merged.is_dev_dependency &= crate_ref.is_dev_dependency;
+                }
+                merged.used_in.extend(crate_ref.used_in);
+                merged.import_count += crate_ref.import_count;
+                merged.features.extend(crate_ref.features);
+            }
+            if merged.used_in.is_empty() {
+                merged.is_dev_dependency = false;
+            }
+            crate_refs.insert(member.name.clone(), merged);
+        }
+    }
+
+    /// A declared registry dependency, keeping its version requirement, features and
+    /// `registry`/`registry-index`.
+    ///
+    /// Such entries carry no usages: `analyze_dependencies` only keeps them for crates the
+    /// code actually uses.
+    fn declared_dependency(name: &str, item: &Item) -> CrateReference {
+        let mut crate_ref = CrateReference::new(name.to_string());
+        crate_ref.version = item
+            .as_str()
+            .or_else(|| item.get("version").and_then(|v| v.as_str()))
+            .map(|version| version.to_string());
+        if let Some(features) = item.get("features").and_then(|f| f.as_array()) {
+            for feature in features.iter().filter_map(|f| f.as_str()) {
+                crate_ref.add_feature(feature.to_string());
+            }
+        }
+        let string_key = |key: &str| item.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        crate_ref.registry = string_key("registry");
+        crate_ref.registry_index = string_key("registry-index");
+        crate_ref
+    }
+
+    /// Names (normalized) of the crates declared in the dependency tables, if the package
+    /// uses edition 2015. A manifest without `edition` is analyzed with modern path rules.
+    fn edition_2015_crates(&self) -> Option<HashSet<String>> {
+        let doc = fs::read_to_string(self.project_root.join("Cargo.toml"))
+            .ok()?
+            .parse::<DocumentMut>()
+            .ok()?;
+        if package_edition(&doc, self.workspace_manifest().as_ref()).as_deref() != Some("2015") {
+            return None;
+        }
+        Some(Self::declared_in(&doc))
+    }
+
+    /// Names (normalized) of the crates declared in the package's dependency tables, if
+    /// Cargo.toml can be read
+    fn declared_crates(&self) -> Option<HashSet<String>> {
+        let doc = fs::read_to_string(self.project_root.join("Cargo.toml"))
+            .ok()?
+            .parse::<DocumentMut>()
+            .ok()?;
+        Some(Self::declared_in(&doc))
+    }
+
+    fn declared_in(doc: &DocumentMut) -> HashSet<String> {
+        ["dependencies", "dev-dependencies", "build-dependencies"]
+            .iter()
+            .filter_map(|table| doc.get(table).and_then(|t| t.as_table_like()))
+            .flat_map(|table| table.iter().map(|(name, _)| normalize_package_name(name)))
+            .collect()
+    }
+
+    /// Parse the manifest of the enclosing workspace root, if there is one
+    fn workspace_manifest(&self) -> Option<DocumentMut> {
+        self.project_root.ancestors().find_map(|dir| {
+            let doc = fs::read_to_string(dir.join("Cargo.toml"))
+                .ok()?
+                .parse::<DocumentMut>()
+                .ok()?;
+            doc.get("workspace").is_some().then_some(doc)
+        })
     }
 
     fn analyze_file(&self, ctx: FileAnalysisContext) -> Result<()> {
@@ -265,13 +1113,40 @@ impl DependencyAnalyzer {
             file_path,
             extern_regex,
             crate_refs,
+            externs,
         } = ctx;
 
+        // Collect this file's references separately so `extern crate foo as bar;` aliases
+        // can be resolved to the real crate name before merging. References are grouped by
+        // the item they are in: a crate used only inside `#[cfg(test)]` items is dev-only,
+        // and one used only under a platform `cfg` (`#[cfg(windows)]`) platform-specific.
+        let mut grouped_refs = BTreeMap::<(bool, Option<String>), HashMap<_, _>>::new();
+        let mut aliases = HashMap::new();
+
         let lines: Vec<&str> = content.lines().collect();
+        let test_lines = Self::cfg_item_lines(&lines, |line| {
+            line.trim_start()
+                .starts_with("#[cfg(test)]")
+                .then(String::new)
+        })
+        .into_iter()
+        .map(|gate| gate.is_some())
+        .collect::<Vec<_>>();
+        let platform_regex = Regex::new(PLATFORM_CFG_PATTERN)?;
+        let platform_lines =
+            Self::cfg_item_lines(&lines, |line| Self::platform_cfg(&platform_regex, line));
+        // Lines of `use` statements; their paths are handled here, not as direct references
+        let mut use_lines = vec![false; lines.len()];
         let mut current_line_num = 0;
 
         while current_line_num < lines.len() {
             let line = lines[current_line_num].trim();
+            let refs = grouped_refs
+                .entry((
+                    test_lines[current_line_num],
+                    platform_lines[current_line_num].clone(),
+                ))
+                .or_default();
             current_line_num += 1;
 
             if line.is_empty() {
@@ -283,124 +1158,315 @@ impl DependencyAnalyzer {
                 continue;
             }
 
-            // Process use statements
-            if line.starts_with("use") {
+            // Process use statements, including re-exports (`pub use`, `pub(crate) use`)
+            let use_line = Self::strip_visibility(line);
+            if Self::is_use_keyword(use_line) {
+                let first_line_num = current_line_num - 1;
                 // Collect multi-line use statements
-                let mut use_statement = line.to_string();
-                let mut brace_count = line.chars().filter(|&c| c == '{').count()
-                    - line.chars().filter(|&c| c == '}').count();
-
-                // Continue reading until all braces are closed
-                while brace_count > 0 && current_line_num < lines.len() {
+                let mut use_statement = use_line.to_string();
+                let brace_delta = |line: &str| {
+                    line.chars().filter(|&c| c == '{').count() as isize
+                        - line.chars().filter(|&c| c == '}').count() as isize
+                };
+                let mut brace_count = brace_delta(line);
+                let mut terminated = line.contains(';');
+
+                // Continue reading until all braces are closed and the statement has ended,
+                // so `use\n    tokio::{...};` and `use tokio\n    ::sync::Mutex;` are whole
+                while (brace_count > 0 || !terminated) && current_line_num < lines.len() {
                     let next_line = lines[current_line_num].trim();
                     current_line_num += 1;
                     use_statement.push('\n');
                     use_statement.push_str(next_line);
 
-                    brace_count += next_line.chars().filter(|&c| c == '{').count();
-                    brace_count -= next_line.chars().filter(|&c| c == '}').count();
+                    brace_count += brace_delta(next_line);
+                    terminated = next_line.contains(';');
+                }
+                use_lines[first_line_num..current_line_num].fill(true);
+
+                // Extract crate names from use statement; each crate counts one import
+                // however many of its items the statement names
+                let mut statement_refs = HashMap::new();
+                self.extract_crates_from_use(&use_statement, file_path, &mut statement_refs)?;
+                for (name, mut statement_ref) in statement_refs {
+                    statement_ref.import_count = 1;
+                    refs.entry(name.clone())
+                        .or_insert_with(|| CrateReference::new(name))
+                        .merge_usages(statement_ref);
                 }
-
-                // Extract crate names from use statement
-                self.extract_crates_from_use(&use_statement, crate_refs)?;
                 continue;
             }
 
             // Process extern crate statements
             if let Some(cap) = extern_regex.captures(line) {
                 let crate_name = cap[1].to_string();
-                if !is_std_crate(&crate_name) {
-                    crate_refs
+                externs.insert(crate_name.clone(), crate_name.clone());
+                if let Some(alias) = cap.get(2) {
+                    aliases.insert(alias.as_str().to_string(), crate_name.clone());
+                    externs.insert(alias.as_str().to_string(), crate_name.clone());
+                }
+                if crate_name != "self" && !is_std_crate(&crate_name) {
+                    let crate_ref = refs
                         .entry(crate_name.clone())
-                        .or_insert_with(|| CrateReference::new(crate_name))
-                        .add_usage(file_path.clone());
+                        .or_insert_with(|| CrateReference::new(crate_name));
+                    crate_ref.add_usage(file_path.clone());
+                    crate_ref.import_count += 1;
                 }
             }
         }
 
-        // Scan for direct references (e.g., serde_json::Value)
-        self.scan_for_direct_references(&content, crate_refs)?;
-
-        Ok(())
-    }
-
-    // Method to extract crate names from use statements
-    fn extract_crates_from_use(
-        &self,
-        use_statement: &str,
-        crate_refs: &mut HashMap<String, CrateReference>,
-    ) -> Result<()> {
-        // Remove comments
-        let clean_use = self.remove_comments(use_statement);
-
-        if self.debug {
-            println!("Cleaned use statement: {}", clean_use);
+        // Scan for direct references (e.g., serde_json::Value) in each group of items; the
+        // inner paths of a grouped import (`runtime::Runtime` in `use tokio::{...}`) are not
+        // crates
+        let groups = test_lines
+            .iter()
+            .copied()
+            .zip(platform_lines.iter().cloned())
+            .collect::<BTreeSet<_>>();
+        for group in groups {
+            let selected = lines
+                .iter()
+                .enumerate()
+                .map(|(index, line)| {
+                    let in_group = test_lines[index] == group.0 && platform_lines[index] == group.1;
+                    if in_group && !use_lines[index] {
+                        *line
+                    } else {
+                        ""
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let refs = grouped_refs.entry(group).or_default();
+            self.scan_for_direct_references(&selected, file_path, refs)?;
         }
 
-        // Remove "use " prefix
-        let statement = clean_use.trim_start_matches("use").trim();
-
-        // Simple use statement (e.g., use serde::Serialize;)
-        if !statement.starts_with('{') && statement.contains("::") {
-            let parts: Vec<&str> = statement.split("::").collect();
-            if !parts.is_empty() {
-                let crate_name = parts[0].trim_end_matches(':').trim();
-                self.add_crate_if_valid(crate_name, crate_refs);
+        let names = grouped_refs
+            .into_iter()
+            .flat_map(|((test_only, platform), refs)| {
+                refs.into_iter().map(move |(name, group_ref)| {
+                    (name, group_ref.import_count, test_only, platform.clone())
+                })
+            });
+        for (name, import_count, test_only, platform) in names {
+            let crate_name = aliases.get(&name).cloned().unwrap_or(name);
+            // `extern crate self as alias;` refers to the current crate
+            if crate_name == "self" || is_std_crate(&crate_name) {
+                continue;
             }
-        }
-        // Use statement with crate name and braces (e.g., use crate_name::{...};)
-        else if !statement.starts_with('{') && statement.contains("::") && statement.contains('{')
-        {
-            let parts: Vec<&str> = statement.split("::").collect();
-            if !parts.is_empty() {
-                let crate_name = parts[0].trim();
-                self.add_crate_if_valid(crate_name, crate_refs);
+            let features = Self::infer_features(&crate_name, &content)?.features;
+            let is_new = !crate_refs.contains_key(&crate_name);
+            let crate_ref = crate_refs
+                .entry(crate_name.clone())
+                .or_insert_with(|| CrateReference::new(crate_name));
+            // Dev-only as long as every reference is inside a `#[cfg(test)]` item, and
+            // platform-specific as long as every reference is under the same platform `cfg`
+            crate_ref.is_dev_dependency = test_only && (is_new || crate_ref.is_dev_dependency);
+            if is_new || crate_ref.target_cfg == platform {
+                crate_ref.target_cfg = platform;
+            } else {
+                crate_ref.target_cfg = None;
             }
-        }
-        // Use statement with braces (e.g., use {crate1, crate2::module, crate3::{...}};)
-        else if statement.starts_with('{') {
-            // Extract content inside braces
-            let content = &statement[1..statement.rfind('}').unwrap_or(statement.len())];
-
-            // Process each item separated by commas
-            for item in content.split(',') {
-                let item = item.trim();
-                if item.is_empty() {
-                    continue;
-                }
-
-                // Item contains :: (e.g., crate::module or crate::{...})
-                if item.contains("::") {
-                    let parts: Vec<&str> = item.split("::").collect();
-                    if !parts.is_empty() {
-                        let crate_name = parts[0].trim();
-                        self.add_crate_if_valid(crate_name, crate_refs);
-                    }
-                }
-                // Simple crate name (e.g., crate)
-                else {
-                    let crate_name = item.trim();
-                    self.add_crate_if_valid(crate_name, crate_refs);
-                }
+            crate_ref.add_usage(file_path.clone());
+            crate_ref.import_count += import_count;
+            for feature in features {
+                crate_ref.add_feature(feature);
             }
         }
-        // Simple use statement (e.g., use tokio;)
-        else {
-            let crate_name = statement.trim_end_matches(';').trim();
-            self.add_crate_if_valid(crate_name, crate_refs);
-        }
 
         Ok(())
     }
 
+    /// Per line, the gate of the `cfg` item it belongs to, for the attributes `gate`
+    /// recognizes: `#[cfg(test)]` on the inline `mod tests { ... }`, or a platform `cfg` on
+    /// a `use` statement. The item ends where its braces balance again, or at its `;` if it
+    /// has no body; items nested in a gated item take the outer gate.
+    fn cfg_item_lines(
+        lines: &[&str],
+        gate: impl Fn(&str) -> Option<String>,
+    ) -> Vec<Option<String>> {
+        let mut gated = vec![None; lines.len()];
+        let mut index = 0;
+        while index < lines.len() {
+            let Some(current) = gate(lines[index]) else {
+                index += 1;
+                continue;
+            };
+            let mut depth = 0usize;
+            let mut opened = false;
+            while index < lines.len() {
+                let line = lines[index];
+                gated[index] = Some(current.clone());
+                index += 1;
+                let opens = line.matches('{').count();
+                depth = (depth + opens).saturating_sub(line.matches('}').count());
+                opened |= opens > 0;
+                if (opened && depth == 0) || (!opened && line.trim_end().ends_with(';')) {
+                    break;
+                }
+            }
+        }
+        gated
+    }
+
+    /// Whether a line starts with the `use` keyword (not an identifier such as `user`)
+    fn is_use_keyword(line: &str) -> bool {
+        line.strip_prefix("use").is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '{' || c == ':')
+        })
+    }
+
+    /// Strip a leading visibility (`pub`, `pub(crate)`, `pub(in path)`, ...) from a line
+    fn strip_visibility(line: &str) -> &str {
+        let Some(rest) = line.strip_prefix("pub") else {
+            return line;
+        };
+        let trimmed = rest.trim_start();
+        if let Some(restricted) = trimmed.strip_prefix('(') {
+            return match restricted.find(')') {
+                Some(end) => restricted[end + 1..].trim_start(),
+                None => line,
+            };
+        }
+        // `pub` must be followed by whitespace (`pubsub::...` is not a visibility)
+        if trimmed.len() < rest.len() {
+            trimmed
+        } else {
+            line
+        }
+    }
+
+    /// Features of `crate_name` implied by the paths `content` uses from it, e.g.
+    /// `#[tokio::main]` or `use tokio::{fs, sync::mpsc};`
+    fn infer_features(crate_name: &str, content: &str) -> Result<FeatureUsage> {
+        if !has_feature_hints(crate_name) {
+            return Ok(FeatureUsage::default());
+        }
+        let pattern = Regex::new(&format!(
+            r"\b{}\s*::\s*(\{{[^}}]*\}}|\*|[A-Za-z_][A-Za-z0-9_]*)",
+            regex::escape(crate_name)
+        ))?;
+        // Paths through an alias (`use tokio as rt;`) can't be attributed
+        let alias = Regex::new(&format!(r"\b{}\s+as\b", regex::escape(crate_name)))?;
+
+        let mut usage = FeatureUsage {
+            certain: !alias.is_match(content),
+            ..Default::default()
+        };
+        for cap in pattern.captures_iter(content) {
+            let items = cap[1].trim_start_matches('{').trim_end_matches('}');
+            for item in items.split(',') {
+                let item = item.split("::").next().unwrap_or_default().trim();
+                if item.is_empty() {
+                    continue;
+                }
+                usage.certain &= has_item_hint(crate_name, item);
+                usage.features.extend(
+                    implied_features(crate_name, item)
+                        .iter()
+                        .map(|feature| feature.to_string()),
+                );
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Features the analyzed code needs from each crate with feature hints
+    /// (`--prune-features`)
+    pub fn feature_usage(&self) -> Result<HashMap<String, FeatureUsage>> {
+        let mut usage = hinted_crates()
+            .into_iter()
+            .map(|name| {
+                let usage = FeatureUsage {
+                    certain: true,
+                    ..Default::default()
+                };
+                (name.to_string(), usage)
+            })
+            .collect::<HashMap<_, _>>();
+        for file_path in self.collect_rust_files()? {
+            let content = fs::read_to_string(&file_path)?;
+            for (name, crate_usage) in usage.iter_mut() {
+                crate_usage.merge(Self::infer_features(name, &content)?);
+            }
+        }
+        Ok(usage)
+    }
+
+    // Method to extract crate names from use statements
+    fn extract_crates_from_use(
+        &self,
+        use_statement: &str,
+        file_path: &Path,
+        crate_refs: &mut HashMap<String, CrateReference>,
+    ) -> Result<()> {
+        // Remove comments
+        let clean_use = self.remove_comments(use_statement);
+
+        if self.debug {
+            println!("Cleaned use statement: {}", clean_use);
+        }
+
+        // Remove "use " prefix, a leading `::` (2015-style absolute path) and the `;`
+        let statement = clean_use
+            .trim_start_matches("use")
+            .trim()
+            .trim_start_matches("::")
+            .trim_end_matches(';')
+            .trim();
+
+        // Use statement with braces (e.g., use {crate1, crate2::module, crate3::{...}};)
+        if let Some(group) = statement.strip_prefix('{') {
+            let content = &group[..group.rfind('}').unwrap_or(group.len())];
+
+            // Process each top-level item; commas inside nested braces belong to the item
+            for item in Self::split_top_level(content) {
+                let item = item.trim().trim_start_matches("::");
+                if item.is_empty() {
+                    continue;
+                }
+                let crate_name = item.split("::").next().unwrap_or_default();
+                self.add_crate_if_valid(crate_name, file_path, crate_refs);
+            }
+        }
+        // Paths, with or without braces (e.g., use serde::Serialize; use foo::{ Bar, };),
+        // and bare crates (e.g., use tokio;) are credited to their first segment
+        else {
+            let crate_name = statement.split("::").next().unwrap_or_default();
+            self.add_crate_if_valid(crate_name, file_path, crate_refs);
+        }
+
+        Ok(())
+    }
+
+    /// Split a brace group's contents on commas that are not nested in inner braces
+    fn split_top_level(content: &str) -> Vec<&str> {
+        let mut items = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in content.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    items.push(&content[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        items.push(&content[start..]);
+        items
+    }
+
     // Helper method to add crate if it's valid
     fn add_crate_if_valid(
         &self,
         crate_name: &str,
+        file_path: &Path,
         crate_refs: &mut HashMap<String, CrateReference>,
     ) {
-        // Remove extra characters from crate name
-        let clean_name = crate_name.trim().trim_end_matches(['}', '\n', '\r', ':']);
+        let clean_name = Self::normalize_crate_name(crate_name);
 
         if !clean_name.is_empty()
             && !is_std_crate(clean_name)
@@ -418,10 +1484,31 @@ impl DependencyAnalyzer {
             crate_refs
                 .entry(original_name.clone())
                 .or_insert_with(|| CrateReference::new(original_name))
-                .add_usage(PathBuf::from(""));
+                .add_usage(file_path.to_path_buf());
         }
     }
 
+    /// Whether a path's first segment starts with an uppercase letter. Crate names are
+    /// lowercase by convention, so such a segment is most likely a type or a local module.
+    fn is_capitalized(name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_uppercase())
+    }
+
+    /// Normalize a raw crate segment taken from a `use` statement or path.
+    ///
+    /// Strips surrounding whitespace and stray punctuation (`;`, `,`, braces, `:`, a glob
+    /// `*`) and drops anything after the first whitespace (e.g. an `as` alias), so `serde ;`,
+    /// `serde;` and `serde as s` all yield `serde`, and a bare `*` yields nothing.
+    fn normalize_crate_name(raw: &str) -> &str {
+        const PUNCTUATION: [char; 6] = [';', ',', '{', '}', ':', '*'];
+        let trimmed = raw.trim_matches(|c: char| c.is_whitespace() || PUNCTUATION.contains(&c));
+        trimmed
+            .split(char::is_whitespace)
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(PUNCTUATION)
+    }
+
     // Helper method to remove comments
     fn remove_comments(&self, code: &str) -> String {
         let mut clean_code = String::new();
@@ -469,22 +1556,54 @@ impl DependencyAnalyzer {
         clean_code
     }
 
+    /// Files whose code (comments removed) names one of `names` as a whole word, by name
+    fn find_mentions(
+        &self,
+        files: &[PathBuf],
+        names: &[String],
+    ) -> Result<HashMap<String, HashSet<PathBuf>>> {
+        let mut mentions = HashMap::<String, HashSet<PathBuf>>::new();
+        if names.is_empty() {
+            return Ok(mentions);
+        }
+        let patterns = names
+            .iter()
+            .map(|name| Regex::new(&format!(r"\b{}\b", regex::escape(name))))
+            .collect::<Result<Vec<_>, _>>()?;
+        for file_path in files {
+            let content = self.remove_comments(&fs::read_to_string(file_path)?);
+            for (name, pattern) in names.iter().zip(&patterns) {
+                if pattern.is_match(&content) {
+                    mentions
+                        .entry(name.clone())
+                        .or_default()
+                        .insert(file_path.clone());
+                }
+            }
+        }
+        Ok(mentions)
+    }
+
     // Method to detect direct references in fully qualified paths
     fn scan_for_direct_references(
         &self,
         content: &str,
+        file_path: &Path,
         crate_refs: &mut HashMap<String, CrateReference>,
     ) -> Result<()> {
         // Use content with comments removed
         let clean_content = self.remove_comments(content);
 
-        // Pattern for fully qualified paths (e.g., serde_json::value::Value)
-        let direct_ref_regex = Regex::new(r"([a-zA-Z_][a-zA-Z0-9_-]*)::([a-zA-Z0-9_:]+)")?;
+        // Pattern for fully qualified paths (e.g., serde_json::value::Value), including the
+        // absolute form (::serde::Serialize) common in macro bodies. The first segment must
+        // not be preceded by `$`, so macro metavariables like `$crate` or `$t` are ignored.
+        let direct_ref_regex =
+            Regex::new(r"(?:^|[^a-zA-Z0-9_$:])(?:::)?([a-zA-Z_][a-zA-Z0-9_-]*)::([a-zA-Z0-9_:]+)")?;
 
         for cap in direct_ref_regex.captures_iter(&clean_content) {
             let potential_crate = &cap[1];
             if !is_std_crate(potential_crate) {
-                self.add_crate_if_valid(potential_crate, crate_refs);
+                self.add_crate_if_valid(potential_crate, file_path, crate_refs);
             }
         }
 
@@ -492,11 +1611,26 @@ impl DependencyAnalyzer {
     }
 }
 
+/// `path` with symlinks and `..` resolved, or as given if it doesn't exist
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// A `mod foo;` declaration resolved to its file, with the gates of its `cfg` attributes
+struct ModuleDeclaration {
+    path: PathBuf,
+    feature: Option<String>,
+    platform: Option<String>,
+}
+
 struct FileAnalysisContext<'a> {
     content: String,
     file_path: &'a PathBuf,
     extern_regex: &'a Regex,
     crate_refs: &'a mut HashMap<String, CrateReference>,
+    /// Names bound by `extern crate` in the file, mapped to the crate they refer to
+    /// (`bar` -> `foo` for `extern crate foo as bar;`, `foo` -> `foo` without an alias)
+    externs: &'a mut HashMap<String, String>,
 }
 
 #[cfg(test)]
@@ -583,6 +1717,401 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_missing_path_dependency_is_flagged() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let app = temp_dir.path().join("app");
+        fs::create_dir_all(app.join("src"))?;
+        fs::write(
+            app.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nmoved = { path = \"../moved\" }\n",
+        )?;
+        fs::write(app.join("src/main.rs"), "use moved::run;\n")?;
+
+        let analyzer = DependencyAnalyzer::new(app);
+        analyzer.analyze_dependencies()?;
+        let broken = analyzer.broken_paths();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].name, "moved");
+        assert_eq!(broken[0].path, "../moved");
+        assert_eq!(broken[0].problem, "directory does not exist");
+
+        fs::create_dir_all(temp_dir.path().join("moved"))?;
+        fs::write(
+            temp_dir.path().join("moved/Cargo.toml"),
+            "[package]\nname = \"moved\"\nversion = \"0.1.0\"\n",
+        )?;
+        analyzer.analyze_dependencies()?;
+        assert!(analyzer.broken_paths().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_patched_crates_are_marked() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"
+[package]
+name = "test-package"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde_json = "1.0"
+
+[patch.crates-io]
+serde-json = { git = "https://github.com/serde-rs/json" }
+"#,
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "use serde_json::Value;\nuse regex::Regex;\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs["serde_json"].is_patched);
+        assert!(!crate_refs["regex"].is_patched);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_gated_modules() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("backend"))?;
+        fs::write(
+            src.join("lib.rs"),
+            r#"
+use anyhow::Result;
+
+#[cfg(feature = "json")]
+mod json;
+
+#[cfg(feature = "db")]
+#[allow(dead_code)]
+pub mod backend;
+"#,
+        )?;
+        fs::write(
+            src.join("json.rs"),
+            "use serde_json::Value;\nuse anyhow::Context;\n",
+        )?;
+        fs::write(src.join("backend/mod.rs"), "mod pool;\n")?;
+        fs::write(src.join("backend/pool.rs"), "use sqlx::Pool;\n")?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        let json_gate: HashSet<String> = ["json".to_string()].into_iter().collect();
+        assert_eq!(crate_refs["serde_json"].gated_by_features, json_gate);
+        // Nested modules inherit the gate of their parent
+        assert!(crate_refs["sqlx"].gated_by_features.contains("db"));
+        // Used outside the gated module as well, so not optional
+        assert!(!crate_refs["anyhow"].is_optional());
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude_dirs_from_config() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::write(
+            temp_dir.path().join(".cargo-autodd.toml"),
+            "exclude_dirs = [\"generated\"]\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::create_dir_all(temp_dir.path().join("generated"))?;
+        fs::write(temp_dir.path().join("src/main.rs"), "use regex::Regex;\n")?;
+        fs::write(temp_dir.path().join("generated/api.rs"), "use fakecrate;\n")?;
+
+        let mut analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        analyzer.set_config(Config::load_default(temp_dir.path())?);
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        assert!(crate_refs.contains_key("regex"));
+        assert!(
+            !crate_refs.contains_key("fakecrate"),
+            "imports inside an excluded directory should be ignored"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_features() -> Result<()> {
+        let content = "use tokio::{fs, sync::mpsc};\n\n#[tokio::main]\nasync fn main() {}\n";
+        let usage = DependencyAnalyzer::infer_features("tokio", content)?;
+        assert_eq!(
+            usage.features.into_iter().collect::<Vec<_>>(),
+            ["fs", "macros", "rt-multi-thread", "sync"]
+        );
+        assert!(usage.certain);
+
+        // Items without a hint, globs and aliases leave the needed features unknown
+        for content in [
+            "use tokio::io;",
+            "use tokio::*;",
+            "use tokio as rt;\nfn f() { rt::spawn(async {}); }",
+        ] {
+            let usage = DependencyAnalyzer::infer_features("tokio", content)?;
+            assert!(usage.features.is_empty(), "{}", content);
+            assert!(!usage.certain, "{}", content);
+        }
+        assert!(!DependencyAnalyzer::infer_features("tokio", "use tokio::{net, io};")?.certain);
+        assert!(
+            DependencyAnalyzer::infer_features("serde", "use serde::Serialize;")?
+                .features
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_usage_across_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "#[tokio::main]\nasync fn main() { net::serve().await; }\nmod net;\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("src/net.rs"),
+            "use tokio::net::TcpListener;\npub async fn serve() {}\n",
+        )?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+
+        let usage = analyzer.feature_usage()?;
+        assert!(usage["tokio"].certain);
+        assert_eq!(
+            usage["tokio"].features.iter().collect::<Vec<_>>(),
+            ["macros", "net", "rt-multi-thread"]
+        );
+
+        fs::write(
+            temp_dir.path().join("src/io.rs"),
+            "use tokio::io::AsyncReadExt;\n",
+        )?;
+        assert!(!analyzer.feature_usage()?["tokio"].certain);
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_members_resolved_as_path_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )?;
+        for (name, code) in [
+            ("core", "pub fn run() {}\n"),
+            ("cli", "fn main() {\n    demo_core::run();\n}\n"),
+        ] {
+            let dir = root.join("crates").join(name);
+            fs::create_dir_all(dir.join("src"))?;
+            fs::create_dir_all(dir.join("tests"))?;
+            fs::write(
+                dir.join("Cargo.toml"),
+                format!("[package]\nname = \"demo-{}\"\nversion = \"0.1.0\"\n", name),
+            )?;
+            let file = if name == "cli" { "main.rs" } else { "lib.rs" };
+            fs::write(dir.join("src").join(file), code)?;
+        }
+        fs::write(
+            root.join("crates/cli/tests/it.rs"),
+            "use demo_cli::Args;\nuse demo_core::run;\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(root.join("crates/cli"));
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        let core = &crate_refs["demo-core"];
+        assert!(core.is_path_dependency);
+        assert_eq!(core.path.as_deref(), Some("../core"));
+        assert!(!core.is_dev_dependency);
+        assert!(!crate_refs.contains_key("demo_core"));
+        assert!(
+            !crate_refs.keys().any(|name| name.contains("cli")),
+            "the package's own crate is not a dependency"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_renamed_lib_of_workspace_member() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"core\", \"app\"]\n",
+        )?;
+        fs::create_dir_all(root.join("core/src"))?;
+        fs::write(
+            root.join("core/Cargo.toml"),
+            "[package]\nname = \"demo-core\"\nversion = \"0.1.0\"\n\n[lib]\nname = \"engine\"\n",
+        )?;
+        fs::write(root.join("core/src/lib.rs"), "pub fn run() {}\n")?;
+        fs::create_dir_all(root.join("app/src"))?;
+        fs::write(
+            root.join("app/Cargo.toml"),
+            "[package]\nname = \"demo-app\"\nversion = \"0.1.0\"\n\n[[bin]]\nname = \"demo\"\npath = \"src/main.rs\"\n",
+        )?;
+        fs::write(
+            root.join("app/src/main.rs"),
+            "use engine::run;\nuse demo::helpers;\n\nfn main() {\n    run();\n}\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(root.join("app"));
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        let core = &crate_refs["demo-core"];
+        assert!(core.is_path_dependency);
+        assert_eq!(core.path.as_deref(), Some("../core"));
+        assert!(!crate_refs.contains_key("engine"));
+        assert!(
+            !crate_refs.contains_key("demo"),
+            "the package's own binary name is not a dependency"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_analysis_matches_serial() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::create_dir_all(root.join("src"))?;
+        fs::create_dir_all(root.join("tests"))?;
+        for i in 0..16 {
+            fs::write(
+                root.join(format!("src/m{}.rs", i)),
+                format!("use serde::Serialize;\nuse crate_{}::Item;\n", i % 4),
+            )?;
+        }
+        fs::write(root.join("tests/it.rs"), "use proptest::prelude::*;\n")?;
+
+        let analyzer = DependencyAnalyzer::new(root.to_path_buf());
+        let analyze_on = |threads| -> Result<HashMap<String, CrateReference>> {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| analyzer.analyze_dependencies())
+        };
+
+        let serial = analyze_on(1)?;
+        let parallel = analyze_on(4)?;
+        assert_eq!(serial.len(), parallel.len());
+        for (name, crate_ref) in &serial {
+            let other = &parallel[name];
+            assert_eq!(crate_ref.used_in, other.used_in, "{}", name);
+            assert_eq!(crate_ref.is_dev_dependency, other.is_dev_dependency);
+        }
+        assert_eq!(serial["serde"].usage_count(), 16);
+        assert!(serial["proptest"].is_dev_dependency);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hidden_dirs_skipped_by_default() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::create_dir_all(temp_dir.path().join(".git/hooks"))?;
+        fs::write(temp_dir.path().join("src/main.rs"), "use regex::Regex;\n")?;
+        fs::write(
+            temp_dir.path().join(".git/hooks/check.rs"),
+            "use fakecrate;\n",
+        )?;
+
+        let mut analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs.contains_key("regex"));
+        assert!(!crate_refs.contains_key("fakecrate"));
+
+        analyzer.set_config(Config {
+            include_hidden: true,
+            ..Default::default()
+        });
+        assert!(analyzer.analyze_dependencies()?.contains_key("fakecrate"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_files_skipped_by_default() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(temp_dir.path().join("src/main.rs"), "use regex::Regex;\n")?;
+        fs::write(
+            temp_dir.path().join("src/gen.rs"),
+            "// @generated by a code generator. Do not edit.\nuse fakecrate;\n",
+        )?;
+
+        let mut analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs.contains_key("regex"));
+        assert!(!crate_refs.contains_key("fakecrate"));
+
+        analyzer.set_config(Config {
+            include_generated: true,
+            ..Default::default()
+        });
+        assert!(analyzer.analyze_dependencies()?.contains_key("fakecrate"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_publish_inherited_from_workspace() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\"]\n\n[workspace.package]\npublish = false\n",
+        )?;
+        let app = temp_dir.path().join("app");
+        fs::create_dir_all(app.join("src"))?;
+        fs::write(
+            app.join("Cargo.toml"),
+            r#"
+[package]
+name = "app"
+version = "0.1.0"
+publish.workspace = true
+
+[dependencies]
+internal-crate = { path = "../internal-crate" }
+"#,
+        )?;
+        fs::write(app.join("src/main.rs"), "fn main() {}\n")?;
+
+        let analyzer = DependencyAnalyzer::new(app);
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert_eq!(crate_refs["internal-crate"].publish, Some(false));
+        Ok(())
+    }
+
     #[test]
     fn test_load_existing_dependencies() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -647,6 +2176,79 @@ fn main() {
         Ok(())
     }
 
+    #[test]
+    fn test_extern_crate_aliases() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("lib.rs");
+        let content = r#"extern crate self as me;
+extern crate serde_json as json;
+extern crate rand as other;
+
+fn parse() -> json::Value {
+    me::helpers::run();
+    json::from_str("{}").unwrap()
+}"#;
+
+        let mut crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+        analyzer.analyze_file(FileAnalysisContext {
+            content: content.to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        // Aliases resolve to the real crate and `self` is never a dependency
+        assert_eq!(names, ["rand", "serde_json"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extern_crate_aliases_apply_across_files() -> Result<()> {
+        for edition in ["2015", "2021"] {
+            let temp_dir = TempDir::new()?;
+            fs::write(
+                temp_dir.path().join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\nedition = \"{}\"\n",
+                    edition
+                ),
+            )?;
+            fs::create_dir_all(temp_dir.path().join("src"))?;
+            fs::write(
+                temp_dir.path().join("src/lib.rs"),
+                "extern crate serde_json as json;\nextern crate rand as random;\n\nmod parse;\n",
+            )?;
+            fs::write(
+                temp_dir.path().join("src/parse.rs"),
+                "use json::Value;\n\npub fn parse(input: &str) -> Value {\n    \
+                 let _ = random::random::<u8>();\n    json::from_str(input).unwrap()\n}\n",
+            )?;
+
+            let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+            let crate_refs = analyzer.analyze_dependencies()?;
+            let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, ["rand", "serde_json"], "edition {}", edition);
+            for name in names {
+                assert!(
+                    crate_refs[&name]
+                        .used_in
+                        .iter()
+                        .any(|path| path.ends_with("src/parse.rs")),
+                    "{} should be credited with its uses through the alias (edition {})",
+                    name,
+                    edition
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_analyze_file() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -661,13 +2263,14 @@ fn main() {
         println!("\nStarting analysis...\n");
 
         let mut crate_refs = HashMap::new();
-        let extern_regex = Regex::new(r"^\s*extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)")?;
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
 
         analyzer.analyze_file(FileAnalysisContext {
             content: content.trim().to_string(),
             file_path: &file_path,
             extern_regex: &extern_regex,
             crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
         })?;
 
         println!("\nAnalysis complete. Found crates:");
@@ -692,18 +2295,68 @@ fn main() {
     }
 
     #[test]
-    fn test_complex_use_statements() -> Result<()> {
+    fn test_analyze_source() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
-        let file_path = temp_dir.path().join("complex_use.rs");
+        let content = "extern crate serde_json as json;\n\
+                       use regex::Regex;\n\
+                       fn parse() -> json::Value { todo!() }\n\
+                       #[cfg(test)]\n\
+                       mod tests {\n    use proptest::prelude::*;\n}\n";
+
+        let crate_refs = analyzer.analyze_source(content, Path::new("<stdin>"))?;
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["proptest", "regex", "serde_json"]);
+        assert!(crate_refs["proptest"].is_dev_dependency);
+        assert!(!crate_refs["regex"].is_dev_dependency);
+
+        let in_tests = temp_dir.path().join("tests/it.rs");
+        let crate_refs = analyzer.analyze_source("use regex::Regex;", &in_tests)?;
+        assert!(crate_refs["regex"].is_dev_dependency);
+        Ok(())
+    }
 
-        // テスト用の複雑な use ステートメントを含むコンテンツ
-        let content = r#"
-        // Simple use statement
-        use serde::Serialize;
-        
-        // Braced use statement
-        use {
+    #[test]
+    fn test_capitalized_segments_are_not_crates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\n\n[dependencies]\nGSL = \"6\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        let content = "use SomeLocalModule::X;\n\
+                       use GSL::VectorF64;\n\
+                       use regex::Regex;\n\n\
+                       fn build() -> Shape {\n    Shape::new(X, VectorF64::new(3))\n}\n";
+        fs::write(temp_dir.path().join("src/lib.rs"), content)?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        // GSL is a (legacy) crate only because Cargo.toml declares it
+        assert_eq!(names, ["GSL", "regex"]);
+        assert_eq!(crate_refs["GSL"].usage_count(), 1);
+
+        let crate_refs = analyzer.analyze_source(content, Path::new("<stdin>"))?;
+        assert_eq!(crate_refs.keys().collect::<Vec<_>>(), ["regex"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_complex_use_statements() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("complex_use.rs");
+
+        // テスト用の複雑な use ステートメントを含むコンテンツ
+        let content = r#"
+        // Simple use statement
+        use serde::Serialize;
+        
+        // Braced use statement
+        use {
             tokio::runtime::Runtime,
             reqwest::Client,
             anyhow::Result
@@ -733,13 +2386,14 @@ fn main() {
         println!("\nStarting analysis...\n");
 
         let mut crate_refs = HashMap::new();
-        let extern_regex = Regex::new(r"^\s*extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)")?;
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
 
         analyzer.analyze_file(FileAnalysisContext {
             content: content.to_string(),
             file_path: &file_path,
             extern_regex: &extern_regex,
             crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
         })?;
 
         println!("\nAnalysis complete. Found crates:");
@@ -783,6 +2437,27 @@ fn main() {
         Ok(())
     }
 
+    #[test]
+    fn test_multiline_use_statements() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let content = "use tokio::{\n    runtime::Runtime,\n    sync::Mutex\n};\n\
+                       use\n    regex::{\n        Regex,\n        RegexSet,\n    };\n\
+                       pub use rayon\n    ::prelude::*;\n\
+                       fn user() { let users = vec![1];\n    let user_count = users.len(); }\n";
+
+        let crate_refs = analyzer.analyze_source(content, &temp_dir.path().join("src/lib.rs"))?;
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["rayon", "regex", "tokio"]);
+        assert!(
+            crate_refs
+                .values()
+                .all(|crate_ref| crate_ref.import_count == 1)
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_nested_and_complex_use_statements() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -831,13 +2506,14 @@ fn main() {
         println!("\nStarting analysis...\n");
 
         let mut crate_refs = HashMap::new();
-        let extern_regex = Regex::new(r"^\s*extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)")?;
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
 
         analyzer.analyze_file(FileAnalysisContext {
             content: content.to_string(),
             file_path: &file_path,
             extern_regex: &extern_regex,
             crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
         })?;
 
         println!("\nAnalysis complete. Found crates:");
@@ -946,6 +2622,353 @@ fn main() {}
         Ok(())
     }
 
+    #[test]
+    fn test_macro_use_extern_crate_counts_as_usage() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "legacy"
+version = "0.1.0"
+edition = "2015"
+
+[dependencies]
+log = "0.4"
+lazy_static = "1"
+"#,
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        // Only the crates' macros are used; no path mentions either crate
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            r#"#[macro_use] extern crate log;
+#[macro_use(lazy_static)] extern crate lazy_static;
+
+lazy_static! {
+    static ref LEVEL: u8 = 3;
+}
+
+pub fn run() {
+    info!("level {}", *LEVEL);
+}
+"#,
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert_eq!(crate_refs["log"].usage_count(), 1);
+        assert_eq!(crate_refs["lazy_static"].usage_count(), 1);
+
+        let updater =
+            crate::dependency_manager::DependencyUpdater::new(temp_dir.path().to_path_buf());
+        assert!(updater.plan(&crate_refs)?.removals.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_only_crates_are_not_removed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "settings"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+strum = { version = "0.26", features = ["derive"] }
+thiserror = "1"
+unused_crate = "0.1"
+"#,
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        // `Display` comes in through the prelude's glob re-export; only the derive's
+        // helper attribute names strum. The comment doesn't keep unused_crate.
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            r#"mod prelude;
+use prelude::*;
+
+#[derive(Debug, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid settings")]
+    Invalid,
+}
+
+// unused_crate::parse was replaced by the derive
+"#,
+        )?;
+        fs::write(
+            temp_dir.path().join("src/prelude.rs"),
+            "pub use crate::Theme as Mode;\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert_eq!(crate_refs["thiserror"].usage_count(), 1);
+        let strum = &crate_refs["strum"];
+        assert_eq!(strum.usage_count(), 0);
+        assert_eq!(
+            strum.mentioned_in,
+            HashSet::from([temp_dir.path().join("src/lib.rs")])
+        );
+        assert!(!crate_refs.contains_key("unused_crate"));
+
+        let updater =
+            crate::dependency_manager::DependencyUpdater::new(temp_dir.path().to_path_buf());
+        let removals = updater
+            .plan(&crate_refs)?
+            .removals
+            .into_iter()
+            .map(|change| change.name)
+            .collect::<Vec<_>>();
+        assert_eq!(removals, ["unused_crate"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edition_2015_only_counts_extern_crates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "legacy"
+version = "0.1.0"
+edition = "2015"
+
+[dependencies]
+log = "0.4"
+"#,
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            r#"extern crate regex;
+#[macro_use]
+extern crate log;
+
+mod parser;
+
+use parser::Token;
+use regex::Regex;
+use ::log::Level;
+
+pub fn tokens() -> Vec<Token> {
+    helpers::split(Regex::new("x").unwrap())
+}
+"#,
+        )?;
+        fs::write(
+            temp_dir.path().join("src/parser.rs"),
+            "use regex::Regex;
+
+pub struct Token;
+",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["log", "regex"]);
+        assert_eq!(crate_refs["regex"].usage_count(), 2);
+
+        // The same sources in a 2018 crate treat local module paths as crates
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"legacy\"\nversion = \"0.1.0\"\nedition = \"2018\"\n",
+        )?;
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs.contains_key("parser"));
+        assert!(crate_refs.contains_key("helpers"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_leading_colons_credit_the_crate() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_source(
+            "use ::regex::Regex;\n\nfn main() {\n    let _ = Regex::new(\"a\");\n}\n",
+            Path::new("src/main.rs"),
+        )?;
+        assert_eq!(crate_refs.keys().collect::<Vec<_>>(), ["regex"]);
+
+        // In edition 2015 `::regex` is a crate-root path, which names the declared crate
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"legacy\"\nversion = \"0.1.0\"\nedition = \"2015\"\n\n\
+             [dependencies]\nregex = \"1\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "use ::regex::Regex;\nuse ::parser::Token;\n\nmod parser;\n",
+        )?;
+        fs::write(temp_dir.path().join("src/parser.rs"), "pub struct Token;\n")?;
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert_eq!(crate_refs.keys().collect::<Vec<_>>(), ["regex"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_imports_count_one_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file_path = create_test_file(
+            &temp_dir,
+            "models.rs",
+            r#"use serde::Serialize;
+use serde::Deserialize;
+use serde::{de, ser::Serializer};
+use anyhow::Result;
+
+fn parse() -> serde_json::Value {
+    serde_json::Value::Null
+}"#,
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let mut crate_refs = HashMap::new();
+        analyzer.analyze_file(FileAnalysisContext {
+            content: fs::read_to_string(&file_path)?,
+            file_path: &file_path,
+            extern_regex: &Regex::new(EXTERN_CRATE_PATTERN)?,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        assert_eq!(crate_refs["serde"].usage_count(), 1);
+        assert_eq!(crate_refs["serde"].import_count, 3);
+        assert_eq!(crate_refs["anyhow"].import_count, 1);
+        // Path references are usages, not imports
+        assert_eq!(crate_refs["serde_json"].usage_count(), 1);
+        assert_eq!(crate_refs["serde_json"].import_count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_platform_gated_imports() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            r#"
+#[cfg(windows)]
+use winapi;
+
+#[cfg(target_os="linux")]
+fn page_size() -> usize {
+    nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE).unwrap_or_default()
+}
+
+#[cfg(unix)]
+use libc::c_int;
+
+#[cfg(windows)]
+use regex::Regex;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use memchr::memchr;
+
+#[cfg(unix)]
+mod unix;
+"#,
+        )?;
+        fs::write(
+            temp_dir.path().join("src/unix.rs"),
+            "use libc::getpid;\nuse mio::Poll;\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "use regex::Regex;\nfn main() {}\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert_eq!(crate_refs["winapi"].target_cfg.as_deref(), Some("windows"));
+        assert_eq!(
+            crate_refs["nix"].target_cfg.as_deref(),
+            Some("target_os = \"linux\"")
+        );
+        // Gated item-by-item in lib.rs and by module declaration for unix.rs
+        assert_eq!(crate_refs["libc"].target_cfg.as_deref(), Some("unix"));
+        assert_eq!(crate_refs["mio"].target_cfg.as_deref(), Some("unix"));
+        // Also used unconditionally in main.rs
+        assert_eq!(crate_refs["regex"].target_cfg, None);
+        // Compound predicates are not platform gates
+        assert_eq!(crate_refs["memchr"].target_cfg, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cfg_test_module_imports_are_dev_only() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            r#"
+use regex::Regex;
+
+pub fn parse(input: &str) -> bool {
+    Regex::new(input).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn never_panics(input in ".*") {
+            let _ = parse(&input);
+            let _ = regex::escape(&input);
+        }
+    }
+}
+
+pub fn after_tests() -> String {
+    serde_json::json!({}).to_string()
+}
+"#,
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs["proptest"].is_dev_dependency);
+        // Also used outside the test module
+        assert!(!crate_refs["regex"].is_dev_dependency);
+        // The test module ends where its braces balance
+        assert!(!crate_refs["serde_json"].is_dev_dependency);
+
+        let updater =
+            crate::dependency_manager::DependencyUpdater::new(temp_dir.path().to_path_buf());
+        let plan = updater.plan(&crate_refs)?;
+        let proptest = plan
+            .additions
+            .iter()
+            .find(|change| change.name == "proptest")
+            .unwrap();
+        assert_eq!(proptest.table, "dev-dependencies");
+        Ok(())
+    }
+
     #[test]
     fn test_dev_dependencies_from_tests_directory() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1085,6 +3108,20 @@ fn main() {
         Ok(())
     }
 
+    #[test]
+    fn test_is_test_file_handles_separators() {
+        let root = PathBuf::from("/work/tests/project");
+        let analyzer = DependencyAnalyzer::new(root.clone());
+
+        assert!(analyzer.is_test_file(&root.join("tests/cli.rs")));
+        assert!(analyzer.is_test_file(&root.join("tests\\cli.rs")));
+        assert!(analyzer.is_test_file(&root.join("crates\\core\\tests\\it.rs")));
+        assert!(analyzer.is_test_file(&root.join("src\\parser_test.rs")));
+        // A `tests` directory above the project root doesn't make every file a test
+        assert!(!analyzer.is_test_file(&root.join("src/main.rs")));
+        assert!(!analyzer.is_test_file(&root.join("src\\contests\\mod.rs")));
+    }
+
     #[test]
     fn test_direct_reference_detection() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1129,4 +3166,456 @@ fn main() {
 
         Ok(())
     }
+
+    #[test]
+    fn test_absolute_paths_in_macro_rules() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("macros.rs");
+        let content = r#"
+macro_rules! make_regex {
+    ($t:ty, $pattern:expr) => {{
+        let _ = $crate::helpers::noop();
+        let _value = $t::default();
+        ::regex::Regex::new($pattern).unwrap()
+    }};
+}
+"#;
+
+        let mut crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+
+        analyzer.analyze_file(FileAnalysisContext {
+            content: content.to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        assert!(
+            crate_refs.contains_key("regex"),
+            "::regex::Regex should credit regex"
+        );
+        assert!(
+            !crate_refs.contains_key("crate"),
+            "$crate should be ignored"
+        );
+        assert!(
+            !crate_refs.contains_key("helpers"),
+            "$crate paths should not credit their first module"
+        );
+        assert!(
+            !crate_refs.contains_key("t"),
+            "macro metavariables should be ignored"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_fingerprint_tracks_imports() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cargo_toml = temp_dir.path().join("Cargo.toml");
+        fs::write(&cargo_toml, "[package]\nname = \"fp\"\n")?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        let main_rs = temp_dir.path().join("src/main.rs");
+        fs::write(&main_rs, "use serde::Serialize;\n\nfn main() {}\n")?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let initial = analyzer.import_fingerprint()?;
+        assert_eq!(initial, analyzer.import_fingerprint()?, "should be stable");
+
+        // Changes that don't touch imports keep the fingerprint
+        fs::write(
+            &main_rs,
+            "use serde::Serialize;\n\nfn main() {\n    let x = 1;\n}\n",
+        )?;
+        assert_eq!(initial, analyzer.import_fingerprint()?);
+
+        // New imports change it
+        fs::write(&main_rs, "use serde::Serialize;\nuse regex::Regex;\n")?;
+        let with_regex = analyzer.import_fingerprint()?;
+        assert_ne!(initial, with_regex);
+
+        // Manifest edits change it too
+        fs::write(&cargo_toml, "[package]\nname = \"fp2\"\n")?;
+        assert_ne!(with_regex, analyzer.import_fingerprint()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_fingerprint_tracks_mentions_of_declared_crates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"fp\"\n\n[dependencies]\nlazy_static = \"1\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        let main_rs = temp_dir.path().join("src/main.rs");
+        fs::write(
+            &main_rs,
+            "lazy_static! {\n    static ref X: u8 = 1;\n}\n\nfn main() {}\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let with_macro = analyzer.import_fingerprint()?;
+
+        // Dropping the only mention must not hit the fast path, or the crate would stay
+        fs::write(&main_rs, "// lazy_static is gone\nfn main() {}\n")?;
+        let without_macro = analyzer.import_fingerprint()?;
+        assert_ne!(with_macro, without_macro);
+
+        // Mentions in comments don't count, as in the analysis
+        fs::write(&main_rs, "// lazy_static is gone for good\nfn main() {}\n")?;
+        assert_eq!(without_macro, analyzer.import_fingerprint()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_fingerprint_tracks_config_members_and_includes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[workspace]\nmembers = [\"crates/*\"]\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "include!(\"tables.rs.in\");\n\nfn main() {}\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("src/tables.rs.in"),
+            "use serde::Serialize;\n",
+        )?;
+        let member = temp_dir.path().join("crates/core/Cargo.toml");
+        fs::create_dir_all(member.parent().unwrap())?;
+        fs::write(&member, "[package]\nname = \"core\"\n")?;
+
+        let mut analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let initial = analyzer.import_fingerprint()?;
+
+        // The effective config, which carries CLI overrides such as --exclude-dir
+        let mut config = Config::default();
+        config.exclude_dirs.push(PathBuf::from("generated"));
+        analyzer.set_config(config);
+        let configured = analyzer.import_fingerprint()?;
+        assert_ne!(initial, configured);
+
+        // A member manifest renaming its library
+        fs::write(
+            &member,
+            "[package]\nname = \"core\"\n\n[lib]\nname = \"engine\"\n",
+        )?;
+        let renamed = analyzer.import_fingerprint()?;
+        assert_ne!(configured, renamed);
+
+        // A file pulled in with include!
+        fs::write(
+            temp_dir.path().join("src/tables.rs.in"),
+            "use regex::Regex;\n",
+        )?;
+        assert_ne!(renamed, analyzer.import_fingerprint()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_crate_name() {
+        assert_eq!(DependencyAnalyzer::normalize_crate_name("serde"), "serde");
+        assert_eq!(DependencyAnalyzer::normalize_crate_name("serde;"), "serde");
+        assert_eq!(DependencyAnalyzer::normalize_crate_name("serde ;"), "serde");
+        assert_eq!(
+            DependencyAnalyzer::normalize_crate_name("  serde\t;\n"),
+            "serde"
+        );
+        assert_eq!(
+            DependencyAnalyzer::normalize_crate_name("serde as s;"),
+            "serde"
+        );
+        assert_eq!(
+            DependencyAnalyzer::normalize_crate_name("{ regex }"),
+            "regex"
+        );
+        assert_eq!(DependencyAnalyzer::normalize_crate_name("tokio::"), "tokio");
+        assert_eq!(DependencyAnalyzer::normalize_crate_name(" ; "), "");
+        assert_eq!(
+            DependencyAnalyzer::normalize_crate_name("rayon::*"),
+            "rayon"
+        );
+        assert_eq!(DependencyAnalyzer::normalize_crate_name("*;"), "");
+    }
+
+    #[test]
+    fn test_wildcard_and_incomplete_imports() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        // Globs at every position, and paths left incomplete by an edit in progress
+        let content = "use rayon::prelude::*;\n\
+                       use regex::*;\n\
+                       use {tokio::*, *};\n\
+                       use ::walkdir::*;\n\
+                       use *;\n\
+                       use self::*;\n\
+                       use super::*;\n\
+                       use serde::;\n\
+                       use anyhow::\n";
+
+        let crate_refs = analyzer.analyze_source(content, Path::new("src/lib.rs"))?;
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            ["anyhow", "rayon", "regex", "serde", "tokio", "walkdir"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_oddly_spaced_use_statements() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("spacing.rs");
+        let content =
+            "use serde ;\nuse regex;\nuse   walkdir   ;\nuse anyhow as ah;\nuse semver\t;";
+
+        let mut crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+
+        analyzer.analyze_file(FileAnalysisContext {
+            content: content.to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        let mut names: Vec<_> = crate_refs.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["anyhow", "regex", "semver", "serde", "walkdir"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_commas_and_multiline_braces() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("braces.rs");
+        let content = "use foo::{ Bar, };\n\
+                       use qux::{\n    Alpha,\n    Beta,\n};\n\
+                       use {\n    baz::{A, B,},\n    quux,\n};\n\
+                       use ::lead::Thing;\n";
+
+        let mut crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+
+        analyzer.analyze_file(FileAnalysisContext {
+            content: content.to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        let mut names: Vec<_> = crate_refs.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["baz", "foo", "lead", "quux", "qux"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pub_use_reexports() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let file_path = temp_dir.path().join("lib.rs");
+        let content = "pub use reexported;\n\
+                       pub(crate) use inner::{A, B};\n\
+                       pub(in crate::api) use deep;\n\
+                       pub use facade::{\n    One,\n    Two,\n};\n\
+                       pub use self::local::Item;\n";
+
+        let mut crate_refs = HashMap::new();
+        let extern_regex = Regex::new(EXTERN_CRATE_PATTERN)?;
+
+        analyzer.analyze_file(FileAnalysisContext {
+            content: content.to_string(),
+            file_path: &file_path,
+            extern_regex: &extern_regex,
+            crate_refs: &mut crate_refs,
+            externs: &mut HashMap::new(),
+        })?;
+
+        let mut names: Vec<_> = crate_refs.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["deep", "facade", "inner", "reexported"]);
+
+        assert_eq!(
+            DependencyAnalyzer::strip_visibility("pub(super) use x;"),
+            "use x;"
+        );
+        assert_eq!(
+            DependencyAnalyzer::strip_visibility("pubsub::run();"),
+            "pubsub::run();"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_attribute_includes_out_of_tree_module() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(project.join("src"))?;
+        fs::create_dir_all(temp_dir.path().join("shared"))?;
+
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"project\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+        )?;
+        fs::write(
+            project.join("src/main.rs"),
+            "#[path = \"../../shared/mod.rs\"]\nmod shared;\n\nfn main() {}\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("shared/mod.rs"),
+            "use regex::Regex;\n\npub fn pattern() -> Regex { Regex::new(\"a\").unwrap() }\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(project);
+        let crate_refs = analyzer.analyze_dependencies()?;
+
+        let regex_ref = crate_refs
+            .get("regex")
+            .expect("regex from #[path] module should be detected");
+        assert!(!regex_ref.is_dev_dependency);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_targets_outside_src_are_analyzed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("app");
+        fs::create_dir_all(root.join("src"))?;
+        fs::create_dir_all(root.join("perf"))?;
+        fs::create_dir_all(temp_dir.path().join("tools"))?;
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [[bin]]\nname = \"tool\"\npath = \"../tools/tool.rs\"\n\n\
+             [[bench]]\nname = \"speed\"\npath = \"perf/speed.rs\"\nharness = false\n",
+        )?;
+        fs::write(root.join("src/lib.rs"), "pub fn run() {}\n")?;
+        // The binary lives outside the project root and pulls in a module of its own
+        fs::write(
+            temp_dir.path().join("tools/tool.rs"),
+            "mod cli;\n\nfn main() {\n    cli::parse();\n}\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("tools/cli.rs"),
+            "use clap::Parser;\n\npub fn parse() {}\n",
+        )?;
+        fs::write(
+            root.join("perf/speed.rs"),
+            "use rand::Rng;\n\nfn main() {}\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(root.clone());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        let clap_ref = crate_refs
+            .get("clap")
+            .expect("clap from the [[bin]] outside the project root should be detected");
+        assert!(!clap_ref.is_dev_dependency);
+        let rand_ref = crate_refs
+            .get("rand")
+            .expect("rand from the [[bench]] target should be detected");
+        assert!(
+            rand_ref.is_dev_dependency,
+            "bench targets only need dev-dependencies"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_macro_files_are_analyzed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src/tables"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "include!(\"tables/lookup.rs.in\");\n\
+             const SCHEMA: &str = include_str!(\"schema.txt\");\n\
+             include!(concat!(env!(\"OUT_DIR\"), \"/bindings.rs\"));\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("src/tables/lookup.rs.in"),
+            "// @generated by build tooling\nuse regex::Regex;\n\
+             pub fn table() -> Regex { Regex::new(\"a\").unwrap() }\n",
+        )?;
+        // Text that happens to look like Rust; include_str! never compiles it
+        fs::write(temp_dir.path().join("src/schema.txt"), "use fakecrate;\n")?;
+        fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n")?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        let regex_ref = crate_refs
+            .get("regex")
+            .expect("regex from the include!-ed file should be detected");
+        assert!(
+            regex_ref
+                .used_in
+                .iter()
+                .any(|path| path.ends_with("src/tables/lookup.rs.in"))
+        );
+        assert!(!crate_refs.contains_key("fakecrate"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_patterns_spare_declared_and_real_crates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nmycrate_tests = \"0.1\"\ncrates_io_api = \"0.11\"\n\n\
+             [dev-dependencies]\ntempfile = \"3\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("src"))?;
+        fs::create_dir_all(temp_dir.path().join("tests"))?;
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "use mycrate_tests::Fixture;\nuse crates_io_api::SyncClient;\n\
+             use integration_tests::helper;\n",
+        )?;
+        fs::write(
+            temp_dir.path().join("tests/files.rs"),
+            "use tempfile::TempDir;\n\n#[test]\nfn works() { TempDir::new().unwrap(); }\n",
+        )?;
+
+        let analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        let crate_refs = analyzer.analyze_dependencies()?;
+        let tempfile_ref = crate_refs
+            .get("tempfile")
+            .expect("tempfile is a real crate and should be detected");
+        assert!(tempfile_ref.is_dev_dependency);
+        // Declared in Cargo.toml, so the default `*_tests` pattern does not apply
+        assert!(crate_refs.contains_key("mycrate_tests"));
+        assert!(crate_refs.contains_key("crates_io_api"));
+        assert!(!crate_refs.contains_key("integration_tests"));
+
+        let mut analyzer = DependencyAnalyzer::new(temp_dir.path().to_path_buf());
+        analyzer.set_config(Config {
+            ignore_patterns: Vec::new(),
+            ..Config::default()
+        });
+        let crate_refs = analyzer.analyze_dependencies()?;
+        assert!(crate_refs.contains_key("integration_tests"));
+        Ok(())
+    }
 }
@@ -0,0 +1,512 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Deserializer};
+use toml_edit::DocumentMut;
+
+use crate::config::DEPENDENCY_TABLES;
+use crate::dependency_manager::reporter::DependencyReporter;
+use crate::dependency_manager::updater::DependencyUpdater;
+use crate::lockfile::ResolvedGraph;
+use crate::models::{Advisory, AdvisoryFix, AuditReport, DependencySource, WorkspaceAdvisory};
+use crate::utils::{normalize_package_name, patched_crates};
+
+/// A RustSec advisory as stored in the advisory database (`crates/<name>/RUSTSEC-*.md`)
+#[derive(Debug, Clone, Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    /// Only set in the legacy `.toml` format; markdown advisories use their first heading
+    title: Option<String>,
+    /// Set for notices such as `unmaintained`, which are not vulnerabilities
+    informational: Option<String>,
+    withdrawn: Option<toml::Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default, deserialize_with = "version_reqs")]
+    patched: Vec<VersionReq>,
+    #[serde(default, deserialize_with = "version_reqs")]
+    unaffected: Vec<VersionReq>,
+}
+
+fn version_reqs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<VersionReq>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|req| VersionReq::parse(req).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+impl AdvisoryFile {
+    /// Parse an advisory: markdown with a fenced TOML front matter, or plain TOML
+    fn parse(content: &str) -> Result<Self> {
+        let Some(rest) = content.trim_start().strip_prefix("```toml") else {
+            return Ok(toml::from_str(content)?);
+        };
+        let (front_matter, body) = rest
+            .split_once("\n```")
+            .context("unterminated TOML front matter")?;
+        let mut advisory: AdvisoryFile = toml::from_str(front_matter)?;
+        if advisory.advisory.title.is_none() {
+            advisory.advisory.title = body
+                .lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string());
+        }
+        Ok(advisory)
+    }
+
+    /// Whether `version` is neither patched nor unaffected
+    fn affects(&self, version: &Version) -> bool {
+        !self
+            .versions
+            .patched
+            .iter()
+            .chain(&self.versions.unaffected)
+            .any(|req| req.matches(version))
+    }
+}
+
+/// Checks declared dependencies against a local copy of the RustSec advisory database
+/// (`cargo autodd audit`)
+pub struct DependencyAuditor {
+    cargo_toml: PathBuf,
+    advisory_db: PathBuf,
+}
+
+impl DependencyAuditor {
+    pub fn new(project_root: PathBuf) -> Self {
+        Self {
+            cargo_toml: project_root.join("Cargo.toml"),
+            advisory_db: Self::default_advisory_db(),
+        }
+    }
+
+    /// Where `cargo audit` keeps its copy of the database: `$CARGO_HOME/advisory-db`
+    fn default_advisory_db() -> PathBuf {
+        env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+            .unwrap_or_default()
+            .join("advisory-db")
+    }
+
+    /// Read advisories from a checkout of https://github.com/rustsec/advisory-db at `path`
+    pub fn set_advisory_db(&mut self, path: PathBuf) {
+        self.advisory_db = path;
+    }
+
+    /// Check every registry dependency in the manifest's dependency tables.
+    ///
+    /// Path, git, workspace-inherited, patched and alternative-registry dependencies are
+    /// skipped: the database only covers crates.io.
+    pub fn audit(&self, updater: &DependencyUpdater) -> Result<AuditReport> {
+        self.ensure_advisory_db()?;
+        let content = fs::read_to_string(&self.cargo_toml)
+            .with_context(|| format!("Failed to read {}", self.cargo_toml.display()))?;
+        let doc = content.parse::<DocumentMut>()?;
+        let patched = patched_crates(&doc);
+
+        let mut report = AuditReport::default();
+        for table in DEPENDENCY_TABLES {
+            let mut item = doc.as_item();
+            for key in table.split('.') {
+                item = item.get(key).unwrap_or(&toml_edit::Item::None);
+            }
+            let Some(deps) = item.as_table_like() else {
+                continue;
+            };
+            for (name, dep) in deps.iter() {
+                if DependencyUpdater::dependency_source(dep) != DependencySource::Registry
+                    || DependencyUpdater::dependency_registry(dep).is_some()
+                    || !updater.is_selected(name)
+                    || patched.contains(&normalize_package_name(name))
+                {
+                    continue;
+                }
+                let Some(current) = updater.get_dependency_version(dep) else {
+                    continue;
+                };
+                let Ok(req) = VersionReq::parse(current.trim()) else {
+                    continue;
+                };
+                let Some(version) = DependencyReporter::minimum_version(&req) else {
+                    continue;
+                };
+                let advisories = self.advisories_for(name);
+                Self::audit_dependency(table, name, &current, &req, &version, &advisories)
+                    .apply(&mut report);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Whether the advisory database checkout exists
+    pub fn has_advisory_db(&self) -> bool {
+        self.advisory_db.join("crates").is_dir()
+    }
+
+    fn ensure_advisory_db(&self) -> Result<()> {
+        if !self.has_advisory_db() {
+            anyhow::bail!(
+                "No RustSec advisory database at {} (run `cargo audit fetch` or clone \
+                 https://github.com/rustsec/advisory-db there)",
+                self.advisory_db.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Check every crates.io package of a resolved graph (as locked, so transitive
+    /// dependencies too). `dependents` holds, per package, the members that depend on it.
+    pub fn audit_resolved(
+        &self,
+        graph: &ResolvedGraph,
+        dependents: &[BTreeSet<String>],
+    ) -> Result<Vec<WorkspaceAdvisory>> {
+        self.ensure_advisory_db()?;
+        let mut vulnerable = Vec::new();
+        for (package, members) in graph.packages.iter().zip(dependents) {
+            if !package.is_crates_io() {
+                continue;
+            }
+            for advisory in self.advisories_for(&package.name) {
+                if !advisory.affects(&package.version) {
+                    continue;
+                }
+                vulnerable.push(WorkspaceAdvisory {
+                    id: advisory.advisory.id.clone(),
+                    name: package.name.clone(),
+                    version: package.version.to_string(),
+                    summary: advisory.advisory.title.clone().unwrap_or_default(),
+                    patched: advisory
+                        .versions
+                        .patched
+                        .iter()
+                        .map(|req| req.to_string())
+                        .collect(),
+                    members: members.iter().cloned().collect(),
+                });
+            }
+        }
+        vulnerable.sort_by(|a, b| (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id)));
+        Ok(vulnerable)
+    }
+
+    /// Advisories for `name` that are neither withdrawn nor informational. An advisory
+    /// that can't be read or parsed is skipped with a warning, so one file the parser
+    /// doesn't understand doesn't stop the whole audit.
+    fn advisories_for(&self, name: &str) -> Vec<AdvisoryFile> {
+        let dir = self.advisory_db.join("crates").join(name);
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "md" || ext == "toml")
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut advisories = Vec::new();
+        for path in paths {
+            let advisory = match Self::read_advisory(&path) {
+                Ok(advisory) => advisory,
+                Err(e) => {
+                    eprintln!("⚠️ Skipping advisory: {:#}", e);
+                    continue;
+                }
+            };
+            if advisory.advisory.package == name
+                && advisory.advisory.withdrawn.is_none()
+                && advisory.advisory.informational.is_none()
+            {
+                advisories.push(advisory);
+            }
+        }
+        advisories
+    }
+
+    fn read_advisory(path: &Path) -> Result<AdvisoryFile> {
+        let content = fs::read_to_string(path)?;
+        AdvisoryFile::parse(&content)
+            .with_context(|| format!("Failed to parse advisory {}", path.display()))
+    }
+
+    /// Find the advisories affecting `version` (the lowest version `req` allows) and the
+    /// lowest patched version that none of the crate's advisories affect, preferring one
+    /// the existing requirement already allows
+    fn audit_dependency(
+        table: &str,
+        name: &str,
+        current: &str,
+        req: &VersionReq,
+        version: &Version,
+        advisories: &[AdvisoryFile],
+    ) -> Finding {
+        let affecting = advisories
+            .iter()
+            .filter(|advisory| advisory.affects(version))
+            .collect::<Vec<_>>();
+        if affecting.is_empty() {
+            return Finding::Clean;
+        }
+
+        let fixed = affecting
+            .iter()
+            .flat_map(|advisory| &advisory.versions.patched)
+            .filter_map(DependencyReporter::minimum_version)
+            .filter(|candidate| candidate > version)
+            .filter(|candidate| {
+                advisories
+                    .iter()
+                    .all(|advisory| !advisory.affects(candidate))
+            })
+            .min_by_key(|candidate| (!req.matches(candidate), candidate.clone()));
+
+        match fixed {
+            Some(fixed) => Finding::Fixable(AdvisoryFix {
+                table: table.to_string(),
+                name: name.to_string(),
+                current: current.to_string(),
+                compatible: req.matches(&fixed),
+                fixed: fixed.to_string(),
+                advisories: affecting
+                    .iter()
+                    .map(|advisory| advisory.advisory.id.clone())
+                    .collect(),
+            }),
+            None => Finding::Unfixed(
+                affecting
+                    .iter()
+                    .map(|advisory| Advisory {
+                        id: advisory.advisory.id.clone(),
+                        table: table.to_string(),
+                        name: name.to_string(),
+                        current: current.to_string(),
+                        summary: advisory.advisory.title.clone().unwrap_or_default(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Outcome of auditing a single dependency
+#[derive(Debug, PartialEq)]
+enum Finding {
+    Clean,
+    Fixable(AdvisoryFix),
+    Unfixed(Vec<Advisory>),
+}
+
+impl Finding {
+    fn apply(self, report: &mut AuditReport) {
+        match self {
+            Finding::Clean => {}
+            Finding::Fixable(fix) => report.fixes.push(fix),
+            Finding::Unfixed(advisories) => report.unfixed.extend(advisories),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn advisory(id: &str, package: &str, patched: &[&str], unaffected: &[&str]) -> String {
+        let list = |reqs: &[&str]| {
+            reqs.iter()
+                .map(|req| format!("\"{}\"", req))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "```toml\n[advisory]\nid = \"{}\"\npackage = \"{}\"\ndate = 2024-01-01\n\n\
+             [versions]\npatched = [{}]\nunaffected = [{}]\n```\n\n# Flaw in {}\n\nDetails.\n",
+            id,
+            package,
+            list(patched),
+            list(unaffected),
+            package
+        )
+    }
+
+    fn write_advisory(db: &Path, package: &str, id: &str, content: &str) -> Result<()> {
+        let dir = db.join("crates").join(package);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.md", id)), content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_picks_lowest_patched_version() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = temp_dir.path().join("advisory-db");
+        // Fixed in a 0.4 patch release and in 0.5; the 0.4 fix matches the requirement
+        write_advisory(
+            &db,
+            "smallvec",
+            "RUSTSEC-2024-0001",
+            &advisory(
+                "RUSTSEC-2024-0001",
+                "smallvec",
+                &[">= 0.5.2", "^0.4.7"],
+                &["< 0.3"],
+            ),
+        )?;
+        // A later advisory only patched in 0.4.9 rules out 0.4.7
+        write_advisory(
+            &db,
+            "smallvec",
+            "RUSTSEC-2024-0002",
+            &advisory("RUSTSEC-2024-0002", "smallvec", &[">= 0.4.9"], &[]),
+        )?;
+        write_advisory(
+            &db,
+            "chrono",
+            "RUSTSEC-2024-0003",
+            &advisory("RUSTSEC-2024-0003", "chrono", &[], &[]),
+        )?;
+        write_advisory(
+            &db,
+            "time",
+            "RUSTSEC-2024-0004",
+            &advisory("RUSTSEC-2024-0004", "time", &[">= 0.2.23"], &[]),
+        )?;
+        write_advisory(
+            &db,
+            "local",
+            "RUSTSEC-2024-0005",
+            &advisory("RUSTSEC-2024-0005", "local", &[], &[]),
+        )?;
+        let withdrawn = advisory("RUSTSEC-2024-0006", "regex", &[], &[]).replace(
+            "date = 2024-01-01",
+            "date = 2024-01-01\nwithdrawn = 2024-02-01",
+        );
+        write_advisory(&db, "regex", "RUSTSEC-2024-0006", &withdrawn)?;
+
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
+
+[dependencies]
+smallvec = "0.4.2"
+chrono = "0.4"
+time = "0.2.23"
+local = { path = "local" }
+regex = "1.0"
+
+[dev-dependencies]
+smallvec = { version = "0.3", features = ["union"] }
+"#,
+        )?;
+
+        let mut auditor = DependencyAuditor::new(temp_dir.path().to_path_buf());
+        auditor.set_advisory_db(db);
+        let updater = DependencyUpdater::new(temp_dir.path().to_path_buf());
+        let report = auditor.audit(&updater)?;
+
+        assert_eq!(
+            report.fixes,
+            vec![
+                AdvisoryFix {
+                    table: "dependencies".to_string(),
+                    name: "smallvec".to_string(),
+                    current: "0.4.2".to_string(),
+                    fixed: "0.4.9".to_string(),
+                    compatible: true,
+                    advisories: vec![
+                        "RUSTSEC-2024-0001".to_string(),
+                        "RUSTSEC-2024-0002".to_string()
+                    ],
+                },
+                AdvisoryFix {
+                    table: "dev-dependencies".to_string(),
+                    name: "smallvec".to_string(),
+                    current: "0.3".to_string(),
+                    fixed: "0.4.9".to_string(),
+                    compatible: false,
+                    advisories: vec![
+                        "RUSTSEC-2024-0001".to_string(),
+                        "RUSTSEC-2024-0002".to_string()
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            report.unfixed,
+            vec![Advisory {
+                id: "RUSTSEC-2024-0003".to_string(),
+                table: "dependencies".to_string(),
+                name: "chrono".to_string(),
+                current: "0.4".to_string(),
+                summary: "Flaw in chrono".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_skips_malformed_advisory() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db = temp_dir.path().join("advisory-db");
+        write_advisory(
+            &db,
+            "smallvec",
+            "RUSTSEC-2024-0001",
+            "```toml\n[advisory]\nid = \"RUSTSEC-2024-0001\"\n",
+        )?;
+        write_advisory(
+            &db,
+            "smallvec",
+            "RUSTSEC-2024-0002",
+            &advisory("RUSTSEC-2024-0002", "smallvec", &[">= 0.4.9"], &[]),
+        )?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nsmallvec = \"0.4.2\"\n",
+        )?;
+
+        let mut auditor = DependencyAuditor::new(temp_dir.path().to_path_buf());
+        auditor.set_advisory_db(db);
+        let updater = DependencyUpdater::new(temp_dir.path().to_path_buf());
+        let report = auditor.audit(&updater)?;
+        assert_eq!(report.fixes.len(), 1);
+        assert_eq!(report.fixes[0].fixed, "0.4.9");
+        assert_eq!(report.fixes[0].advisories, ["RUSTSEC-2024-0002"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_requires_advisory_db() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n",
+        )?;
+        let mut auditor = DependencyAuditor::new(temp_dir.path().to_path_buf());
+        auditor.set_advisory_db(temp_dir.path().join("missing"));
+        let updater = DependencyUpdater::new(temp_dir.path().to_path_buf());
+        let error = auditor.audit(&updater).unwrap_err().to_string();
+        assert!(error.contains("No RustSec advisory database"), "{}", error);
+        Ok(())
+    }
+}
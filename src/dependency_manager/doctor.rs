@@ -0,0 +1,215 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::DocumentMut;
+
+use crate::cache::Cache;
+use crate::dependency_manager::updater::DependencyUpdater;
+use crate::error::AutoddError;
+use crate::models::{CheckStatus, DoctorCheck, DoctorReport};
+
+/// Crate looked up to check that the registry is reachable
+const PROBE_CRATE: &str = "serde";
+
+/// Proxy variables a user may expect to be honored, in the order curl checks them
+const PROXY_VARIABLES: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// Diagnoses the environment cargo-autodd runs in (`cargo autodd doctor`)
+pub struct DependencyDoctor {
+    project_root: PathBuf,
+    cargo_toml: PathBuf,
+}
+
+impl DependencyDoctor {
+    pub fn new(project_root: PathBuf) -> Self {
+        let cargo_toml = project_root.join("Cargo.toml");
+        Self {
+            project_root,
+            cargo_toml,
+        }
+    }
+
+    /// Run every check, using `updater` for registry access
+    pub fn run(&self, updater: &DependencyUpdater) -> DoctorReport {
+        DoctorReport {
+            checks: vec![
+                self.check_manifest_exists(),
+                self.check_manifest_parses(),
+                Self::check_registry(updater),
+                Self::check_proxy(),
+                self.check_cache_writable(),
+            ],
+        }
+    }
+
+    fn check_manifest_exists(&self) -> DoctorCheck {
+        const NAME: &str = "Cargo.toml exists";
+        if self.cargo_toml.is_file() {
+            DoctorCheck::new(
+                NAME,
+                CheckStatus::Pass,
+                self.cargo_toml.display().to_string(),
+            )
+        } else {
+            DoctorCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("no Cargo.toml in {}", self.project_root.display()),
+            )
+        }
+    }
+
+    fn check_manifest_parses(&self) -> DoctorCheck {
+        const NAME: &str = "Cargo.toml parses";
+        let content = match fs::read_to_string(&self.cargo_toml) {
+            Ok(content) => content,
+            Err(e) => return DoctorCheck::new(NAME, CheckStatus::Fail, e.to_string()),
+        };
+        match content.parse::<DocumentMut>() {
+            Ok(doc) if doc.get("package").is_some() || doc.get("workspace").is_some() => {
+                DoctorCheck::new(NAME, CheckStatus::Pass, "valid TOML")
+            }
+            Ok(_) => DoctorCheck::new(
+                NAME,
+                CheckStatus::Warn,
+                "valid TOML, but has neither [package] nor [workspace]",
+            ),
+            Err(e) => DoctorCheck::new(NAME, CheckStatus::Fail, e.to_string().trim().to_string()),
+        }
+    }
+
+    fn check_registry(updater: &DependencyUpdater) -> DoctorCheck {
+        const NAME: &str = "crates.io reachable";
+        match updater.get_latest_version(PROBE_CRATE) {
+            Ok(_) => DoctorCheck::new(NAME, CheckStatus::Pass, "registry lookups work"),
+            Err(AutoddError::Offline(_)) => DoctorCheck::new(
+                NAME,
+                CheckStatus::Warn,
+                "skipped: network access is disabled (--offline/--frozen)",
+            ),
+            Err(e) => DoctorCheck::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("{}; new dependencies can't be resolved", e),
+            ),
+        }
+    }
+
+    fn check_proxy() -> DoctorCheck {
+        const NAME: &str = "Proxy";
+        let configured = PROXY_VARIABLES
+            .iter()
+            .filter(|var| env::var_os(var).is_some_and(|value| !value.is_empty()))
+            .copied()
+            .collect::<Vec<_>>();
+        if configured.is_empty() {
+            DoctorCheck::new(NAME, CheckStatus::Pass, "no proxy configured")
+        } else {
+            // Only the variable names are shown: proxy URLs may contain credentials
+            DoctorCheck::new(
+                NAME,
+                CheckStatus::Warn,
+                format!(
+                    "{} set, but cargo-autodd connects to the registry directly",
+                    configured.join(", ")
+                ),
+            )
+        }
+    }
+
+    fn check_cache_writable(&self) -> DoctorCheck {
+        const NAME: &str = "Cache writable";
+        let path = Cache::path_for(&self.project_root);
+        match Self::probe_write(&path) {
+            Ok(()) => DoctorCheck::new(NAME, CheckStatus::Pass, path.display().to_string()),
+            Err(e) => DoctorCheck::new(
+                NAME,
+                CheckStatus::Warn,
+                format!(
+                    "{}: {}; every run will re-analyze the project",
+                    path.display(),
+                    e
+                ),
+            ),
+        }
+    }
+
+    /// Create and remove a scratch file next to `path`, leaving any existing cache alone
+    fn probe_write(path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let probe = path.with_extension("probe");
+        fs::write(&probe, b"")?;
+        fs::remove_file(&probe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn status_of(report: &DoctorReport, name: &str) -> CheckStatus {
+        report
+            .checks
+            .iter()
+            .find(|check| check.name == name)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn test_doctor_missing_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut updater = DependencyUpdater::new(temp_dir.path().to_path_buf());
+        updater.set_offline(true);
+
+        let report = DependencyDoctor::new(temp_dir.path().to_path_buf()).run(&updater);
+        assert_eq!(status_of(&report, "Cargo.toml exists"), CheckStatus::Fail);
+        assert_eq!(status_of(&report, "Cargo.toml parses"), CheckStatus::Fail);
+        assert_eq!(status_of(&report, "crates.io reachable"), CheckStatus::Warn);
+        assert_eq!(report.failures(), 2);
+    }
+
+    #[test]
+    fn test_doctor_unparseable_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package\nname = ").unwrap();
+
+        let doctor = DependencyDoctor::new(temp_dir.path().to_path_buf());
+        assert_eq!(doctor.check_manifest_exists().status, CheckStatus::Pass);
+        assert_eq!(doctor.check_manifest_parses().status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_doctor_unreachable_registry() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut updater = DependencyUpdater::new(temp_dir.path().to_path_buf());
+        // Nothing listens on the discard port
+        updater.set_registry_api("http://127.0.0.1:9");
+
+        let check = DependencyDoctor::check_registry(&updater);
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_doctor_cache_writable() {
+        let temp_dir = TempDir::new().unwrap();
+        let doctor = DependencyDoctor::new(temp_dir.path().to_path_buf());
+        assert_eq!(doctor.check_cache_writable().status, CheckStatus::Pass);
+        assert!(
+            !Cache::path_for(temp_dir.path())
+                .with_extension("probe")
+                .exists()
+        );
+    }
+}
@@ -1,9 +1,15 @@
 mod analyzer;
+mod auditor;
+mod doctor;
+mod registry;
 mod reporter;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 mod updater;
 
 pub use analyzer::DependencyAnalyzer;
+pub use auditor::DependencyAuditor;
+pub use doctor::DependencyDoctor;
+pub use registry::Registry;
 pub use reporter::DependencyReporter;
-pub use updater::DependencyUpdater;
+pub use updater::{CrateMetadata, DependencyUpdater};
@@ -0,0 +1,117 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml_edit::DocumentMut;
+
+/// A named alternative registry (`registry = "<name>"` in a dependency entry)
+#[derive(Clone)]
+pub struct Registry {
+    /// Base URL of the registry's crates.io-compatible web API (`.../api/v1`)
+    pub api: String,
+    /// Value sent as the `Authorization` header, as cargo does for authenticated registries
+    pub token: Option<String>,
+}
+
+impl fmt::Debug for Registry {
+    // The token is a credential; keep it out of debug output and logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("api", &self.api)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// `config.json` at the root of a sparse index
+#[derive(Deserialize)]
+pub(crate) struct IndexConfig {
+    pub api: Option<String>,
+}
+
+/// Name of cargo's environment variable for a registry setting, e.g.
+/// `CARGO_REGISTRIES_MY_REGISTRY_TOKEN` for (`my-registry`, `TOKEN`)
+pub(crate) fn registry_env_var(name: &str, key: &str) -> String {
+    format!(
+        "CARGO_REGISTRIES_{}_{}",
+        name.to_ascii_uppercase().replace('-', "_"),
+        key
+    )
+}
+
+/// Token for `name` from `CARGO_REGISTRIES_<NAME>_TOKEN`
+pub(crate) fn registry_token(name: &str) -> Option<String> {
+    env::var(registry_env_var(name, "TOKEN"))
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+}
+
+/// Index URL of `name`, from `CARGO_REGISTRIES_<NAME>_INDEX` or the `[registries]` table
+/// of the nearest `.cargo/config.toml` (then `$CARGO_HOME/config.toml`)
+pub(crate) fn registry_index(project_root: &Path, name: &str) -> Option<String> {
+    if let Ok(index) = env::var(registry_env_var(name, "INDEX")) {
+        return Some(index);
+    }
+    let cargo_home = env::var_os("CARGO_HOME").map(PathBuf::from);
+    project_root
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home)
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .find_map(|path| {
+            let doc = fs::read_to_string(path).ok()?.parse::<DocumentMut>().ok()?;
+            doc.get("registries")?
+                .get(name)?
+                .get("index")?
+                .as_str()
+                .map(str::to_string)
+        })
+}
+
+/// URL of `config.json` for a sparse index (`sparse+https://...`); git indexes have none
+pub(crate) fn index_config_url(index: &str) -> Option<String> {
+    let index = index.strip_prefix("sparse+")?;
+    Some(format!("{}/config.json", index.trim_end_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_registry_settings() -> anyhow::Result<()> {
+        assert_eq!(
+            registry_env_var("my-registry", "TOKEN"),
+            "CARGO_REGISTRIES_MY_REGISTRY_TOKEN"
+        );
+        assert_eq!(
+            index_config_url("sparse+https://example.com/index/").as_deref(),
+            Some("https://example.com/index/config.json")
+        );
+        assert_eq!(index_config_url("https://example.com/git-index.git"), None);
+
+        let temp_dir = TempDir::new()?;
+        let member = temp_dir.path().join("crates/member");
+        fs::create_dir_all(&member)?;
+        fs::create_dir_all(temp_dir.path().join(".cargo"))?;
+        fs::write(
+            temp_dir.path().join(".cargo/config.toml"),
+            "[registries.autodd-test-private]\nindex = \"sparse+https://example.com/index/\"\n",
+        )?;
+        assert_eq!(
+            registry_index(&member, "autodd-test-private").as_deref(),
+            Some("sparse+https://example.com/index/")
+        );
+        assert_eq!(registry_index(&member, "autodd-test-unknown"), None);
+
+        let registry = Registry {
+            api: "https://example.com/api/v1".to_string(),
+            token: Some("secret".to_string()),
+        };
+        assert!(!format!("{:?}", registry).contains("secret"));
+        Ok(())
+    }
+}
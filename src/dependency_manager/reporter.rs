@@ -1,18 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 use anyhow::Result;
-use semver::Version;
-use toml_edit::DocumentMut;
+use semver::{Op, Version, VersionReq};
+use toml_edit::{DocumentMut, Table};
 
+use crate::config::{Config, DEPENDENCY_TABLES};
+use crate::dependency_manager::analyzer::DependencyAnalyzer;
+use crate::dependency_manager::auditor::DependencyAuditor;
 use crate::dependency_manager::updater::DependencyUpdater;
-use crate::models::CrateReference;
+use crate::lockfile::ResolvedGraph;
+use crate::metadata;
+use crate::models::{
+    ChangeKind, CrateReference, DependencyReport, DependencySource, DependencyStatus, FailedLookup,
+    ManifestDiff, MissingDependency, OutdatedDependency, PlannedChange, ResolvedOutdated,
+    SecurityReport, UpdateKind, VersionChange, WorkspaceSecurityReport,
+};
+use crate::output::OutputFormat;
+use crate::utils::{months_since, normalize_package_name, patched_crates, slash_path};
 
 pub struct DependencyReporter {
     project_root: PathBuf,
     cargo_toml: PathBuf,
     updater: DependencyUpdater,
+    config: Config,
+    verbose: bool,
+    max_age: Option<u32>,
+    min_usage: Option<usize>,
 }
 
 impl DependencyReporter {
@@ -23,127 +40,883 @@ impl DependencyReporter {
             project_root,
             cargo_toml,
             updater,
+            config: Config::default(),
+            verbose: false,
+            max_age: None,
+            min_usage: None,
         }
     }
 
+    /// Report on the Cargo.toml in `project_root` from now on
+    pub fn set_project_root(&mut self, project_root: PathBuf) {
+        self.updater.set_project_root(project_root.clone());
+        self.cargo_toml = project_root.join("Cargo.toml");
+        self.project_root = project_root;
+    }
+
+    /// Use `config` to decide which dependencies are essential or excluded
+    pub fn set_config(&mut self, config: Config) {
+        self.updater.set_config(config.clone());
+        self.config = config;
+    }
+
+    /// Flag dependencies whose latest release is older than `months` months
+    pub fn set_max_age(&mut self, months: Option<u32>) {
+        self.max_age = months;
+    }
+
+    /// List used dependencies found in at most `files` files as low-usage
+    pub fn set_min_usage(&mut self, files: Option<usize>) {
+        self.min_usage = files;
+    }
+
+    /// Forbid registry lookups (`--frozen`)
+    pub fn set_offline(&mut self, offline: bool) {
+        self.updater.set_offline(offline);
+    }
+
+    /// Only report on the named crates (`--only`)
+    pub fn set_only(&mut self, names: Option<Vec<String>>) {
+        self.updater.set_only(names);
+    }
+
+    /// Compare against the latest pre-release too (`--allow-prerelease`)
+    pub fn set_allow_prerelease(&mut self, allow_prerelease: bool) {
+        self.updater.set_allow_prerelease(allow_prerelease);
+    }
+
+    /// Log registry requests to stderr (`--explain-network`)
+    pub fn set_explain_network(&mut self, explain_network: bool) {
+        self.updater.set_explain_network(explain_network);
+    }
+
+    /// Include registry metadata (downloads, publish date) in dependency reports
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
     pub fn generate_dependency_report(
         &self,
         crate_refs: &HashMap<String, CrateReference>,
     ) -> Result<()> {
-        let content = fs::read_to_string(&self.cargo_toml)?;
-        let doc = content.parse::<DocumentMut>()?;
-
-        println!("\nDependency Usage Report");
-        println!("=====================\n");
+        let mut stdout = std::io::stdout();
+        self.write_dependency_report(crate_refs, OutputFormat::Text, &mut stdout)
+    }
 
-        // Check if this is a workspace or a package
-        let is_workspace = doc.get("workspace").is_some();
+    /// Build the dependency report and render it to `out` in the given format
+    pub fn write_dependency_report(
+        &self,
+        crate_refs: &HashMap<String, CrateReference>,
+        format: OutputFormat,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let report = self.build_dependency_report(crate_refs)?;
+        match format {
+            OutputFormat::Text => Self::render_dependency_report(&report, out)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, &report)?;
+                writeln!(out)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => Self::render_markdown_report(&report, out)?,
+        }
+        Ok(())
+    }
 
-        // Determine the correct dependencies table (workspace or package)
-        let deps_path = if is_workspace {
-            "workspace.dependencies"
-        } else {
-            "dependencies"
+    /// Collect the status of every declared dependency
+    pub fn build_dependency_report(
+        &self,
+        crate_refs: &HashMap<String, CrateReference>,
+    ) -> Result<DependencyReport> {
+        let content = fs::read_to_string(&self.cargo_toml)?;
+        let doc = content.parse::<DocumentMut>()?;
+        let deps_path = Self::deps_path(&doc);
+        let mut report = DependencyReport {
+            table: deps_path.to_string(),
+            broken_paths: DependencyAnalyzer::find_broken_paths(&self.project_root, &doc),
+            ..Default::default()
         };
+        self.add_missing_dependencies(&mut report, &doc, crate_refs)?;
 
-        // Get dependencies from the correct table
-        let deps = if deps_path.contains('.') {
-            // Handle nested table path like "workspace.dependencies"
-            let parts: Vec<&str> = deps_path.split('.').collect();
-            doc.get(parts[0])
-                .and_then(|t| t.as_table())
-                .and_then(|t| t.get(parts[1]))
-                .and_then(|t| t.as_table())
-        } else {
-            doc.get(deps_path).and_then(|t| t.as_table())
+        let Some(deps) = Self::deps_table(&doc, deps_path) else {
+            return Ok(report);
         };
 
-        if let Some(deps) = deps {
-            for (name, dep) in deps.iter() {
-                println!("📦 {}", name);
-
-                if let Some(version) = self.updater.get_dependency_version(dep) {
-                    println!("  Version: {}", version);
-
-                    match self.updater.get_latest_version(name) {
-                        Ok(latest) => {
-                            if let Ok(needs_update) = self.check_version(&version, &latest) {
-                                if needs_update {
-                                    println!("  ⚠️ Update available: {} -> {}", version, latest);
-                                } else {
-                                    println!("  ✅ Up to date");
-                                }
-                            }
+        let patched = patched_crates(&doc);
+        for (name, dep) in deps.iter() {
+            if !self.updater.is_selected(name) {
+                continue;
+            }
+            let mut status = DependencyStatus {
+                name: name.to_string(),
+                version: self.updater.get_dependency_version(dep),
+                patched: patched.contains(&normalize_package_name(name)),
+                ..Default::default()
+            };
+
+            if let Some(version) = &status.version
+                && !status.patched
+            {
+                let latest = self
+                    .updater
+                    .get_crate_metadata_from(name, DependencyUpdater::dependency_registry(dep))
+                    .map(|metadata| {
+                        if let Some(max_age) = self.max_age
+                            && let Some(published_at) = &metadata.published_at
+                            && let Some(age) = months_since(published_at, SystemTime::now())
+                        {
+                            status.months_since_publish = Some(age);
+                            status.stale = age > max_age;
+                        }
+                        if self.verbose {
+                            status.downloads = metadata.downloads;
+                            status.recent_downloads = metadata.recent_downloads;
+                            status.published_at = metadata.published_at;
                         }
-                        Err(e) => {
-                            println!("  ⚠️ Failed to check latest version: {}", e);
+                        metadata.latest_version
+                    });
+                match latest {
+                    Ok(latest) => {
+                        let kind = Version::parse(&latest)
+                            .map_err(anyhow::Error::from)
+                            .and_then(|latest| self.check_version(version, &latest));
+                        if let Ok(kind) = kind {
+                            status.update_kind = kind;
+                            status.update_available = kind != UpdateKind::UpToDate;
+                            status.latest = Some(latest);
                         }
                     }
+                    Err(e) => status.error = Some(e.to_string()),
                 }
+            }
 
-                if let Some(crate_ref) = crate_refs.get(name) {
-                    println!("  Used in {} file(s)", crate_ref.usage_count());
-                    println!("  Usage locations:");
-                    for path in &crate_ref.used_in {
-                        if let Ok(relative) = path.strip_prefix(&self.project_root) {
-                            println!("    - {}", relative.display());
-                        }
-                    }
+            // Imports use `_` where the package name may use `-`
+            let crate_ref = crate_refs.get(name).or_else(|| {
+                crate_refs
+                    .iter()
+                    .find(|(used, _)| normalize_package_name(used) == normalize_package_name(name))
+                    .map(|(_, crate_ref)| crate_ref)
+            });
+            if let Some(crate_ref) = crate_ref {
+                status.usage_count = crate_ref.usage_count();
+                status.used_in = crate_ref
+                    .used_in
+                    .iter()
+                    .filter_map(|path| path.strip_prefix(&self.project_root).ok())
+                    .map(slash_path)
+                    .collect();
+                status.used_in.sort();
+                status.mentioned_in = crate_ref
+                    .mentioned_in
+                    .iter()
+                    .filter_map(|path| path.strip_prefix(&self.project_root).ok())
+                    .map(slash_path)
+                    .collect();
+                status.mentioned_in.sort();
+                if self.verbose {
+                    status.import_count = Some(crate_ref.import_count);
+                }
+            }
+
+            // Path and git dependencies are left out: they are usually deliberate local
+            // or pinned choices rather than forgotten leftovers
+            if status.usage_count == 0
+                && status.mentioned_in.is_empty()
+                && !self.updater.is_protected(&doc, name)
+                && matches!(
+                    DependencyUpdater::dependency_source(dep),
+                    DependencySource::Registry | DependencySource::Workspace
+                )
+            {
+                report.unused.push(name.to_string());
+            }
+            if let Some(min_usage) = self.min_usage
+                && (1..=min_usage).contains(&status.usage_count)
+            {
+                report.low_usage.push(name.to_string());
+            }
+
+            report.dependencies.push(status);
+        }
+
+        // Sorted by name so that reports are stable and diffable across runs
+        report.dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+        report.unused.sort();
+        report.low_usage.sort();
+        report.low_usage.dedup();
+        Ok(report)
+    }
+
+    /// Fill in the crates the update plan would add that aren't declared in any dependency
+    /// table, with the version that would be written for them, along with the names it
+    /// wouldn't add: unknown to the registry, or declared only in a comment
+    fn add_missing_dependencies(
+        &self,
+        report: &mut DependencyReport,
+        doc: &DocumentMut,
+        crate_refs: &HashMap<String, CrateReference>,
+    ) -> Result<()> {
+        let declared = DEPENDENCY_TABLES
+            .iter()
+            .filter_map(|table| Self::deps_table(doc, table))
+            .flat_map(|table| table.iter().map(|(name, _)| normalize_package_name(name)))
+            .collect::<HashSet<_>>();
+
+        let mut plan = self.updater.plan(crate_refs)?;
+        plan.additions.retain(|change| {
+            !self.config.should_exclude(&change.name)
+                && !declared.contains(&normalize_package_name(&change.name))
+        });
+        self.updater.resolve_plan(&mut plan, crate_refs);
+
+        let mut missing = plan
+            .additions
+            .into_iter()
+            .map(|change| MissingDependency {
+                name: change.name,
+                version: change.version,
+            })
+            .collect::<Vec<_>>();
+        missing.sort_by(|a, b| a.name.cmp(&b.name));
+        missing.dedup_by(|a, b| a.name == b.name);
+        report.missing = missing;
+        report.ambiguous = plan.ambiguous;
+        report.commented_out = plan.commented_out;
+        Ok(())
+    }
+
+    /// Render the report as GitHub-flavored markdown for a PR comment: a summary line,
+    /// one table row per dependency and lists of missing, ambiguous, commented-out and
+    /// broken entries
+    fn render_markdown_report(report: &DependencyReport, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "## Dependency Usage Report\n")?;
+        writeln!(out, "**{}**\n", Self::markdown_summary(report))?;
+
+        if report.dependencies.is_empty() {
+            writeln!(out, "No dependencies found in `[{}]`.", report.table)?;
+        } else {
+            writeln!(out, "| Status | Crate | Version | Latest | Used in |")?;
+            writeln!(out, "| :---: | --- | --- | --- | ---: |")?;
+            for dep in &report.dependencies {
+                let mut status = Vec::new();
+                if dep.patched {
+                    status.push("🩹");
+                } else if dep.error.is_some() {
+                    status.push("❓");
                 } else {
-                    println!("  ⚠️ Warning: No usage detected in the project");
+                    match dep.update_kind {
+                        UpdateKind::Breaking => status.push("⚠️"),
+                        UpdateKind::Compatible => status.push("⬆️"),
+                        UpdateKind::UpToDate => {}
+                    }
+                }
+                if report.unused.contains(&dep.name) {
+                    status.push("🗑️");
+                }
+                if dep.stale {
+                    status.push("🕰️");
                 }
-                println!();
+                if status.is_empty() {
+                    status.push("✅");
+                }
+
+                // Only registry dependencies (the ones with a version) live on crates.io
+                let name = match &dep.version {
+                    Some(_) => format!("[{0}](https://crates.io/crates/{0})", dep.name),
+                    None => dep.name.clone(),
+                };
+                let files = match dep.usage_count {
+                    0 => "-".to_string(),
+                    1 => "1 file".to_string(),
+                    n => format!("{} files", n),
+                };
+                writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} |",
+                    status.join(" "),
+                    name,
+                    Self::markdown_cell(dep.version.as_deref()),
+                    Self::markdown_cell(dep.latest.as_deref()),
+                    files
+                )?;
+            }
+            writeln!(
+                out,
+                "\n✅ up to date · ⬆️ compatible update · ⚠️ breaking update · 🗑️ unused · \
+                 🕰️ stale · 🩹 patched · ❓ lookup failed"
+            )?;
+        }
+
+        if !report.missing.is_empty() {
+            writeln!(out, "\n### Missing dependencies\n")?;
+            for dep in &report.missing {
+                match &dep.version {
+                    Some(version) => writeln!(
+                        out,
+                        "- [{0}](https://crates.io/crates/{0}) `{1}`",
+                        dep.name, version
+                    )?,
+                    None => writeln!(out, "- {} (version not resolved)", dep.name)?,
+                }
+            }
+        }
+
+        if !report.ambiguous.is_empty() {
+            writeln!(out, "\n### Ambiguous imports\n")?;
+            writeln!(
+                out,
+                "Not declared and not on crates.io, so never added; likely local modules.\n"
+            )?;
+            for name in &report.ambiguous {
+                writeln!(out, "- `{}`", name)?;
+            }
+        }
+
+        if !report.commented_out.is_empty() {
+            writeln!(out, "\n### Commented-out dependencies\n")?;
+            for entry in &report.commented_out {
+                writeln!(
+                    out,
+                    "- `{}`: uncomment line {} in `[{}]`",
+                    entry.name, entry.line, entry.table
+                )?;
             }
+        }
+
+        if !report.broken_paths.is_empty() {
+            writeln!(out, "\n### Broken path dependencies\n")?;
+            for dep in &report.broken_paths {
+                writeln!(
+                    out,
+                    "- `[{}] {}` → `{}`: {}",
+                    dep.table, dep.name, dep.path, dep.problem
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// One-line summary such as `3 updates available, 1 unused`
+    fn markdown_summary(report: &DependencyReport) -> String {
+        let updates = report
+            .dependencies
+            .iter()
+            .filter(|dep| dep.update_available)
+            .count();
+        let mut parts = Vec::new();
+        match updates {
+            0 => {}
+            1 => parts.push("1 update available".to_string()),
+            n => parts.push(format!("{} updates available", n)),
+        }
+        for (count, label) in [
+            (report.unused.len(), "unused"),
+            (report.missing.len(), "missing"),
+            (report.ambiguous.len(), "ambiguous"),
+            (report.commented_out.len(), "commented out"),
+            (report.broken_paths.len(), "broken path"),
+        ] {
+            if count > 0 {
+                parts.push(format!("{} {}", count, label));
+            }
+        }
+        if parts.is_empty() {
+            "All dependencies are used and up to date".to_string()
         } else {
-            println!("⚠️ No dependencies found in the {} table", deps_path);
+            parts.join(", ")
+        }
+    }
+
+    /// Table cell text: code-formatted, with `|` escaped so it can't split the row
+    fn markdown_cell(value: Option<&str>) -> String {
+        match value {
+            Some(value) => format!("`{}`", value.replace('|', "\\|")),
+            None => "-".to_string(),
+        }
+    }
+
+    fn render_dependency_report(report: &DependencyReport, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "\nDependency Usage Report")?;
+        writeln!(out, "=====================\n")?;
+
+        if report.dependencies.is_empty() {
+            writeln!(
+                out,
+                "⚠️ No dependencies found in the {} table\n",
+                report.table
+            )?;
+        }
+
+        for dep in &report.dependencies {
+            writeln!(out, "📦 {}", dep.name)?;
+
+            if let Some(version) = &dep.version {
+                writeln!(out, "  Version: {}", version)?;
+                if dep.patched {
+                    writeln!(
+                        out,
+                        "  🩹 Patched via [patch]/[replace]; version check skipped"
+                    )?;
+                } else if let Some(error) = &dep.error {
+                    writeln!(out, "  ⚠️ Failed to check latest version: {}", error)?;
+                } else if let Some(latest) = &dep.latest {
+                    match dep.update_kind {
+                        UpdateKind::Breaking => writeln!(
+                            out,
+                            "  ⚠️ Breaking update available: {} -> {}",
+                            version, latest
+                        )?,
+                        UpdateKind::Compatible => writeln!(
+                            out,
+                            "  ⬆️ Compatible update available: {} -> {} (cargo update)",
+                            version, latest
+                        )?,
+                        UpdateKind::UpToDate => writeln!(out, "  ✅ Up to date")?,
+                    }
+                }
+            }
+
+            if dep.stale
+                && let Some(age) = dep.months_since_publish
+            {
+                writeln!(out, "  🕰️ last published {} months ago", age)?;
+            }
+            if let Some(downloads) = dep.downloads {
+                writeln!(out, "  Downloads: {}", downloads)?;
+            }
+            if let Some(recent) = dep.recent_downloads {
+                writeln!(out, "  Recent downloads: {}", recent)?;
+            }
+            if let Some(published_at) = &dep.published_at {
+                writeln!(out, "  Last published: {}", published_at)?;
+            }
+
+            if dep.usage_count > 0 {
+                writeln!(out, "  Used in {} file(s)", dep.usage_count)?;
+                if let Some(imports) = dep.import_count {
+                    writeln!(out, "  Imported by {} statement(s)", imports)?;
+                }
+                writeln!(out, "  Usage locations:")?;
+                for path in &dep.used_in {
+                    writeln!(out, "    - {}", path)?;
+                }
+            } else if !dep.mentioned_in.is_empty() {
+                writeln!(
+                    out,
+                    "  ⚠️ No import or path detected, but mentioned in (kept):"
+                )?;
+                for path in &dep.mentioned_in {
+                    writeln!(out, "    - {}", path)?;
+                }
+            } else {
+                writeln!(out, "  ⚠️ Warning: No usage detected in the project")?;
+            }
+            writeln!(out)?;
+        }
+
+        if !report.unused.is_empty() {
+            writeln!(out, "Unused dependencies")?;
+            writeln!(out, "===================\n")?;
+            for name in &report.unused {
+                writeln!(out, "  - {}", name)?;
+            }
+            writeln!(
+                out,
+                "\nReview these, then run `cargo autodd` to remove them from [{}].",
+                report.table
+            )?;
+        }
+
+        if !report.low_usage.is_empty() {
+            if !report.unused.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "Low-usage dependencies")?;
+            writeln!(out, "======================\n")?;
+            for name in &report.low_usage {
+                let files = report
+                    .dependencies
+                    .iter()
+                    .find(|dep| &dep.name == name)
+                    .map_or(0, |dep| dep.usage_count);
+                writeln!(out, "  - {} ({} file(s))", name, files)?;
+            }
+            writeln!(
+                out,
+                "\nThese may be worth inlining or replacing with the standard library."
+            )?;
+        }
+
+        if !report.missing.is_empty() {
+            if !report.unused.is_empty() || !report.low_usage.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "Missing dependencies")?;
+            writeln!(out, "====================\n")?;
+            for dep in &report.missing {
+                match &dep.version {
+                    Some(version) => writeln!(out, "  + {} = \"{}\"", dep.name, version)?,
+                    None => writeln!(out, "  + {} (version not resolved)", dep.name)?,
+                }
+            }
+            writeln!(out, "\nRun `cargo autodd` to add them to Cargo.toml.")?;
+        }
+
+        if !report.ambiguous.is_empty() {
+            if !report.unused.is_empty()
+                || !report.low_usage.is_empty()
+                || !report.missing.is_empty()
+            {
+                writeln!(out)?;
+            }
+            writeln!(out, "Ambiguous imports")?;
+            writeln!(out, "=================\n")?;
+            for name in &report.ambiguous {
+                writeln!(out, "  ? {}", name)?;
+            }
+            writeln!(
+                out,
+                "\nNot declared and not on crates.io, so never added; likely local modules."
+            )?;
+        }
+
+        if !report.commented_out.is_empty() {
+            if !report.unused.is_empty()
+                || !report.low_usage.is_empty()
+                || !report.missing.is_empty()
+                || !report.ambiguous.is_empty()
+            {
+                writeln!(out)?;
+            }
+            writeln!(out, "Commented-out dependencies")?;
+            writeln!(out, "==========================\n")?;
+            for entry in &report.commented_out {
+                writeln!(
+                    out,
+                    "  # [{}] {} (line {}): {}",
+                    entry.table, entry.name, entry.line, entry.text
+                )?;
+            }
+            writeln!(
+                out,
+                "\nThe code uses these; uncomment the lines instead of adding new entries."
+            )?;
+        }
+
+        if !report.broken_paths.is_empty() {
+            if !report.unused.is_empty()
+                || !report.low_usage.is_empty()
+                || !report.missing.is_empty()
+                || !report.ambiguous.is_empty()
+                || !report.commented_out.is_empty()
+            {
+                writeln!(out)?;
+            }
+            writeln!(out, "Broken path dependencies")?;
+            writeln!(out, "========================\n")?;
+            for dep in &report.broken_paths {
+                writeln!(
+                    out,
+                    "  ! [{}] {} = {{ path = \"{}\" }}: {}",
+                    dep.table, dep.name, dep.path, dep.problem
+                )?;
+            }
+            writeln!(
+                out,
+                "\nFix or remove these entries; cargo can't build with them."
+            )?;
         }
 
         Ok(())
     }
 
     pub fn generate_security_report(&self) -> Result<()> {
-        println!("\nDependency Security Report");
-        println!("========================\n");
+        let mut stdout = std::io::stdout();
+        self.write_security_report(OutputFormat::Text, &mut stdout)
+    }
 
-        let outdated = self.check_security()?;
+    /// Run the security check and render it to `out` in the given format
+    pub fn write_security_report(&self, format: OutputFormat, out: &mut dyn Write) -> Result<()> {
+        let report = SecurityReport {
+            outdated: self.check_security()?,
+        };
+        match format {
+            OutputFormat::Text => Self::render_security_report(&report, out),
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, &report)?;
+                writeln!(out)?;
+                Ok(())
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+    }
+
+    fn render_security_report(report: &SecurityReport, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "\nDependency Security Report")?;
+        writeln!(out, "========================\n")?;
 
-        if outdated.is_empty() {
-            println!("✅ All dependencies are up to date.");
+        if report.outdated.is_empty() {
+            writeln!(out, "✅ All dependencies are up to date.")?;
             return Ok(());
         }
 
-        println!("⚠️ The following dependencies have updates available:\n");
+        writeln!(
+            out,
+            "⚠️ The following dependencies have updates available:\n"
+        )?;
+
+        for dep in &report.outdated {
+            writeln!(out, "📦 {}", dep.name)?;
+            let kind = match dep.kind {
+                UpdateKind::Breaking => "Breaking",
+                _ => "Compatible",
+            };
+            writeln!(
+                out,
+                "  {} update available: {} -> {}",
+                kind, dep.current, dep.latest
+            )?;
+            writeln!(out)?;
+        }
+
+        writeln!(out, "Note: For a complete security audit, please use:")?;
+        writeln!(out, "  cargo audit")?;
+        writeln!(out, "  https://github.com/rustsec/rustsec\n")?;
+
+        Ok(())
+    }
+
+    /// Check the whole workspace in one pass over its resolved versions (Cargo.lock, or
+    /// `cargo metadata` without one): each crate version the members depend on directly is
+    /// looked up once, and every locked crates.io version is checked against the RustSec
+    /// advisories when `auditor` has a database.
+    ///
+    /// A lookup that still fails after the registry client's retries is recorded in the
+    /// report instead of aborting the scan.
+    pub fn workspace_security_report(
+        &self,
+        auditor: &DependencyAuditor,
+    ) -> Result<WorkspaceSecurityReport> {
+        let workspace = metadata::load(&self.project_root)?;
+        let graph = ResolvedGraph::load(&workspace.workspace_root)?;
+        let member_names = workspace
+            .members
+            .iter()
+            .map(|member| member.name.clone())
+            .collect::<Vec<_>>();
+        let members = graph.member_indices(&member_names);
+        let dependents = graph.dependent_members(&members);
+
+        // (name, locked version) -> members depending on it directly
+        let mut direct = BTreeMap::<(String, Version), BTreeSet<String>>::new();
+        for &member in &members {
+            let member_name = &graph.packages[member].name;
+            for &dep in &graph.packages[member].dependencies {
+                let package = &graph.packages[dep];
+                if package.is_crates_io() {
+                    direct
+                        .entry((package.name.clone(), package.version.clone()))
+                        .or_default()
+                        .insert(member_name.clone());
+                }
+            }
+        }
+
+        let mut report = WorkspaceSecurityReport {
+            members: members
+                .iter()
+                .map(|&i| graph.packages[i].name.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let mut latest_versions = HashMap::new();
+        for ((name, version), dependents) in direct {
+            let latest = latest_versions
+                .entry(name.clone())
+                .or_insert_with(|| self.updater.get_latest_version_from(&name, None));
+            let latest = match latest {
+                Ok(latest) => latest.clone(),
+                Err(e) => {
+                    if !report.failed_lookups.iter().any(|f| f.name == name) {
+                        report.failed_lookups.push(FailedLookup {
+                            name,
+                            error: e.to_string(),
+                        });
+                    }
+                    continue;
+                }
+            };
+            if let Ok(latest_version) = Version::parse(&latest)
+                && let Ok(kind) = self.check_version(&version.to_string(), &latest_version)
+                && kind != UpdateKind::UpToDate
+            {
+                report.outdated.push(ResolvedOutdated {
+                    name,
+                    current: version.to_string(),
+                    latest,
+                    kind,
+                    members: dependents.into_iter().collect(),
+                });
+            }
+        }
+
+        report.advisories_checked = auditor.has_advisory_db();
+        if report.advisories_checked {
+            report.vulnerable = auditor.audit_resolved(&graph, &dependents)?;
+        }
+        Ok(report)
+    }
+
+    /// Run the workspace-wide security check and render it to `out` in the given format
+    pub fn write_workspace_security_report(
+        &self,
+        auditor: &DependencyAuditor,
+        format: OutputFormat,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let report = self.workspace_security_report(auditor)?;
+        match format {
+            OutputFormat::Text => Self::render_workspace_security_report(&report, out),
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, &report)?;
+                writeln!(out)?;
+                Ok(())
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+    }
 
-        for (name, version_info) in outdated {
-            println!("📦 {}", name);
-            println!("  Version update available: {}", version_info);
-            println!();
+    fn render_workspace_security_report(
+        report: &WorkspaceSecurityReport,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        writeln!(out, "\nWorkspace Security Report")?;
+        writeln!(out, "=========================\n")?;
+        writeln!(
+            out,
+            "Scanned {} member(s): {}\n",
+            report.members.len(),
+            report.members.join(", ")
+        )?;
+
+        if !report.vulnerable.is_empty() {
+            writeln!(out, "🚨 Vulnerable crates:")?;
+            for advisory in &report.vulnerable {
+                writeln!(
+                    out,
+                    "  ! {} {}: {} {}",
+                    advisory.name, advisory.version, advisory.id, advisory.summary
+                )?;
+                if !advisory.patched.is_empty() {
+                    writeln!(out, "      patched: {}", advisory.patched.join(", "))?;
+                }
+                writeln!(out, "      affects: {}", advisory.members.join(", "))?;
+            }
+            writeln!(out)?;
+        } else if report.advisories_checked {
+            writeln!(out, "✅ No known vulnerabilities in the resolved crates.\n")?;
         }
 
-        println!("Note: For a complete security audit, please use:");
-        println!("  cargo audit");
-        println!("  https://github.com/rustsec/rustsec\n");
+        if report.outdated.is_empty() {
+            if report.failed_lookups.is_empty() {
+                writeln!(out, "✅ All direct dependencies are up to date.\n")?;
+            }
+        } else {
+            writeln!(out, "⚠️ Direct dependencies with updates available:")?;
+            for dep in &report.outdated {
+                let kind = match dep.kind {
+                    UpdateKind::Breaking => "Breaking",
+                    _ => "Compatible",
+                };
+                writeln!(
+                    out,
+                    "  📦 {} {} -> {} ({}), used by: {}",
+                    dep.name,
+                    dep.current,
+                    dep.latest,
+                    kind,
+                    dep.members.join(", ")
+                )?;
+            }
+            writeln!(out)?;
+        }
+
+        if !report.failed_lookups.is_empty() {
+            writeln!(out, "❓ Could not look up the latest version of:")?;
+            for lookup in &report.failed_lookups {
+                writeln!(out, "  ? {}: {}", lookup.name, lookup.error)?;
+            }
+            writeln!(out)?;
+        }
 
+        if !report.advisories_checked {
+            writeln!(
+                out,
+                "Note: no RustSec advisory database was found, so advisories were not checked."
+            )?;
+            writeln!(
+                out,
+                "  Run `cargo audit fetch` or pass --advisory-db <DIR>.\n"
+            )?;
+        }
         Ok(())
     }
 
-    fn check_security(&self) -> Result<Vec<(String, String)>> {
+    fn check_security(&self) -> Result<Vec<OutdatedDependency>> {
         let content = fs::read_to_string(&self.cargo_toml)?;
         let doc = content.parse::<DocumentMut>()?;
         let mut outdated = Vec::new();
 
-        // Check if this is a workspace or a package
-        let is_workspace = doc.get("workspace").is_some();
+        let patched = patched_crates(&doc);
+        if let Some(deps) = Self::deps_table(&doc, Self::deps_path(&doc)) {
+            for (name, dep) in deps.iter() {
+                if patched.contains(&normalize_package_name(name))
+                    || !self.updater.is_selected(name)
+                {
+                    continue;
+                }
+                if let Some(version) = self.updater.get_dependency_version(dep)
+                    && let Ok(latest) = self
+                        .updater
+                        .get_latest_version_from(name, DependencyUpdater::dependency_registry(dep))
+                    && let Ok(latest_version) = Version::parse(&latest)
+                    && let Ok(kind) = self.check_version(&version, &latest_version)
+                    && kind != UpdateKind::UpToDate
+                {
+                    outdated.push(OutdatedDependency {
+                        name: name.to_string(),
+                        current: version,
+                        latest,
+                        kind,
+                    });
+                }
+            }
+        }
+
+        outdated.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(outdated)
+    }
 
-        // Determine the correct dependencies table (workspace or package)
-        let deps_path = if is_workspace {
+    /// Determine the correct dependencies table (workspace or package)
+    fn deps_path(doc: &DocumentMut) -> &'static str {
+        if doc.get("workspace").is_some() {
             "workspace.dependencies"
         } else {
             "dependencies"
-        };
+        }
+    }
 
-        // Get dependencies from the correct table
-        let deps = if deps_path.contains('.') {
+    /// Get dependencies from the given (possibly dotted) table path
+    fn deps_table<'a>(doc: &'a DocumentMut, deps_path: &str) -> Option<&'a Table> {
+        if deps_path.contains('.') {
             // Handle nested table path like "workspace.dependencies"
             let parts: Vec<&str> = deps_path.split('.').collect();
             doc.get(parts[0])
@@ -152,49 +925,165 @@ impl DependencyReporter {
                 .and_then(|t| t.as_table())
         } else {
             doc.get(deps_path).and_then(|t| t.as_table())
+        }
+    }
+
+    /// Compare the dependency tables of two manifests (`cargo autodd diff`). `old_label`
+    /// and `new_label` say where each was read from.
+    pub fn manifest_diff(
+        &self,
+        old: &DocumentMut,
+        new: &DocumentMut,
+        old_label: &str,
+        new_label: &str,
+    ) -> ManifestDiff {
+        let old_deps = self.declared_versions(old);
+        let new_deps = self.declared_versions(new);
+        let mut diff = ManifestDiff {
+            old: old_label.to_string(),
+            new: new_label.to_string(),
+            ..Default::default()
         };
 
-        if let Some(deps) = deps {
-            for (name, dep) in deps.iter() {
-                if let Some(version) = self.updater.get_dependency_version(dep)
-                    && let Ok(latest) = self.updater.get_latest_version(name)
-                    && let Ok(true) = self.check_version(&version, &latest)
-                {
-                    outdated.push((name.to_string(), format!("{} -> {}", version, latest)));
+        for ((table, name), version) in &new_deps {
+            match old_deps.get(&(table.clone(), name.clone())) {
+                None => diff.added.push(PlannedChange {
+                    table: table.clone(),
+                    name: name.clone(),
+                    version: version.clone(),
+                    path: None,
+                }),
+                Some(old_version) if old_version != version => diff.changed.push(VersionChange {
+                    table: table.clone(),
+                    name: name.clone(),
+                    kind: self.change_kind(old_version.as_deref(), version.as_deref()),
+                    old: old_version.clone(),
+                    new: version.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old_deps
+            .into_iter()
+            .filter(|(key, _)| !new_deps.contains_key(key))
+            .map(|((table, name), version)| PlannedChange {
+                table,
+                name,
+                version,
+                path: None,
+            })
+            .collect();
+        diff
+    }
+
+    /// Version requirement of every dependency, keyed by `(table, name)`. Covers the
+    /// regular tables and their `target.<platform>` counterparts.
+    fn declared_versions(&self, doc: &DocumentMut) -> BTreeMap<(String, String), Option<String>> {
+        let mut tables = DEPENDENCY_TABLES
+            .iter()
+            .filter_map(|path| {
+                let mut item = doc.as_item();
+                for key in path.split('.') {
+                    item = item.get(key)?;
+                }
+                Some((path.to_string(), item.as_table_like()?))
+            })
+            .collect::<Vec<_>>();
+        if let Some(targets) = doc.get("target").and_then(|t| t.as_table_like()) {
+            for (platform, target) in targets.iter() {
+                for kind in ["dependencies", "dev-dependencies", "build-dependencies"] {
+                    if let Some(deps) = target.get(kind).and_then(|t| t.as_table_like()) {
+                        tables.push((format!("target.{}.{}", platform, kind), deps));
+                    }
                 }
             }
         }
 
-        Ok(outdated)
+        let mut versions = BTreeMap::new();
+        for (table, deps) in tables {
+            for (name, dep) in deps.iter() {
+                versions.insert(
+                    (table.clone(), name.to_string()),
+                    self.updater.get_dependency_version(dep),
+                );
+            }
+        }
+        versions
+    }
+
+    /// Classify a requirement change by comparing the new minimum version with the old
+    /// requirement, as `check_version` does for registry releases
+    fn change_kind(&self, old: Option<&str>, new: Option<&str>) -> ChangeKind {
+        let minimum = |req: &str| {
+            VersionReq::parse(req.trim())
+                .ok()
+                .and_then(|req| Self::minimum_version(&req))
+        };
+        let (Some(old), Some(new)) = (old, new) else {
+            return ChangeKind::Other;
+        };
+        let (Some(old_min), Some(new_min)) = (minimum(old), minimum(new)) else {
+            return ChangeKind::Other;
+        };
+        if new_min < old_min {
+            return ChangeKind::Downgrade;
+        }
+        match self.check_version(old, &new_min) {
+            Ok(UpdateKind::Breaking) => ChangeKind::Breaking,
+            Ok(UpdateKind::Compatible) => ChangeKind::Compatible,
+            _ => ChangeKind::Other,
+        }
     }
 
-    pub fn check_version(&self, version: &str, latest: &str) -> Result<bool> {
-        let current = Version::parse(Self::strip_version_prefix(version))?;
-        let latest_ver = Version::parse(Self::strip_version_prefix(latest))?;
-        Ok(latest_ver > current)
-    }
-
-    /// Strip version requirement prefixes (^, ~, =, >=, <=, >, <)
-    fn strip_version_prefix(version: &str) -> &str {
-        let version = version.trim();
-        if version.starts_with(">=") || version.starts_with("<=") {
-            &version[2..]
-        } else if version.starts_with('^')
-            || version.starts_with('~')
-            || version.starts_with('=')
-            || version.starts_with('>')
-            || version.starts_with('<')
-        {
-            &version[1..]
+    /// Classify `latest` against the version requirement `req` (cargo semantics, so a
+    /// bare `1.2` means `^1.2`).
+    ///
+    /// A version outside the requirement but above its lower bounds is a breaking update;
+    /// one the requirement already allows that is newer than its minimum is compatible.
+    pub fn check_version(&self, req: &str, latest: &Version) -> Result<UpdateKind> {
+        let req = VersionReq::parse(req.trim())?;
+        if Self::exceeds_upper_bound(&req, latest) {
+            return Ok(UpdateKind::Breaking);
+        }
+        let newer_than_minimum = Self::minimum_version(&req).is_some_and(|min| *latest > min);
+        if req.matches(latest) && newer_than_minimum {
+            Ok(UpdateKind::Compatible)
         } else {
-            version
+            Ok(UpdateKind::UpToDate)
         }
     }
+
+    /// Whether `version` falls outside `req` because it is too new (it still satisfies every
+    /// lower bound)
+    fn exceeds_upper_bound(req: &VersionReq, version: &Version) -> bool {
+        !req.matches(version)
+            && Self::minimum_version(req).is_none_or(|min| *version >= min)
+            && req
+                .comparators
+                .iter()
+                .filter(|c| matches!(c.op, Op::Greater | Op::GreaterEq))
+                .all(|c| c.matches(version))
+    }
+
+    /// Lowest version named by the requirement's lower bounds (`1.2` for `^1.2`,
+    /// `>=1.2, <2`), or `None` if it has no lower bound (`*`, `<2`)
+    pub(crate) fn minimum_version(req: &VersionReq) -> Option<Version> {
+        req.comparators
+            .iter()
+            .filter(|c| !matches!(c.op, Op::Less | Op::LessEq))
+            .map(|c| {
+                let mut version = Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+                version.pre = c.pre.clone();
+                version
+            })
+            .max()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::BrokenPathDependency;
     use std::fs::File;
     use std::io::Write;
     use tempfile::TempDir;
@@ -266,95 +1155,701 @@ tokio = "1.0"
     }
 
     #[test]
-    fn test_generate_security_report() -> Result<()> {
+    fn test_write_dependency_report_json() -> Result<()> {
         let (temp_dir, _) = create_test_environment()?;
         let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
-        reporter.generate_security_report()?;
+
+        let mut crate_refs = HashMap::new();
+        let mut serde_ref = CrateReference::new("serde".to_string());
+        serde_ref.add_usage(temp_dir.path().join("src/main.rs"));
+        crate_refs.insert("serde".to_string(), serde_ref);
+
+        let mut out = Vec::new();
+        reporter.write_dependency_report(&crate_refs, OutputFormat::Json, &mut out)?;
+
+        let json: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(json["table"], "dependencies");
+        let deps = json["dependencies"].as_array().unwrap();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0]["name"], "serde");
+        assert_eq!(deps[0]["usage_count"], 1);
+        assert_eq!(deps[0]["used_in"][0], "src/main.rs");
         Ok(())
     }
 
     #[test]
-    fn test_generate_workspace_security_report() -> Result<()> {
-        let (temp_dir, _) = create_workspace_test_environment()?;
-        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
-        reporter.generate_security_report()?;
+    fn test_report_lists_unused_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+regex = "1.0"
+tracing-subscriber = "0.3"
+local = { path = "../local" }
+forked = { git = "https://example.com/forked.git" }
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.set_offline(true);
+
+        let mut crate_refs = HashMap::new();
+        let mut subscriber = CrateReference::new("tracing_subscriber".to_string());
+        subscriber.add_usage(temp_dir.path().join("src/main.rs"));
+        crate_refs.insert("tracing_subscriber".to_string(), subscriber);
+
+        let report = reporter.build_dependency_report(&crate_refs)?;
+        assert_eq!(report.unused, vec!["regex"]);
+
+        let mut out = Vec::new();
+        reporter.write_dependency_report(&crate_refs, OutputFormat::Json, &mut out)?;
+        let json: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(json["unused"], serde_json::json!(["regex"]));
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("Unused dependencies\n===================\n\n  - regex\n"));
         Ok(())
     }
 
     #[test]
-    fn test_check_version_update_available() -> Result<()> {
-        let (temp_dir, _) = create_test_environment()?;
-        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+    fn test_report_min_usage_and_mentions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
 
-        // Newer version available
-        assert!(reporter.check_version("1.0.0", "1.1.0")?);
-        assert!(reporter.check_version("1.0.0", "2.0.0")?);
-        assert!(reporter.check_version("1.0.0", "1.0.1")?);
+[dependencies]
+serde = "1.0"
+regex = "1.0"
+anyhow = "1.0"
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.set_offline(true);
+        reporter.set_min_usage(Some(1));
 
+        let mut crate_refs = HashMap::new();
+        let mut serde = CrateReference::new("serde".to_string());
+        serde
+            .mentioned_in
+            .insert(temp_dir.path().join("src/main.rs"));
+        crate_refs.insert("serde".to_string(), serde);
+        let mut regex = CrateReference::new("regex".to_string());
+        regex.add_usage(temp_dir.path().join("src/main.rs"));
+        crate_refs.insert("regex".to_string(), regex);
+        let mut anyhow = CrateReference::new("anyhow".to_string());
+        anyhow.add_usage(temp_dir.path().join("src/main.rs"));
+        anyhow.add_usage(temp_dir.path().join("src/cli.rs"));
+        crate_refs.insert("anyhow".to_string(), anyhow);
+
+        let report = reporter.build_dependency_report(&crate_refs)?;
+        assert!(report.unused.is_empty(), "{:?}", report.unused);
+        assert_eq!(report.low_usage, ["regex"]);
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(
+            text.contains("mentioned in (kept):\n    - src/main.rs\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains(
+                "Low-usage dependencies\n======================\n\n  - regex (1 file(s))\n"
+            )
+        );
         Ok(())
     }
 
     #[test]
-    fn test_check_version_up_to_date() -> Result<()> {
-        let (temp_dir, _) = create_test_environment()?;
-        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+    fn test_report_lists_missing_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
 
-        // Same version
-        assert!(!reporter.check_version("1.0.0", "1.0.0")?);
+[dependencies]
+tracing-subscriber = "0.3"
+
+[dev-dependencies]
+rstest = "0.18"
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.set_offline(true);
+        reporter.set_config(Config {
+            exclude: ["internal".to_string()].into(),
+            ..Default::default()
+        });
+
+        let main_rs = temp_dir.path().join("src/main.rs");
+        let mut crate_refs = HashMap::new();
+        for name in ["tracing_subscriber", "rstest", "internal", "regex", "rayon"] {
+            let mut crate_ref = CrateReference::new(name.to_string());
+            crate_ref.add_usage(main_rs.clone());
+            crate_refs.insert(name.to_string(), crate_ref);
+        }
+        crate_refs.get_mut("regex").unwrap().version = Some("1.10".to_string());
+
+        let report = reporter.build_dependency_report(&crate_refs)?;
+        assert_eq!(
+            report.missing,
+            vec![
+                MissingDependency {
+                    name: "rayon".to_string(),
+                    version: None,
+                },
+                MissingDependency {
+                    name: "regex".to_string(),
+                    version: Some("1.10".to_string()),
+                },
+            ]
+        );
+
+        let mut out = Vec::new();
+        reporter.write_dependency_report(&crate_refs, OutputFormat::Json, &mut out)?;
+        let json: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(
+            json["missing"],
+            serde_json::json!([
+                { "name": "rayon", "version": null },
+                { "name": "regex", "version": "1.10" },
+            ])
+        );
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains(
+            "Missing dependencies\n====================\n\n  + rayon (version not resolved)\n  + regex = \"1.10\"\n"
+        ));
+        Ok(())
+    }
 
-        // Current is newer (shouldn't happen in practice but test the logic)
-        assert!(!reporter.check_version("2.0.0", "1.0.0")?);
-        assert!(!reporter.check_version("1.1.0", "1.0.0")?);
+    #[test]
+    fn test_report_is_sorted_by_name() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
 
+[dependencies]
+zstd = "0.13"
+serde = "1.0"
+regex = "1.10"
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.set_offline(true);
+
+        let mut crate_refs = HashMap::new();
+        let mut serde_ref = CrateReference::new("serde".to_string());
+        for file in ["src/z.rs", "src/main.rs", "src/b/mod.rs", "src/a.rs"] {
+            serde_ref.add_usage(temp_dir.path().join(file));
+        }
+        crate_refs.insert("serde".to_string(), serde_ref);
+
+        let report = reporter.build_dependency_report(&crate_refs)?;
+        let names = report
+            .dependencies
+            .iter()
+            .map(|dep| dep.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["regex", "serde", "zstd"]);
+        assert_eq!(report.unused, ["regex", "zstd"]);
+        assert_eq!(
+            report.dependencies[1].used_in,
+            ["src/a.rs", "src/b/mod.rs", "src/main.rs", "src/z.rs"]
+        );
         Ok(())
     }
 
     #[test]
-    fn test_check_version_with_caret_prefix() -> Result<()> {
-        let (temp_dir, _) = create_test_environment()?;
-        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+    fn test_report_lists_broken_path_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("crates/core"))?;
+        fs::write(
+            temp_dir.path().join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core-lib\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("crates/empty"))?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "test-package"
+version = "0.1.0"
+
+[dependencies]
+core-lib = { path = "crates/core" }
+renamed = { path = "crates/old-name" }
+
+[dev-dependencies]
+fixtures = { path = "crates/empty" }
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.set_offline(true);
+
+        let report = reporter.build_dependency_report(&HashMap::new())?;
+        assert_eq!(
+            report.broken_paths,
+            vec![
+                BrokenPathDependency {
+                    table: "dependencies".to_string(),
+                    name: "renamed".to_string(),
+                    path: "crates/old-name".to_string(),
+                    problem: "directory does not exist".to_string(),
+                },
+                BrokenPathDependency {
+                    table: "dev-dependencies".to_string(),
+                    name: "fixtures".to_string(),
+                    path: "crates/empty".to_string(),
+                    problem: "directory has no Cargo.toml".to_string(),
+                },
+            ]
+        );
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains(
+            "  ! [dependencies] renamed = { path = \"crates/old-name\" }: directory does not exist\n"
+        ));
+        Ok(())
+    }
 
-        // Caret prefix should be stripped
-        assert!(reporter.check_version("^1.0.0", "1.1.0")?);
-        assert!(reporter.check_version("^1.0.0", "^1.1.0")?);
-        assert!(!reporter.check_version("^1.0.0", "^1.0.0")?);
+    #[test]
+    fn test_render_stale_marker() -> Result<()> {
+        let report = DependencyReport {
+            table: "dependencies".to_string(),
+            dependencies: vec![
+                DependencyStatus {
+                    name: "old".to_string(),
+                    version: Some("0.3".to_string()),
+                    months_since_publish: Some(40),
+                    stale: true,
+                    ..Default::default()
+                },
+                DependencyStatus {
+                    name: "fresh".to_string(),
+                    version: Some("1.0".to_string()),
+                    months_since_publish: Some(2),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
 
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("🕰️ last published 40 months ago"));
+        assert_eq!(text.matches("🕰️").count(), 1);
         Ok(())
     }
 
     #[test]
-    fn test_check_version_with_tilde_prefix() -> Result<()> {
+    fn test_render_ambiguous_imports() -> Result<()> {
+        let report = DependencyReport {
+            table: "dependencies".to_string(),
+            ambiguous: vec!["widgets".to_string()],
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("Ambiguous imports\n=================\n\n  ? widgets\n"));
+
+        let mut out = Vec::new();
+        DependencyReporter::render_markdown_report(&report, &mut out)?;
+        let markdown = String::from_utf8(out)?;
+        assert!(markdown.contains("**1 ambiguous**"));
+        assert!(markdown.contains("### Ambiguous imports\n"));
+        assert_eq!(
+            serde_json::to_value(&report)?["ambiguous"],
+            serde_json::json!(["widgets"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_markdown_table() -> Result<()> {
+        let report = DependencyReport {
+            table: "dependencies".to_string(),
+            dependencies: vec![
+                DependencyStatus {
+                    name: "local".to_string(),
+                    usage_count: 1,
+                    ..Default::default()
+                },
+                DependencyStatus {
+                    name: "regex".to_string(),
+                    version: Some(">=1.0, <1.5 | 2".to_string()),
+                    latest: Some("1.11.1".to_string()),
+                    update_available: true,
+                    update_kind: UpdateKind::Breaking,
+                    ..Default::default()
+                },
+                DependencyStatus {
+                    name: "serde".to_string(),
+                    version: Some("1.0".to_string()),
+                    latest: Some("1.0.217".to_string()),
+                    update_available: true,
+                    update_kind: UpdateKind::Compatible,
+                    usage_count: 3,
+                    ..Default::default()
+                },
+            ],
+            unused: vec!["regex".to_string()],
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        DependencyReporter::render_markdown_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("**2 updates available, 1 unused**"));
+
+        // Header, delimiter and one row per dependency, all with the same number of cells
+        // once escaped pipes are discounted
+        let rows = text
+            .lines()
+            .filter(|line| line.starts_with('|'))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2 + report.dependencies.len());
+        assert_eq!(rows[0], "| Status | Crate | Version | Latest | Used in |");
+        assert_eq!(rows[1], "| :---: | --- | --- | --- | ---: |");
+        for row in &rows {
+            assert!(row.ends_with(" |"), "{}", row);
+            assert_eq!(row.replace("\\|", "").matches('|').count(), 6, "{}", row);
+        }
+
+        assert_eq!(rows[2], "| ✅ | local | - | - | 1 file |");
+        assert_eq!(
+            rows[3],
+            "| ⚠️ 🗑️ | [regex](https://crates.io/crates/regex) | `>=1.0, <1.5 \\| 2` | `1.11.1` | - |"
+        );
+        assert_eq!(
+            rows[4],
+            "| ⬆️ | [serde](https://crates.io/crates/serde) | `1.0` | `1.0.217` | 3 files |"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_verbose_fields() -> Result<()> {
+        let report = DependencyReport {
+            table: "dependencies".to_string(),
+            dependencies: vec![
+                DependencyStatus {
+                    name: "serde".to_string(),
+                    version: Some("1.0".to_string()),
+                    downloads: Some(1000),
+                    recent_downloads: Some(10),
+                    published_at: Some("2024-01-01T00:00:00Z".to_string()),
+                    usage_count: 1,
+                    used_in: vec!["src/main.rs".to_string()],
+                    import_count: Some(3),
+                    ..Default::default()
+                },
+                DependencyStatus {
+                    name: "private".to_string(),
+                    version: Some("0.1".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("Downloads: 1000"));
+        assert!(text.contains("Recent downloads: 10"));
+        assert!(text.contains("Last published: 2024-01-01T00:00:00Z"));
+        assert!(text.contains("Imported by 3 statement(s)"));
+        assert_eq!(text.matches("Downloads:").count(), 1);
+
+        let json = serde_json::to_value(&report.dependencies[1])?;
+        assert!(json.get("downloads").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_patched_dependency_skips_version_check() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"
+[package]
+name = "test-package"
+version = "0.1.0"
+
+[dependencies]
+regex = "1.0"
+
+[replace]
+"regex:1.0.0" = { path = "../regex" }
+"#,
+        )?;
+        let mut reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        // Any registry lookup would fail and show up as an error
+        reporter.set_offline(true);
+
+        let report = reporter.build_dependency_report(&HashMap::new())?;
+        assert!(report.dependencies[0].patched);
+        assert!(report.dependencies[0].error.is_none());
+        assert!(reporter.check_security()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_security_report() -> Result<()> {
         let (temp_dir, _) = create_test_environment()?;
         let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.generate_security_report()?;
+        Ok(())
+    }
 
-        // Tilde prefix should be stripped
-        assert!(reporter.check_version("~1.0.0", "1.1.0")?);
-        assert!(!reporter.check_version("~1.0.0", "~1.0.0")?);
+    #[test]
+    fn test_generate_workspace_security_report() -> Result<()> {
+        let (temp_dir, _) = create_workspace_test_environment()?;
+        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.generate_security_report()?;
+        Ok(())
+    }
 
+    #[test]
+    fn test_workspace_security_report_dedupes_across_members() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"core\"]\n",
+        )?;
+        for member in ["app", "core"] {
+            fs::create_dir_all(root.join(member).join("src"))?;
+            fs::write(
+                root.join(member).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", member),
+            )?;
+            fs::write(root.join(member).join("src/lib.rs"), "")?;
+        }
+        let crates_io = "registry+https://github.com/rust-lang/crates.io-index";
+        fs::write(
+            root.join("Cargo.lock"),
+            format!(
+                "version = 3\n\n\
+                 [[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"core\", \"rand\"]\n\n\
+                 [[package]]\nname = \"core\"\nversion = \"0.1.0\"\ndependencies = [\"rand\"]\n\n\
+                 [[package]]\nname = \"rand\"\nversion = \"0.7.3\"\nsource = \"{}\"\n",
+                crates_io
+            ),
+        )?;
+
+        let advisory_db = root.join("advisory-db");
+        fs::create_dir_all(advisory_db.join("crates/rand"))?;
+        fs::write(
+            advisory_db.join("crates/rand/RUSTSEC-2099-0001.toml"),
+            "[advisory]\nid = \"RUSTSEC-2099-0001\"\npackage = \"rand\"\n\
+             title = \"Predictable output\"\n\n[versions]\npatched = [\">= 0.8.0\"]\n",
+        )?;
+        let mut auditor = DependencyAuditor::new(root.to_path_buf());
+        auditor.set_advisory_db(advisory_db);
+
+        let mut reporter = DependencyReporter::new(root.to_path_buf());
+        reporter.set_offline(true);
+        let report = reporter.workspace_security_report(&auditor)?;
+
+        assert_eq!(report.members, ["app", "core"]);
+        assert!(report.advisories_checked);
+        assert_eq!(report.vulnerable.len(), 1, "reported once for both members");
+        assert_eq!(report.vulnerable[0].id, "RUSTSEC-2099-0001");
+        assert_eq!(report.vulnerable[0].version, "0.7.3");
+        assert_eq!(report.vulnerable[0].members, ["app", "core"]);
+        // Offline, the single lookup for rand fails and is recorded, not fatal
+        assert_eq!(report.failed_lookups.len(), 1);
+        assert_eq!(report.failed_lookups[0].name, "rand");
+
+        let mut out = Vec::new();
+        DependencyReporter::render_workspace_security_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("! rand 0.7.3: RUSTSEC-2099-0001 Predictable output"));
+        assert!(text.contains("affects: app, core"));
         Ok(())
     }
 
     #[test]
-    fn test_check_version_with_comparison_prefixes() -> Result<()> {
-        let (temp_dir, _) = create_test_environment()?;
+    fn test_manifest_diff_classifies_changes() -> Result<()> {
+        let old = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+rand = "0.7"
+log = "0.4.20"
+local = { path = "../local" }
+regex = "1"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+"#
+        .parse::<DocumentMut>()?;
+        let new = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0.200", features = ["derive"] }
+rand = "0.8"
+log = "0.4.1"
+local = { path = "../local", version = "0.1" }
+regex = "1"
+anyhow = "1.0"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+"#
+        .parse::<DocumentMut>()?;
+
+        let temp_dir = TempDir::new()?;
         let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        let diff = reporter.manifest_diff(&old, &new, "HEAD:./Cargo.toml", "Cargo.toml");
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "anyhow");
+        assert_eq!(diff.added[0].version.as_deref(), Some("1.0"));
+        assert!(diff.removed.is_empty());
+        let kinds = diff
+            .changed
+            .iter()
+            .map(|change| (change.name.as_str(), change.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("local", ChangeKind::Other),
+                ("log", ChangeKind::Downgrade),
+                ("rand", ChangeKind::Breaking),
+                ("serde", ChangeKind::Compatible),
+            ]
+        );
+
+        let reverse = reporter.manifest_diff(&new, &old, "Cargo.toml", "HEAD:./Cargo.toml");
+        assert_eq!(reverse.removed[0].name, "anyhow");
+        let text = reverse.to_string();
+        assert!(text.contains("  - [dependencies] anyhow\n"));
+        assert!(text.contains("  ~ [dependencies] local: 0.1 -> -\n"));
+        assert!(text.contains("  ~ [dependencies] rand: 0.8 -> 0.7 (downgrade)\n"));
+        Ok(())
+    }
+
+    fn kind(req: &str, latest: &str) -> Result<UpdateKind> {
+        let temp_dir = TempDir::new()?;
+        let reporter = DependencyReporter::new(temp_dir.path().to_path_buf());
+        reporter.check_version(req, &Version::parse(latest)?)
+    }
 
-        // Various comparison prefixes should be stripped
-        assert!(reporter.check_version("=1.0.0", "1.1.0")?);
-        assert!(reporter.check_version(">=1.0.0", "1.1.0")?);
-        assert!(reporter.check_version("<=1.0.0", "1.1.0")?);
-        assert!(reporter.check_version(">1.0.0", "1.1.0")?);
-        assert!(reporter.check_version("<1.0.0", "1.1.0")?);
+    #[test]
+    fn test_check_version_exact_and_bare_requirements() -> Result<()> {
+        // A bare version is a caret requirement
+        assert_eq!(kind("1.0.0", "1.0.0")?, UpdateKind::UpToDate);
+        assert_eq!(kind("1.0.0", "1.0.1")?, UpdateKind::Compatible);
+        assert_eq!(kind("1.0.0", "1.1.0")?, UpdateKind::Compatible);
+        assert_eq!(kind("1.0.0", "2.0.0")?, UpdateKind::Breaking);
+        assert_eq!(kind("1.2", "1.2.0")?, UpdateKind::UpToDate);
+        assert_eq!(kind("0.3", "0.3.9")?, UpdateKind::Compatible);
+        assert_eq!(kind("0.3", "0.4.0")?, UpdateKind::Breaking);
+
+        // Latest older than the requirement (e.g. the required release was yanked)
+        assert_eq!(kind("2.0.0", "1.0.0")?, UpdateKind::UpToDate);
+        assert_eq!(kind("1.1.0", "1.0.0")?, UpdateKind::UpToDate);
+        Ok(())
+    }
 
+    #[test]
+    fn test_check_version_with_caret_prefix() -> Result<()> {
+        assert_eq!(kind("^1.0.0", "1.0.0")?, UpdateKind::UpToDate);
+        assert_eq!(kind("^1.2", "1.9.0")?, UpdateKind::Compatible);
+        assert_eq!(kind("^1.2", "2.0.0")?, UpdateKind::Breaking);
+        assert_eq!(kind("^0.0.3", "0.0.4")?, UpdateKind::Breaking);
         Ok(())
     }
 
     #[test]
-    fn test_strip_version_prefix() {
-        // Test the private helper function behavior through check_version
-        // This test verifies that all prefixes are correctly handled
+    fn test_check_version_with_tilde_prefix() -> Result<()> {
+        assert_eq!(kind("~1.0.0", "1.0.5")?, UpdateKind::Compatible);
+        assert_eq!(kind("~1.0.0", "1.1.0")?, UpdateKind::Breaking);
+        assert_eq!(kind("~1.0.0", "1.0.0")?, UpdateKind::UpToDate);
+        Ok(())
+    }
 
-        // The function is private, so we test it indirectly
-        // through the check_version method which uses it
+    #[test]
+    fn test_check_version_with_comparison_prefixes() -> Result<()> {
+        assert_eq!(kind("=1.0.0", "1.0.0")?, UpdateKind::UpToDate);
+        assert_eq!(kind("=1.0.0", "1.1.0")?, UpdateKind::Breaking);
+        assert_eq!(kind(">=1.0.0", "1.1.0")?, UpdateKind::Compatible);
+        assert_eq!(kind(">1.0.0", "3.0.0")?, UpdateKind::Compatible);
+        assert_eq!(kind("<=1.0.0", "1.1.0")?, UpdateKind::Breaking);
+        assert_eq!(kind("<1.0.0", "1.1.0")?, UpdateKind::Breaking);
+        assert_eq!(kind("*", "5.0.0")?, UpdateKind::UpToDate);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_version_with_multiple_bounds() -> Result<()> {
+        // Latest is within the range but newer than its minimum
+        assert_eq!(kind(">=1.2, <2.0", "1.9.3")?, UpdateKind::Compatible);
+        // Latest is past the upper bound
+        assert_eq!(kind(">=1.2, <2.0", "2.0.0")?, UpdateKind::Breaking);
+        assert_eq!(kind(">= 1.2.0, <= 1.4.0", "1.5.0")?, UpdateKind::Breaking);
+        // Latest is below the lower bound
+        assert_eq!(kind(">=1.2, <2.0", "1.1.0")?, UpdateKind::UpToDate);
+        assert!(kind("not a requirement", "1.0.0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_distinguishes_update_kinds() -> Result<()> {
+        let status = |name: &str, latest: &str, update_kind| DependencyStatus {
+            name: name.to_string(),
+            version: Some("1.2".to_string()),
+            latest: Some(latest.to_string()),
+            update_available: update_kind != UpdateKind::UpToDate,
+            update_kind,
+            ..Default::default()
+        };
+        let report = DependencyReport {
+            table: "dependencies".to_string(),
+            dependencies: vec![
+                status("a", "1.5.0", UpdateKind::Compatible),
+                status("b", "2.0.0", UpdateKind::Breaking),
+            ],
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        DependencyReporter::render_dependency_report(&report, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("⬆️ Compatible update available: 1.2 -> 1.5.0"));
+        assert!(text.contains("⚠️ Breaking update available: 1.2 -> 2.0.0"));
+
+        let json = serde_json::to_value(&report.dependencies[1])?;
+        assert_eq!(json["update_kind"], "breaking");
+        Ok(())
     }
 }
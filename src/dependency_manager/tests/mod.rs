@@ -1 +1,2 @@
+pub(crate) mod mock_registry;
 mod monorepo_tests;
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Errors returned by cargo-autodd's core operations.
+///
+/// Library consumers can match on the variant to tell, for example, a crate that doesn't
+/// exist on the registry apart from a network failure.
+#[derive(Debug, Error)]
+pub enum AutoddError {
+    /// The registry could not be reached or returned an unexpected status
+    #[error("Failed to fetch {url}: {message}")]
+    NetworkError { url: String, message: String },
+
+    /// The registry kept answering HTTP 429 after all retries
+    #[error("Rate limited by the registry after {retries} retries: {url}")]
+    RateLimited { url: String, retries: u32 },
+
+    /// Network access was needed but is disabled (`--offline` or `--frozen`)
+    #[error("Cannot look up {0} on the registry: network access is disabled")]
+    Offline(String),
+
+    /// A registry response or manifest could not be parsed
+    #[error("Failed to parse {context}: {message}")]
+    ParseError { context: String, message: String },
+
+    /// The crate does not exist on the registry
+    #[error("Crate {0} was not found on the registry")]
+    NotFound(String),
+
+    /// The crate exists on the registry but has no usable (non-yanked, semver) version
+    #[error("No valid versions found for {0}")]
+    NoVersions(String),
+
+    /// A dependency names a registry whose sparse index isn't configured
+    #[error(
+        "Registry {0} has no sparse index configured (set CARGO_REGISTRIES_<NAME>_INDEX or [registries] in .cargo/config.toml)"
+    )]
+    UnknownRegistry(String),
+
+    /// The crate is a workspace member that isn't published to the registry
+    #[error("{0} is an internal crate not published on crates.io")]
+    InternalCrate(String),
+
+    /// A dependency table key holds something other than a table (`dependencies = "1"`)
+    #[error("`{table}` in {manifest:?} must be a table, found {found}")]
+    InvalidTable {
+        manifest: PathBuf,
+        table: String,
+        found: String,
+    },
+
+    /// `cargo check` failed after Cargo.toml was modified. `suspects` are the added or
+    /// updated crates its errors mention; `output` is cargo's captured stderr.
+    #[error(
+        "cargo check failed after updating Cargo.toml{}\n{output}",
+        suspects_hint(.suspects)
+    )]
+    VerificationFailed {
+        suspects: Vec<String>,
+        output: String,
+    },
+
+    /// No Cargo.toml exists where one was expected
+    #[error("Cargo.toml not found at {0:?}")]
+    ManifestNotFound(PathBuf),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn suspects_hint(suspects: &[String]) -> String {
+    if suspects.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "; the added or updated version of {} may be incompatible",
+            suspects.join(", ")
+        )
+    }
+}
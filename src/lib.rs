@@ -1,138 +1,526 @@
+pub mod cache;
+pub mod changelog;
 pub mod config;
 pub mod dependency_manager;
+pub mod error;
+pub mod lockfile;
+pub mod metadata;
 pub mod models;
+pub mod output;
+pub mod sarif;
 pub mod utils;
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::Result;
-pub use config::Config;
+use anyhow::{Context, Result};
+pub use cache::Cache;
+pub use config::{Config, CrateClass, Rule, VersionFormat};
+pub use error::AutoddError;
+pub use output::{OutputFormat, ReportWriter};
+
+/// Verbosity: errors only (`-q`)
+pub const QUIET: u8 = 0;
+/// Verbosity: progress and summaries (the default)
+pub const NORMAL: u8 = 1;
+/// Verbosity: per-crate detail (`-v`)
+pub const VERBOSE: u8 = 2;
+/// Verbosity: analysis internals (`-vv` or `--debug`)
+pub const DEBUG: u8 = 3;
+
+/// Receives analysis results and the changes written to Cargo.toml, so a host application
+/// can present them itself instead of parsing the printed output.
+///
+/// Every method has a no-op default; implement only the events you need.
+pub trait AutoddObserver: Send + Sync {
+    /// A crate the code uses, after config exclusions
+    fn on_crate_found(&self, _crate_ref: &models::CrateReference) {}
+
+    /// The version a new dependency will be written with was looked up on its registry
+    fn on_version_resolved(&self, _name: &str, _version: &str) {}
+
+    /// A dependency was added to Cargo.toml
+    fn on_add(&self, _change: &models::PlannedChange) {}
+
+    /// An unused dependency was removed from Cargo.toml
+    fn on_remove(&self, _change: &models::PlannedChange) {}
+
+    /// A declared version requirement was raised in Cargo.toml
+    fn on_update(&self, _update: &models::VersionUpdate) {}
+}
 
 pub struct CargoAutodd {
-    #[allow(dead_code)]
     project_root: PathBuf,
     analyzer: dependency_manager::DependencyAnalyzer,
     updater: dependency_manager::DependencyUpdater,
     reporter: dependency_manager::DependencyReporter,
     config: Config,
     debug: bool,
+    verbosity: u8,
     dry_run: bool,
+    output: Option<PathBuf>,
+    format: Option<OutputFormat>,
+    assume_yes: bool,
+    observer: Option<Arc<dyn AutoddObserver>>,
+    changelog: Option<PathBuf>,
+    advisory_db: Option<PathBuf>,
+    dump_plan: Option<PathBuf>,
+    rollback: bool,
+    /// Whether `--only` restricts the run to some crates
+    filtered: bool,
 }
 
 impl CargoAutodd {
     pub fn new(project_root: PathBuf) -> Self {
-        let config = Config::load_default(&project_root).unwrap_or_default();
-        Self {
-            project_root: project_root.clone(),
-            analyzer: dependency_manager::DependencyAnalyzer::new(project_root.clone()),
-            updater: dependency_manager::DependencyUpdater::new(project_root.clone()),
-            reporter: dependency_manager::DependencyReporter::new(project_root),
-            config,
-            debug: false,
-            dry_run: false,
-        }
+        Self::with_debug(project_root, false)
     }
 
     pub fn with_debug(project_root: PathBuf, debug: bool) -> Self {
         let config = Config::load_default(&project_root).unwrap_or_default();
-        Self {
-            project_root: project_root.clone(),
-            analyzer: dependency_manager::DependencyAnalyzer::with_debug(
-                project_root.clone(),
-                debug,
-            ),
-            updater: dependency_manager::DependencyUpdater::new(project_root.clone()),
-            reporter: dependency_manager::DependencyReporter::new(project_root),
-            config,
-            debug,
-            dry_run: false,
-        }
+        Self::with_options(project_root, debug, false, config)
     }
 
     pub fn with_options(project_root: PathBuf, debug: bool, dry_run: bool, config: Config) -> Self {
+        let mut updater = dependency_manager::DependencyUpdater::new(project_root.clone());
+        updater.set_config(config.clone());
+        let mut analyzer =
+            dependency_manager::DependencyAnalyzer::with_debug(project_root.clone(), debug);
+        analyzer.set_config(config.clone());
+        let mut reporter = dependency_manager::DependencyReporter::new(project_root.clone());
+        reporter.set_config(config.clone());
         Self {
             project_root: project_root.clone(),
-            analyzer: dependency_manager::DependencyAnalyzer::with_debug(
-                project_root.clone(),
-                debug,
-            ),
-            updater: dependency_manager::DependencyUpdater::new(project_root.clone()),
-            reporter: dependency_manager::DependencyReporter::new(project_root),
+            analyzer,
+            updater,
+            reporter,
             config,
             debug,
+            verbosity: if debug { DEBUG } else { NORMAL },
             dry_run,
+            output: None,
+            format: None,
+            assume_yes: false,
+            observer: None,
+            changelog: None,
+            advisory_db: None,
+            dump_plan: None,
+            rollback: true,
+            filtered: false,
+        }
+    }
+
+    /// Append a record of every change written to Cargo.toml to `path` (`--changelog`)
+    pub fn set_changelog(&mut self, path: Option<PathBuf>) {
+        self.changelog = path;
+    }
+
+    /// Write the computed plan as JSON to `path` instead of applying it (`--dump-plan`)
+    pub fn set_dump_plan(&mut self, path: Option<PathBuf>) {
+        self.dump_plan = path;
+    }
+
+    /// Write `plan` to the `--dump-plan` file, if one was requested
+    fn write_plan(&self, plan: &models::UpdatePlan) -> Result<()> {
+        let Some(path) = &self.dump_plan else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(plan)?;
+        utils::write_atomic(path, format!("{}\n", json).as_bytes())?;
+        self.status(format_args!(
+            "📝 Wrote the change plan to {}",
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// Restore Cargo.toml when `cargo check` fails after an update (the default);
+    /// `false` keeps the changes for inspection (`--no-rollback`)
+    pub fn set_rollback(&mut self, rollback: bool) {
+        self.rollback = rollback;
+    }
+
+    /// Check the manifest written from `original` with `cargo check`, then record the
+    /// applied changes.
+    ///
+    /// A failure only counts against the rewrite if cargo's errors name a changed crate or
+    /// `original` still passes the check; then `original` is restored unless rollback is
+    /// disabled, and nothing is recorded. A project that didn't build before is only
+    /// warned about.
+    fn verify_applied(&self, original: &str, applied: &models::UpdatePlan) -> Result<()> {
+        if !applied.edits_manifest() {
+            return Ok(());
+        }
+        self.status("\n🔍 Verifying dependencies...");
+        let Err(error) = self.updater.verify_dependencies(applied) else {
+            return self.record_changes(applied);
+        };
+
+        let manifest = self.project_root.join("Cargo.toml");
+        let rewritten = std::fs::read_to_string(&manifest)?;
+        utils::write_atomic(&manifest, original.as_bytes())?;
+        if self.caused_by_rewrite(&error) {
+            if self.rollback {
+                return Err(error).context(
+                    "Restored the previous Cargo.toml (rerun with --no-rollback to keep the changes)",
+                );
+            }
+            utils::write_atomic(&manifest, rewritten.as_bytes())?;
+            self.record_changes(applied)?;
+            return Err(error);
+        }
+
+        utils::write_atomic(&manifest, rewritten.as_bytes())?;
+        self.progress(format_args!(
+            "⚠️ The project doesn't build, but it didn't before the update either: {:#}",
+            error
+        ));
+        self.record_changes(applied)
+    }
+
+    /// Whether a failed check of the rewritten manifest is the rewrite's fault: cargo's
+    /// errors name an added or updated crate, or the previous manifest (restored while
+    /// this runs) passes the check
+    fn caused_by_rewrite(&self, error: &anyhow::Error) -> bool {
+        let names_changed_crate = matches!(
+            error.downcast_ref::<error::AutoddError>(),
+            Some(error::AutoddError::VerificationFailed { suspects, .. }) if !suspects.is_empty()
+        );
+        names_changed_crate
+            || self
+                .updater
+                .verify_dependencies(&models::UpdatePlan::default())
+                .is_ok()
+    }
+
+    /// Read RustSec advisories from `path` instead of `$CARGO_HOME/advisory-db` (`audit`)
+    pub fn set_advisory_db(&mut self, path: Option<PathBuf>) {
+        self.advisory_db = path;
+    }
+
+    /// Append the applied changes to the changelog, if one was requested and anything changed
+    fn record_changes(&self, applied: &models::UpdatePlan) -> Result<()> {
+        match &self.changelog {
+            Some(path) if !applied.is_empty() => {
+                changelog::ChangelogEntry::new(&self.project_root, applied).append(path)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Report found crates, resolved versions and applied changes to `observer`
+    pub fn set_observer(&mut self, observer: Arc<dyn AutoddObserver>) {
+        self.updater.set_observer(Some(observer.clone()));
+        self.observer = Some(observer);
+    }
+
+    /// Write reports to `path` instead of stdout
+    pub fn set_output(&mut self, path: Option<PathBuf>) {
+        self.output = path;
+    }
+
+    /// Answer "yes" to every confirmation prompt (non-interactive mode)
+    pub fn set_assume_yes(&mut self, assume_yes: bool) {
+        self.assume_yes = assume_yes;
+    }
+
+    /// Ask the user to confirm an action.
+    ///
+    /// Returns `true` without prompting when `--yes` was given or stdin is not a
+    /// terminal, which keeps the current auto-apply behavior for scripts and CI.
+    pub fn confirm(&self, prompt: &str) -> Result<bool> {
+        if self.assume_yes || !std::io::stdin().is_terminal() {
+            return Ok(true);
+        }
+        eprint!("{} [y/N] ", prompt);
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
+
+    /// Error out instead of modifying Cargo.toml when changes are needed (`--locked`)
+    pub fn set_locked(&mut self, locked: bool) {
+        self.updater.set_locked(locked);
+    }
+
+    /// Modify Cargo.toml even when it has uncommitted changes in git (`--allow-dirty`)
+    pub fn set_allow_dirty(&mut self, allow_dirty: bool) {
+        self.updater.set_allow_dirty(allow_dirty);
+    }
+
+    /// Restrict update, sync, report, check, security and audit to the named crates
+    /// (`--only serde,tokio`): other crates are neither changed nor looked up on the
+    /// registry. `None` selects every crate.
+    pub fn set_only(&mut self, names: Option<Vec<String>>) {
+        self.filtered = names.is_some();
+        self.updater.set_only(names.clone());
+        self.reporter.set_only(names);
+    }
+
+    /// Analyze only the files listed in `list` (`--files-from`): one path or glob per line,
+    /// relative to the project root unless absolute. Blank lines and `#` comments are
+    /// skipped.
+    pub fn set_files_from(&mut self, list: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(list)
+            .with_context(|| format!("Failed to read file list {}", list.display()))?;
+        let mut files = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for entry in content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let matched = metadata::expand_glob(&self.project_root, entry)
+                .into_iter()
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            if matched.is_empty() {
+                anyhow::bail!("{}: no file matches {:?}", list.display(), entry);
+            }
+            files.extend(matched.into_iter().filter(|path| seen.insert(path.clone())));
+        }
+        self.analyzer.set_files(Some(files));
+        Ok(())
+    }
+
+    /// Consider pre-releases when looking up a crate's latest version
+    /// (`--allow-prerelease`)
+    pub fn set_allow_prerelease(&mut self, allow_prerelease: bool) {
+        self.updater.set_allow_prerelease(allow_prerelease);
+        self.reporter.set_allow_prerelease(allow_prerelease);
+    }
+
+    /// Like `--locked`, and additionally forbid any network access (`--frozen`)
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if frozen {
+            self.set_locked(true);
+            self.set_offline(true);
+        }
+    }
+
+    /// Never access the network (`--offline`); registry versions are left unresolved
+    pub fn set_offline(&mut self, offline: bool) {
+        self.updater.set_offline(offline);
+        self.reporter.set_offline(offline);
+    }
+
+    /// Log every registry request (URL, HTTP status, timing) to stderr, with credentials
+    /// redacted (`--explain-network`)
+    pub fn set_explain_network(&mut self, explain_network: bool) {
+        self.updater.set_explain_network(explain_network);
+        self.reporter.set_explain_network(explain_network);
+    }
+
+    /// Set how much is printed, from `QUIET` (errors only) to `DEBUG`.
+    ///
+    /// `VERBOSE` and above also include registry metadata (downloads, last publish date)
+    /// in reports. Requested output such as reports is written at every level.
+    pub fn set_verbosity(&mut self, verbosity: u8) {
+        self.verbosity = verbosity;
+        self.debug = verbosity >= DEBUG;
+        self.analyzer.set_debug(self.debug);
+        self.updater.set_debug(self.debug);
+        self.reporter.set_verbose(verbosity >= VERBOSE);
+    }
+
+    /// Print a progress or summary line to stdout unless `--quiet`
+    fn status(&self, message: impl std::fmt::Display) {
+        if self.verbosity >= NORMAL {
+            println!("{}", message);
+        }
+    }
+
+    /// Print a progress line to stderr unless `--quiet`, keeping stdout for reports
+    fn progress(&self, message: impl std::fmt::Display) {
+        if self.verbosity >= NORMAL {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// List the analyzed crates and where they are used (`-v`)
+    fn print_crate_details(&self, crate_refs: &HashMap<String, models::CrateReference>) {
+        if self.verbosity < VERBOSE {
+            return;
+        }
+        let mut names = crate_refs.keys().collect::<Vec<_>>();
+        names.sort();
+        println!("📦 {} crate(s) referenced:", names.len());
+        for name in names {
+            let crate_ref = &crate_refs[name];
+            let kind = if crate_ref.is_dev_dependency {
+                " (dev)"
+            } else {
+                ""
+            };
+            println!(
+                "  - {}{}: used in {} file(s)",
+                name,
+                kind,
+                crate_ref.usage_count()
+            );
         }
     }
 
+    /// Analyze the code and drop the crates the config excludes
+    fn analyze_crates(&self) -> Result<HashMap<String, models::CrateReference>> {
+        let mut crate_refs = self.analyzer.analyze_dependencies()?;
+        for broken in self.analyzer.broken_paths() {
+            self.progress(format_args!(
+                "⚠️ Path dependency {} ({}) in [{}]: {}",
+                broken.name, broken.path, broken.table, broken.problem
+            ));
+        }
+        crate_refs.retain(|name, _| !self.config.should_exclude(name));
+        if let Some(observer) = &self.observer {
+            let mut names = crate_refs.keys().collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                observer.on_crate_found(&crate_refs[name]);
+            }
+        }
+        Ok(crate_refs)
+    }
+
+    /// Flag dependencies whose latest release is older than `months` months in reports
+    pub fn set_max_age(&mut self, months: Option<u32>) {
+        self.reporter.set_max_age(months);
+    }
+
+    /// List used dependencies found in at most `files` files in reports (`--min-usage`)
+    pub fn set_min_usage(&mut self, files: Option<usize>) {
+        self.reporter.set_min_usage(files);
+    }
+
+    /// Force the report format instead of inferring it from the output path
+    pub fn set_format(&mut self, format: Option<OutputFormat>) {
+        self.format = format;
+    }
+
+    fn report_format(&self) -> OutputFormat {
+        OutputFormat::resolve(self.format, self.output.as_deref())
+    }
+
+    /// Print the counts of a finished run: a status line, or `{"summary": {...}}` with
+    /// `--format json` (written even with `--quiet`, like other requested reports)
+    fn print_summary(&self, summary: models::RunSummary) -> Result<()> {
+        if self.report_format() == OutputFormat::Json {
+            let mut writer = ReportWriter::for_output(self.output.as_deref());
+            serde_json::to_writer_pretty(&mut writer, &serde_json::json!({ "summary": summary }))?;
+            writeln!(writer)?;
+            return writer.finish();
+        }
+        self.status(format_args!("✅ {}", summary));
+        Ok(())
+    }
+
     pub fn analyze_and_update(&self) -> Result<()> {
         if self.debug {
             println!("🔍 Starting dependency analysis in debug mode...");
         }
         if self.dry_run {
-            println!("🔍 Running in dry-run mode (no changes will be made)...");
+            self.status("🔍 Running in dry-run mode (no changes will be made)...");
         }
 
-        println!("🔍 Analyzing project dependencies...");
-        let mut crate_refs = self.analyzer.analyze_dependencies()?;
+        // Fast path: nothing import-related changed since the last successful run. A run
+        // restricted with `--only` may leave other crates behind, so it neither uses nor
+        // updates the cache.
+        let fingerprint = self.analyzer.import_fingerprint()?;
+        let mut cache = Cache::load(&self.project_root);
+        if cache.imports_hash == Some(fingerprint) && self.dump_plan.is_none() && !self.filtered {
+            self.status("✅ No changes detected since the last run.");
+            return self.print_summary(models::RunSummary::default());
+        }
 
-        // Apply config exclusions
-        crate_refs.retain(|name, _| !self.config.should_exclude(name));
+        self.status("🔍 Analyzing project dependencies...");
+        let crate_refs = self.analyze_crates()?;
+        self.print_crate_details(&crate_refs);
 
-        if self.dry_run {
-            self.print_dry_run_summary(&crate_refs)?;
-            return Ok(());
+        if self.dry_run || self.dump_plan.is_some() {
+            let mut plan = self.updater.plan(&crate_refs)?;
+            self.updater.resolve_plan(&mut plan, &crate_refs);
+            self.write_plan(&plan)?;
+            if self.dry_run {
+                self.print_dry_run_summary(&plan);
+            }
+            return self.ensure_members_readable();
         }
 
         if self.debug {
             println!("\n📝 Updating Cargo.toml with found dependencies...");
         }
-        println!("📝 Updating Cargo.toml...");
-        self.updater.update_cargo_toml(&crate_refs)?;
+        self.status("📝 Updating Cargo.toml...");
+        let applied = self.updater.update_cargo_toml(&crate_refs)?;
+        self.record_changes(&applied)?;
+        self.warn_ambiguous(&applied);
+        // Also skips the cache, so the broken members are retried on the next run
+        self.ensure_members_readable()?;
 
-        println!("✅ Dependencies updated successfully!");
-        Ok(())
+        // Only remember this state if every dependency could be resolved; otherwise
+        // the next run must retry the unresolved ones.
+        let unresolved = self.updater.find_missing_dependencies(&crate_refs)?.len();
+        if !self.filtered && unresolved == 0 {
+            cache.imports_hash = Some(self.analyzer.import_fingerprint()?);
+            if let Err(e) = cache.save(&self.project_root)
+                && self.debug
+            {
+                println!("Warning: failed to write cache: {}", e);
+            }
+        }
+
+        self.print_summary(models::RunSummary::new(&applied, unresolved))
+    }
+
+    /// Point out the used names a run didn't add because the registry doesn't know them
+    fn warn_ambiguous(&self, plan: &models::UpdatePlan) {
+        if !plan.ambiguous.is_empty() {
+            self.progress(format_args!(
+                "⚠️ Not declared and not on crates.io, so not added (local modules?): {}",
+                plan.ambiguous.join(", ")
+            ));
+        }
     }
 
-    fn print_dry_run_summary(
-        &self,
-        crate_refs: &std::collections::HashMap<String, models::CrateReference>,
-    ) -> Result<()> {
+    fn print_dry_run_summary(&self, plan: &models::UpdatePlan) {
+        if self.verbosity < NORMAL {
+            return;
+        }
         println!("\n📋 Dry-run summary:");
         println!("==================");
 
-        let (regular, dev): (Vec<_>, Vec<_>) = crate_refs
-            .iter()
-            .partition(|(_, crate_ref)| !crate_ref.is_dev_dependency);
-
-        if !regular.is_empty() {
-            println!("\n[dependencies] would add:");
-            for (name, crate_ref) in regular {
-                if crate_ref.is_path_dependency {
-                    println!(
-                        "  {} = {{ path = \"{}\" }}",
-                        name,
-                        crate_ref.path.as_ref().unwrap_or(&"?".to_string())
-                    );
-                } else {
-                    println!("  {} = \"<latest>\"", name);
-                }
-            }
+        if plan.is_empty() {
+            println!("\nCargo.toml is already up to date.");
+        } else {
+            println!("\nCargo.toml would change as follows:");
+            print!("{}", plan);
         }
 
-        if !dev.is_empty() {
-            println!("\n[dev-dependencies] would add:");
-            for (name, crate_ref) in dev {
-                if crate_ref.is_path_dependency {
-                    println!(
-                        "  {} = {{ path = \"{}\" }}",
-                        name,
-                        crate_ref.path.as_ref().unwrap_or(&"?".to_string())
-                    );
-                } else {
-                    println!("  {} = \"<latest>\"", name);
-                }
-            }
+        // Path additions are written with their path, so only registry crates are listed
+        let unresolved: Vec<_> = plan
+            .additions
+            .iter()
+            .filter(|change| change.version.is_none() && change.path.is_none())
+            .map(|change| change.name.as_str())
+            .collect();
+        if !unresolved.is_empty() {
+            println!(
+                "\nNo crates.io version resolved for (patched, offline or unpublished): {}",
+                unresolved.join(", ")
+            );
+        }
+        if !plan.ambiguous.is_empty() {
+            println!(
+                "\nNot declared and not on crates.io, so not added (local modules?): {}",
+                plan.ambiguous.join(", ")
+            );
         }
+        let applied = plan.writable();
+        let would_apply = models::RunSummary::new(&applied, plan.unresolved(&applied));
+        println!("\nWould apply: {}", would_apply);
 
         // Show config exclusions
         if !self.config.exclude.is_empty() {
@@ -143,28 +531,620 @@ impl CargoAutodd {
         }
 
         println!("\n✅ No changes were made (dry-run mode)");
-        Ok(())
     }
 
     pub fn update_dependencies(&self) -> Result<()> {
-        println!("🔍 Checking for dependency updates...");
+        self.status("🔍 Checking for dependency updates...");
+        let crate_refs = self.analyze_crates()?;
+        self.print_crate_details(&crate_refs);
+
+        let mut plan = self.updater.plan(&crate_refs)?;
+        self.updater.resolve_plan(&mut plan, &crate_refs);
+        plan.updates = self.updater.find_version_updates(&plan)?;
+
+        if self.dry_run || self.dump_plan.is_some() {
+            self.write_plan(&plan)?;
+            if self.dry_run {
+                self.print_dry_run_summary(&plan);
+            }
+            return self.ensure_members_readable();
+        }
+
+        let original = std::fs::read_to_string(self.project_root.join("Cargo.toml"))?;
+        let applied = self.updater.apply_plan(&plan, &crate_refs)?;
+        self.verify_applied(&original, &applied)?;
+        self.ensure_members_readable()?;
+        self.warn_ambiguous(&plan);
+        self.print_summary(models::RunSummary::new(&applied, plan.unresolved(&applied)))
+    }
+
+    /// Add missing dependencies, remove unused ones and raise declared requirements to
+    /// their latest compatible versions, in a single rewrite of Cargo.toml
+    /// (`cargo autodd sync`)
+    pub fn sync(&self) -> Result<()> {
+        if self.dry_run {
+            self.status("🔍 Running in dry-run mode (no changes will be made)...");
+        }
+        self.status("🔍 Analyzing project dependencies...");
+        let crate_refs = self.analyze_crates()?;
+        self.print_crate_details(&crate_refs);
+
+        let mut plan = self.updater.plan(&crate_refs)?;
+        self.updater.resolve_plan(&mut plan, &crate_refs);
+        plan.updates = self.updater.find_compatible_updates(&plan)?;
+
+        if self.dry_run || self.dump_plan.is_some() {
+            self.write_plan(&plan)?;
+            if self.dry_run {
+                self.print_dry_run_summary(&plan);
+            }
+            return self.ensure_members_readable();
+        }
+        if plan.is_empty() {
+            self.ensure_members_readable()?;
+            self.warn_ambiguous(&plan);
+            self.status("✅ Cargo.toml is already in sync.");
+            return self.print_summary(models::RunSummary::new(&plan, plan.unresolved(&plan)));
+        }
+
+        self.status("📝 Updating Cargo.toml...");
+        let original = std::fs::read_to_string(self.project_root.join("Cargo.toml"))?;
+        let applied = self.updater.apply_plan(&plan, &crate_refs)?;
+        self.verify_applied(&original, &applied)?;
+        self.ensure_members_readable()?;
+        self.warn_ambiguous(&plan);
+        self.print_summary(models::RunSummary::new(&applied, plan.unresolved(&applied)))
+    }
+
+    pub fn generate_report(&self) -> Result<()> {
+        self.progress("📊 Analyzing dependency usage...");
         let crate_refs = self.analyzer.analyze_dependencies()?;
-        self.updater.update_cargo_toml(&crate_refs)?;
-        println!("\n🔍 Verifying dependencies...");
-        self.updater.verify_dependencies()?;
-        println!("✅ Dependencies updated successfully!");
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        self.reporter
+            .write_dependency_report(&crate_refs, self.report_format(), &mut writer)?;
+        writer.finish()
+    }
+
+    /// Verify that Cargo.toml matches the code without modifying anything.
+    ///
+    /// Returns an error listing the required changes if the manifest is out of date.
+    pub fn check(&self) -> Result<()> {
+        self.progress("🔍 Analyzing project dependencies...");
+        let crate_refs = self.analyze_crates()?;
+
+        let mut plan = self.updater.plan(&crate_refs)?;
+        if self.report_format() == OutputFormat::Sarif {
+            let out_of_date = !plan.is_empty();
+            // Outdated versions are reported too; they are findings but don't fail the check
+            plan.updates = self.updater.find_version_updates(&plan)?;
+            let log = sarif::SarifLog::from_plan(&self.project_root, &plan, &crate_refs);
+            let mut writer = ReportWriter::for_output(self.output.as_deref());
+            serde_json::to_writer_pretty(&mut writer, &log)?;
+            writeln!(writer)?;
+            writer.finish()?;
+            if out_of_date {
+                anyhow::bail!("Cargo.toml is out of date; see the SARIF results");
+            }
+            return Ok(());
+        }
+        if !plan.is_empty() {
+            anyhow::bail!("Cargo.toml is out of date:\n{}", plan);
+        }
+        self.ensure_members_readable()?;
+        self.status("✅ Cargo.toml is up to date.");
         Ok(())
     }
 
-    pub fn generate_report(&self) -> Result<()> {
-        println!("📊 Analyzing dependency usage...");
+    /// Check only the workspace members with files changed since `base`
+    /// (`check --changed --base <REV>`): the files that differ from the merge base of
+    /// `base` and HEAD, committed or not, and untracked files.
+    pub fn check_changed(&mut self, base: &str) -> Result<()> {
+        let changed = self.git_changed_files(base)?;
+        self.check_changed_files(&changed)
+    }
+
+    /// Check each member owning one of `changed` (absolute paths) as its own project,
+    /// reporting every out-of-date member before failing
+    fn check_changed_files(&mut self, changed: &[PathBuf]) -> Result<()> {
+        if self.report_format() == OutputFormat::Sarif {
+            anyhow::bail!("SARIF output is not supported with --changed");
+        }
+        let workspace = metadata::load(&self.project_root)?;
+        let members = workspace.members_owning(changed);
+        if members.is_empty() {
+            self.status("✅ No workspace member has changed files.");
+            return Ok(());
+        }
+
+        let project_root = self.project_root.clone();
+        let mut out_of_date = Vec::new();
+        for member in members {
+            self.progress(format_args!("📦 {}", member.name));
+            self.set_project_root(member.dir().to_path_buf());
+            if let Err(e) = self.check() {
+                eprintln!("❌ {}: {:#}", member.name, e);
+                out_of_date.push(member.name.as_str());
+            }
+        }
+        self.set_project_root(project_root);
+        if !out_of_date.is_empty() {
+            anyhow::bail!(
+                "{} changed member(s) failed the check: {}",
+                out_of_date.len(),
+                out_of_date.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Files changed since the merge base of `base` and HEAD, plus untracked files, as
+    /// absolute paths. Renames are listed under both names.
+    fn git_changed_files(&self, base: &str) -> Result<Vec<PathBuf>> {
+        let git = |args: &[&str]| -> Result<String> {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .current_dir(&self.project_root)
+                .output()
+                .context("Failed to run git")?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "git {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            Ok(String::from_utf8(output.stdout)?)
+        };
+        let toplevel = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim());
+        let merge_base = git(&["merge-base", base, "HEAD"])?;
+        let diff = git(&["diff", "--name-only", "--no-renames", merge_base.trim()])?;
+        let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name"])?;
+        Ok(diff
+            .lines()
+            .chain(untracked.lines())
+            .filter(|line| !line.is_empty())
+            .map(|line| toplevel.join(line))
+            .collect())
+    }
+
+    /// Point the analysis, updates and reports at the project in `project_root`
+    fn set_project_root(&mut self, project_root: PathBuf) {
+        self.analyzer.set_project_root(project_root.clone());
+        self.updater.set_project_root(project_root.clone());
+        self.reporter.set_project_root(project_root.clone());
+        self.project_root = project_root;
+    }
+
+    /// Fail if the last analysis had to skip workspace members whose Cargo.toml couldn't
+    /// be read or parsed. Called after the rest of the workspace has been handled.
+    fn ensure_members_readable(&self) -> Result<()> {
+        let broken = self.analyzer.broken_members();
+        if broken.is_empty() {
+            return Ok(());
+        }
+        let details = broken
+            .iter()
+            .map(|(manifest, error)| format!("  {}: {}", utils::slash_path(manifest), error))
+            .collect::<Vec<_>>()
+            .join("\n");
+        anyhow::bail!(
+            "Skipped {} workspace member(s) with an unreadable Cargo.toml:\n{}",
+            broken.len(),
+            details
+        )
+    }
+
+    /// Trace why a single crate would be kept, added or removed
+    pub fn explanation(&self, crate_name: &str) -> Result<models::CrateExplanation> {
+        let crate_refs = self.analyze_crates()?;
+
+        let mut explanation = self.updater.explain_crate(crate_name, &crate_refs)?;
+        explanation.excluded = self.config.should_exclude(crate_name);
+        Ok(explanation)
+    }
+
+    pub fn explain(&self, crate_name: &str) -> Result<()> {
+        self.progress("🔍 Analyzing project dependencies...");
+        let explanation = self.explanation(crate_name)?;
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", explanation)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &explanation)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+        writer.finish()
+    }
+
+    /// Compare the dependencies of two manifests. Each side is a Cargo.toml path, a
+    /// directory holding one, or a git revision (`HEAD~1`, or `main:crates/a/Cargo.toml`
+    /// for another path); `new` defaults to the project's Cargo.toml.
+    pub fn manifest_diff(&self, old: &str, new: Option<&str>) -> Result<models::ManifestDiff> {
+        let (old_doc, old_label) = self.read_manifest_spec(old)?;
+        let (new_doc, new_label) = self.read_manifest_spec(new.unwrap_or("Cargo.toml"))?;
+        Ok(self
+            .reporter
+            .manifest_diff(&old_doc, &new_doc, &old_label, &new_label))
+    }
+
+    /// Read a manifest named on the command line, returning it with a label for reports
+    fn read_manifest_spec(&self, spec: &str) -> Result<(toml_edit::DocumentMut, String)> {
+        let mut path = self.project_root.join(spec);
+        if path.is_dir() {
+            path = path.join("Cargo.toml");
+        }
+        let (content, label) = if path.is_file() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            (content, utils::slash_path(Path::new(spec)))
+        } else {
+            // Without a path, git reads the manifest of the current directory
+            let object = if spec.contains(':') {
+                spec.to_string()
+            } else {
+                format!("{}:./Cargo.toml", spec)
+            };
+            let output = std::process::Command::new("git")
+                .args(["show", &object])
+                .current_dir(&self.project_root)
+                .output()
+                .context("Failed to run git show")?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "{} is neither a manifest nor a git revision: {}",
+                    spec,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            (String::from_utf8(output.stdout)?, object)
+        };
+        let doc = content
+            .parse()
+            .with_context(|| format!("Failed to parse {}", label))?;
+        Ok((doc, label))
+    }
+
+    /// Print the dependencies added, removed or re-versioned between two manifests
+    /// (`cargo autodd diff`); read-only
+    pub fn diff(&self, old: &str, new: Option<&str>) -> Result<()> {
+        let diff = self.manifest_diff(old, new)?;
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", diff)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &diff)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+        writer.finish()
+    }
+
+    /// Detect the crates a single piece of Rust source needs; `input` names it in the
+    /// result (a file path, or `<stdin>`). Config exclusions apply, Cargo.toml is not read.
+    pub fn source_analysis(&self, content: &str, input: &Path) -> Result<models::SourceAnalysis> {
+        let crate_refs = self.analyzer.analyze_source(content, input)?;
+        let mut crates = crate_refs
+            .into_values()
+            .filter(|crate_ref| !self.config.should_exclude(&crate_ref.name))
+            .map(|crate_ref| {
+                let mut features = crate_ref.features.into_iter().collect::<Vec<_>>();
+                features.sort();
+                models::DetectedCrate {
+                    name: crate_ref.name,
+                    dev_only: crate_ref.is_dev_dependency,
+                    import_count: crate_ref.import_count,
+                    features,
+                }
+            })
+            .collect::<Vec<_>>();
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(models::SourceAnalysis {
+            input: utils::slash_path(input),
+            crates,
+        })
+    }
+
+    /// Print the crates used by `file`, or by the Rust source on stdin if `None`
+    /// (`cargo autodd analyze`)
+    pub fn analyze(&self, file: Option<&Path>) -> Result<()> {
+        let (content, input) = match file {
+            Some(path) => (
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                path,
+            ),
+            None => {
+                let mut content = String::new();
+                std::io::stdin().read_to_string(&mut content)?;
+                (content, Path::new("<stdin>"))
+            }
+        };
+        let analysis = self.source_analysis(&content, input)?;
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", analysis)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &analysis)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+        writer.finish()
+    }
+
+    /// Build a starter config from the analysis: known macro crates become `essential`
+    /// (they are easily missed and removed) and known test frameworks become `dev_only`
+    pub fn suggested_config(&self) -> Result<Config> {
         let crate_refs = self.analyzer.analyze_dependencies()?;
-        self.reporter.generate_dependency_report(&crate_refs)
+        let plan = self.updater.plan(&crate_refs)?;
+
+        // Crates the code uses plus declared crates the analysis didn't find
+        let candidates = crate_refs
+            .keys()
+            .cloned()
+            .chain(plan.removals.into_iter().map(|change| change.name))
+            .collect::<std::collections::BTreeSet<_>>();
+
+        Ok(Config {
+            essential: candidates
+                .iter()
+                .filter(|name| utils::is_macro_crate(name) && !utils::is_essential_dep(name))
+                .cloned()
+                .collect(),
+            dev_only: candidates
+                .iter()
+                .filter(|name| utils::is_likely_dev_dep(name))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Print the configuration in effect after merging the config file, inline config
+    /// and CLI flags (`--print-config`), as TOML or, with `--format json`, JSON
+    pub fn print_config(&self) -> Result<()> {
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", self.config.to_toml()?)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &self.config)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif | OutputFormat::Markdown => {
+                anyhow::bail!("--print-config supports text (TOML) and json output")
+            }
+        }
+        writer.finish()
+    }
+
+    /// Write a starter `.cargo-autodd.toml` to the project root (`--init`).
+    ///
+    /// Refuses to overwrite an existing file; with `--dry-run` the file is only printed.
+    pub fn init(&self) -> Result<()> {
+        let path = self.project_root.join(config::CONFIG_FILE);
+        if path.exists() {
+            anyhow::bail!("{} already exists; not overwriting it", path.display());
+        }
+        self.progress("🔍 Analyzing project dependencies...");
+        let content = self.suggested_config()?.starter_toml();
+        if self.dry_run {
+            self.status(format_args!(
+                "🔍 Dry run: would write {}:\n",
+                path.display()
+            ));
+            print!("{}", content);
+            return Ok(());
+        }
+        utils::write_atomic(&path, content.as_bytes())?;
+        self.status(format_args!("✅ Wrote {}", path.display()));
+        Ok(())
+    }
+
+    /// Put back the Cargo.toml saved before the last rewrite made with `backup` enabled
+    /// (`--restore`). The backup is removed, so a second restore fails instead of undoing
+    /// the first.
+    pub fn restore(&self) -> Result<()> {
+        let backup = self.project_root.join(config::BACKUP_FILE);
+        if !backup.is_file() {
+            anyhow::bail!(
+                "No backup at {} (enable `backup` in the config or pass --backup)",
+                backup.display()
+            );
+        }
+        let manifest = self.project_root.join("Cargo.toml");
+        if self.dry_run {
+            self.status(format_args!(
+                "🔍 Dry run: would restore {} from {}",
+                manifest.display(),
+                backup.display()
+            ));
+            return Ok(());
+        }
+        let content = std::fs::read(&backup)
+            .with_context(|| format!("Failed to read {}", backup.display()))?;
+        utils::write_atomic(&manifest, &content)?;
+        std::fs::remove_file(&backup)?;
+        self.status(format_args!(
+            "✅ Restored {} from {}",
+            manifest.display(),
+            backup.display()
+        ));
+        Ok(())
+    }
+
+    /// Narrow declared feature lists to the features the code is inferred to need
+    /// (`--prune-features`).
+    ///
+    /// The changes are always printed first; `--dry-run` stops there, otherwise they are
+    /// written after confirmation.
+    pub fn prune_features(&self) -> Result<()> {
+        self.progress("🔍 Inferring the features used by the code...");
+        let usage = self.analyzer.feature_usage()?;
+        let prunings = self.updater.plan_feature_pruning(&usage)?;
+        if prunings.is_empty() {
+            self.status("✅ No declared features can be pruned.");
+            return Ok(());
+        }
+
+        println!("Feature lists to narrow:");
+        for pruning in &prunings {
+            print!("{}", pruning);
+        }
+        if self.dry_run {
+            self.status("\n✅ No changes were made (dry-run mode)");
+            return Ok(());
+        }
+        if self.confirm(&format!(
+            "Narrow the features of {} dependency declaration(s)?",
+            prunings.len()
+        ))? {
+            self.updater.apply_feature_pruning(&prunings)?;
+            self.status(format_args!(
+                "✅ Narrowed the features of {} dependency declaration(s)",
+                prunings.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the environment cargo-autodd depends on (manifest, registry, proxy, cache)
+    pub fn doctor_report(&self) -> models::DoctorReport {
+        dependency_manager::DependencyDoctor::new(self.project_root.clone()).run(&self.updater)
+    }
+
+    pub fn doctor(&self) -> Result<()> {
+        let report = self.doctor_report();
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", report)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &report)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+        writer.finish()?;
+
+        let failures = report.failures();
+        if failures > 0 {
+            anyhow::bail!("{} doctor check(s) failed", failures);
+        }
+        Ok(())
+    }
+
+    /// Check the declared dependencies against the RustSec advisory database
+    pub fn audit_report(&self) -> Result<models::AuditReport> {
+        self.auditor().audit(&self.updater)
+    }
+
+    fn auditor(&self) -> dependency_manager::DependencyAuditor {
+        let mut auditor = dependency_manager::DependencyAuditor::new(self.project_root.clone());
+        if let Some(path) = &self.advisory_db {
+            auditor.set_advisory_db(path.clone());
+        }
+        auditor
+    }
+
+    /// Report vulnerable dependencies (`cargo autodd audit`). With `fix`, raise each fixable
+    /// one to its lowest patched version after confirmation; `--dry-run` only previews.
+    ///
+    /// Fails while any vulnerable dependency is left in Cargo.toml.
+    pub fn audit(&self, fix: bool) -> Result<()> {
+        self.progress("🔍 Checking dependencies against the RustSec advisory database...");
+        let report = self.audit_report()?;
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        match self.report_format() {
+            OutputFormat::Text => write!(writer, "{}", report)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &report)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Sarif => anyhow::bail!("SARIF output is only supported by `check`"),
+            OutputFormat::Markdown => {
+                anyhow::bail!("Markdown output is only supported by `report`")
+            }
+        }
+        writer.finish()?;
+
+        let plan = models::UpdatePlan {
+            updates: report
+                .fixes
+                .iter()
+                .map(|fix| models::VersionUpdate {
+                    table: fix.table.clone(),
+                    name: fix.name.clone(),
+                    current: fix.current.clone(),
+                    latest: fix.fixed.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let unfixed = report
+            .unfixed
+            .iter()
+            .map(|advisory| (&advisory.table, &advisory.name))
+            .collect::<std::collections::HashSet<_>>();
+        let mut remaining = report.fixes.len() + unfixed.len();
+        if fix && !plan.is_empty() {
+            if self.dry_run {
+                self.status(format_args!("🔍 Dry run: would apply\n{}", plan));
+            } else if self.confirm(&format!(
+                "Raise {} version requirement(s) to patched versions?",
+                plan.updates.len()
+            ))? {
+                let applied = self.updater.apply_plan(&plan, &HashMap::new())?;
+                self.record_changes(&applied)?;
+                remaining -= applied.updates.len();
+                self.status(format_args!(
+                    "✅ Raised {} version requirement(s) in Cargo.toml",
+                    applied.updates.len()
+                ));
+            }
+        }
+
+        if remaining > 0 {
+            anyhow::bail!(
+                "{} dependency declaration(s) are still affected by advisories",
+                remaining
+            );
+        }
+        Ok(())
     }
 
     pub fn check_security(&self) -> Result<()> {
-        println!("🔒 Running security check...");
-        self.reporter.generate_security_report()
+        self.progress("🔒 Running security check...");
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        self.reporter
+            .write_security_report(self.report_format(), &mut writer)?;
+        writer.finish()
+    }
+
+    /// Security check across every workspace member's resolved versions
+    /// (`security --workspace`), reporting each crate version once
+    pub fn check_workspace_security(&self) -> Result<()> {
+        self.progress("🔒 Running workspace security check...");
+        let mut writer = ReportWriter::for_output(self.output.as_deref());
+        self.reporter.write_workspace_security_report(
+            &self.auditor(),
+            self.report_format(),
+            &mut writer,
+        )?;
+        writer.finish()
     }
 }
 
@@ -213,6 +1193,731 @@ use tokio;
         Ok(())
     }
 
+    #[test]
+    fn test_analyze_and_update_skips_when_unchanged() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        // Only use already-declared crates so the run resolves without the network
+        std::fs::write(temp_dir.path().join("src/main.rs"), "use serde;\n")?;
+
+        let autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.analyze_and_update()?;
+        let cache = Cache::load(temp_dir.path());
+        assert_eq!(
+            cache.imports_hash,
+            Some(autodd.analyzer.import_fingerprint()?),
+            "fingerprint should be cached after a complete run"
+        );
+
+        // A second run hits the fast path and leaves the manifest untouched
+        let before = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+        autodd.analyze_and_update()?;
+        let after = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+        assert_eq!(before, after);
+        Ok(())
+    }
+
+    #[test]
+    fn test_explanation_reports_usage_and_exclusion() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        let serde = autodd.explanation("serde")?;
+        assert_eq!(serde.used_in, vec!["src/main.rs".to_string()]);
+        assert!(matches!(
+            &serde.decisions[..],
+            [models::Decision::Keep { .. }]
+        ));
+
+        let config = Config {
+            exclude: ["serde".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        let autodd = CargoAutodd::with_options(temp_dir.path().to_path_buf(), false, false, config);
+        let serde = autodd.explanation("serde")?;
+        assert!(serde.excluded);
+        assert!(serde.used_in.is_empty());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl AutoddObserver for RecordingObserver {
+        fn on_crate_found(&self, crate_ref: &models::CrateReference) {
+            self.record(format!("found {}", crate_ref.name));
+        }
+
+        fn on_add(&self, change: &models::PlannedChange) {
+            self.record(format!("add [{}] {}", change.table, change.name));
+        }
+
+        fn on_remove(&self, change: &models::PlannedChange) {
+            self.record(format!("remove [{}] {}", change.table, change.name));
+        }
+    }
+
+    impl RecordingObserver {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn test_observer_receives_crates_and_changes() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"util\"]\n",
+        )?;
+        for member in ["app", "util"] {
+            std::fs::create_dir_all(root.join(member).join("src"))?;
+        }
+        std::fs::write(
+            root.join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\n",
+        )?;
+        std::fs::write(
+            root.join("app/src/main.rs"),
+            "fn main() {\n    util::run();\n}\n",
+        )?;
+        std::fs::write(
+            root.join("util/Cargo.toml"),
+            "[package]\nname = \"util\"\nversion = \"0.1.0\"\n",
+        )?;
+        std::fs::write(root.join("util/src/lib.rs"), "pub fn run() {}\n")?;
+
+        let observer = Arc::new(RecordingObserver::default());
+        let mut autodd = CargoAutodd::new(root.join("app"));
+        autodd.set_offline(true);
+        autodd.set_verbosity(QUIET);
+        autodd.set_observer(observer.clone());
+        autodd.analyze_and_update()?;
+
+        assert_eq!(
+            *observer.events.lock().unwrap(),
+            [
+                "found util",
+                "add [dependencies] util",
+                "remove [dependencies] regex"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_changed_files_only_checks_affected_members() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"fresh\", \"stale\"]\n",
+        )?;
+        for member in ["fresh", "stale"] {
+            std::fs::create_dir_all(root.join(member).join("src"))?;
+            std::fs::write(root.join(member).join("src/lib.rs"), "pub fn run() {}\n")?;
+        }
+        std::fs::write(
+            root.join("fresh/Cargo.toml"),
+            "[package]\nname = \"fresh\"\nversion = \"0.1.0\"\n",
+        )?;
+        // regex is declared but unused, so this member is out of date
+        std::fs::write(
+            root.join("stale/Cargo.toml"),
+            "[package]\nname = \"stale\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\n",
+        )?;
+
+        let mut autodd = CargoAutodd::new(root.to_path_buf());
+        autodd.set_offline(true);
+        autodd.set_verbosity(QUIET);
+        autodd.check_changed_files(&[root.join("fresh/src/lib.rs"), root.join("notes.md")])?;
+        autodd.check_changed_files(&[])?;
+
+        let err = autodd
+            .check_changed_files(&[root.join("stale/src/lib.rs")])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 changed member(s) failed the check: stale"
+        );
+        assert_eq!(autodd.project_root, root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_changelog_records_applied_changes_only() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        // regex is declared but unused, so nothing needs the network
+        std::fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\n",
+        )?;
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n")?;
+        let changelog = temp_dir.path().join("DEPENDENCIES.md");
+
+        let mut autodd = CargoAutodd::with_options(
+            temp_dir.path().to_path_buf(),
+            false,
+            true,
+            Config::default(),
+        );
+        autodd.set_verbosity(QUIET);
+        autodd.set_changelog(Some(changelog.clone()));
+        autodd.analyze_and_update()?;
+        assert!(!changelog.exists(), "dry-run must not write the changelog");
+
+        autodd.dry_run = false;
+        autodd.analyze_and_update()?;
+        let content = std::fs::read_to_string(&changelog)?;
+        assert!(content.starts_with("## "), "{}", content);
+        assert!(content.contains("- removed `regex` from [dependencies]\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_summary_counts_applied_changes() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        std::fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\n",
+        )?;
+        // regex goes away; offline, the new crate can't be resolved and isn't added
+        std::fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "use unpublished_helper::run;\n\nfn main() {\n    run();\n}\n",
+        )?;
+        let summary = temp_dir.path().join("summary.json");
+
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.set_verbosity(QUIET);
+        autodd.set_offline(true);
+        autodd.set_output(Some(summary.clone()));
+        autodd.set_format(Some(OutputFormat::Json));
+        autodd.analyze_and_update()?;
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&summary)?)?;
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "summary": {"added": 0, "removed": 1, "updated": 0, "unresolved": 1}
+            })
+        );
+        assert_eq!(
+            models::RunSummary {
+                added: 3,
+                removed: 1,
+                updated: 2,
+                unresolved: 0,
+            }
+            .to_string(),
+            "Added 3, removed 1, updated 2, 0 unresolved."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_dry_run_leaves_manifest_alone() -> Result<()> {
+        use crate::dependency_manager::tests::mock_registry::{http_response, spawn_mock_registry};
+
+        let body = r#"{"versions":[{"num":"1.43.0","yanked":false}]}"#;
+        let (url, handle) = spawn_mock_registry(vec![http_response(
+            "200 OK",
+            &["Content-Type: application/json"],
+            body,
+        )]);
+        let temp_dir = create_test_environment()?;
+        let manifest = "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\ntokio = \"1.0\"\n";
+        std::fs::write(temp_dir.path().join("Cargo.toml"), manifest)?;
+        std::fs::write(temp_dir.path().join("src/main.rs"), "use tokio::fs;\n")?;
+
+        let mut autodd = CargoAutodd::with_options(
+            temp_dir.path().to_path_buf(),
+            false,
+            true,
+            Config::default(),
+        );
+        autodd.set_verbosity(QUIET);
+        autodd.updater.set_registry_api(url);
+        autodd.update_dependencies()?;
+
+        // The bump was looked up, but neither written nor verified with `cargo check`
+        assert_eq!(handle.join().unwrap().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?,
+            manifest
+        );
+        assert!(!temp_dir.path().join("target").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_files_from_limits_analysis_to_listed_files() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "use regex::Regex;\n\nmod extra;\n",
+        )?;
+        std::fs::write(root.join("src/extra.rs"), "use serde_json::Value;\n")?;
+        std::fs::create_dir_all(root.join("tools"))?;
+        std::fs::write(root.join("tools/gen.rs"), "use clap::App;\n")?;
+        std::fs::write(root.join("tools/notes.txt"), "use rand::Rng;\n")?;
+        let list = root.join("files.txt");
+        std::fs::write(
+            &list,
+            "# from the build system\nsrc/main.rs\n\ntools/*.rs\n",
+        )?;
+
+        let mut autodd = CargoAutodd::new(root.to_path_buf());
+        autodd.set_files_from(&list)?;
+        let crate_refs = autodd.analyzer.analyze_dependencies()?;
+        let mut names = crate_refs.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        // src/extra.rs is declared by main.rs but not listed, so serde_json isn't seen
+        assert_eq!(names, ["clap", "regex"]);
+
+        std::fs::write(&list, "src/main.rs\nsrc/missing.rs\n")?;
+        let err = autodd.set_files_from(&list).unwrap_err().to_string();
+        assert!(
+            err.contains("no file matches \"src/missing.rs\""),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_and_restore() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        // regex is declared but unused, so nothing needs the network
+        let manifest = "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\n";
+        let path = temp_dir.path().join("Cargo.toml");
+        std::fs::write(&path, manifest)?;
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n")?;
+        let backup = temp_dir.path().join(config::BACKUP_FILE);
+
+        let mut autodd = CargoAutodd::with_options(
+            temp_dir.path().to_path_buf(),
+            false,
+            false,
+            Config {
+                backup: true,
+                ..Config::default()
+            },
+        );
+        autodd.set_verbosity(QUIET);
+        autodd.analyze_and_update()?;
+        assert!(!std::fs::read_to_string(&path)?.contains("regex"));
+        assert_eq!(std::fs::read_to_string(&backup)?, manifest);
+
+        autodd.restore()?;
+        assert_eq!(std::fs::read_to_string(&path)?, manifest);
+        assert!(!backup.exists());
+        assert!(autodd.restore().is_err(), "the backup is used up");
+        Ok(())
+    }
+
+    #[test]
+    fn test_offline_sync_verifies_without_freezing_the_lockfile() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let manifest = "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nlazy_static = \"1\"\n";
+        std::fs::write(temp_dir.path().join("Cargo.toml"), manifest)?;
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n")?;
+
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.set_verbosity(QUIET);
+        autodd.set_offline(true);
+        autodd.sync()?;
+        let synced = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+        assert!(!synced.contains("lazy_static"), "{}", synced);
+        assert!(temp_dir.path().join("Cargo.lock").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_verification_rolls_back() -> Result<()> {
+        // The sibling member is added as a path dependency, and it doesn't compile
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"helper\"]\nresolver = \"2\"\n",
+        )?;
+        for member in ["app", "helper"] {
+            std::fs::create_dir_all(root.join(member).join("src"))?;
+        }
+        let manifest = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n";
+        std::fs::write(root.join("app/Cargo.toml"), manifest)?;
+        std::fs::write(
+            root.join("app/src/main.rs"),
+            "use helper::run;\nfn main() { run() }\n",
+        )?;
+        std::fs::write(
+            root.join("helper/Cargo.toml"),
+            "[package]\nname = \"helper\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )?;
+        std::fs::write(
+            root.join("helper/src/lib.rs"),
+            "pub fn run() { broken() }\n",
+        )?;
+        let changelog = root.join("DEPENDENCIES.md");
+
+        let mut autodd = CargoAutodd::new(root.join("app"));
+        autodd.set_verbosity(QUIET);
+        autodd.set_offline(true);
+        autodd.set_changelog(Some(changelog.clone()));
+        let error = format!("{:#}", autodd.sync().unwrap_err());
+        assert!(
+            error.contains("Restored the previous Cargo.toml"),
+            "{}",
+            error
+        );
+        assert!(error.contains("cargo check failed"), "{}", error);
+        assert_eq!(
+            std::fs::read_to_string(root.join("app/Cargo.toml"))?,
+            manifest
+        );
+        assert!(!changelog.exists(), "rolled-back changes are not recorded");
+
+        autodd.set_rollback(false);
+        assert!(autodd.sync().is_err());
+        let kept = std::fs::read_to_string(root.join("app/Cargo.toml"))?;
+        assert!(kept.contains("helper"), "{}", kept);
+        assert!(changelog.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verification_failing_before_the_rewrite_only_warns() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        // regex is unused and gets removed; the missing path crate broke the build already
+        let manifest = "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\nmissing = { path = \"missing\" }\n";
+        std::fs::write(temp_dir.path().join("Cargo.toml"), manifest)?;
+        std::fs::write(
+            temp_dir.path().join("src/main.rs"),
+            "use missing::run;\nfn main() { run() }\n",
+        )?;
+        let changelog = temp_dir.path().join("DEPENDENCIES.md");
+
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.set_verbosity(QUIET);
+        autodd.set_offline(true);
+        autodd.set_changelog(Some(changelog.clone()));
+        autodd.sync()?;
+        let kept = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+        assert!(!kept.contains("regex"), "{}", kept);
+        assert!(kept.contains("missing"), "{}", kept);
+        assert!(changelog.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_analysis_leaves_manifest_alone() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let manifest = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+        let config = Config {
+            exclude: ["internal".to_string()].into(),
+            ..Default::default()
+        };
+        let autodd = CargoAutodd::with_options(temp_dir.path().to_path_buf(), false, false, config);
+
+        let analysis = autodd.source_analysis(
+            "use tokio::fs;\nuse internal::Thing;\nuse regex::Regex;\n",
+            Path::new("<stdin>"),
+        )?;
+        assert_eq!(analysis.input, "<stdin>");
+        let names = analysis
+            .crates
+            .iter()
+            .map(|detected| detected.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["regex", "tokio"]);
+        assert_eq!(analysis.crates[1].features, ["fs"]);
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?,
+            manifest
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_diff_against_git_revision() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let root = temp_dir.path();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(root)
+                .output()
+        };
+        git(&["init", "-q"])?;
+        git(&["add", "Cargo.toml"])?;
+        git(&["commit", "-q", "-m", "initial"])?;
+        let manifest = std::fs::read_to_string(root.join("Cargo.toml"))?;
+        std::fs::write(
+            root.join("Cargo.toml"),
+            format!("{}regex = \"1.11\"\n", manifest),
+        )?;
+
+        let autodd = CargoAutodd::new(root.to_path_buf());
+        let diff = autodd.manifest_diff("HEAD", None)?;
+        assert_eq!(diff.old, "HEAD:./Cargo.toml");
+        assert_eq!(diff.new, "Cargo.toml");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "regex");
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+
+        // A path works as well, and an unknown revision is an error
+        assert!(autodd.manifest_diff(".", None)?.is_empty());
+        assert!(autodd.manifest_diff("no-such-revision", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_fix_raises_vulnerable_requirement() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let manifest = temp_dir.path().join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nsmallvec = { version = \"0.6.2\", features = [\"union\"] }\n",
+        )?;
+        let db = temp_dir.path().join("advisory-db");
+        std::fs::create_dir_all(db.join("crates/smallvec"))?;
+        std::fs::write(
+            db.join("crates/smallvec/RUSTSEC-2019-0009.md"),
+            "```toml\n[advisory]\nid = \"RUSTSEC-2019-0009\"\npackage = \"smallvec\"\n\n[versions]\npatched = [\">= 0.6.10\"]\n```\n\n# Double-free and use-after-free in SmallVec::grow()\n",
+        )?;
+
+        let mut autodd = CargoAutodd::with_options(
+            temp_dir.path().to_path_buf(),
+            false,
+            true,
+            Config::default(),
+        );
+        autodd.set_verbosity(QUIET);
+        autodd.set_assume_yes(true);
+        autodd.set_advisory_db(Some(db));
+        autodd.set_output(Some(temp_dir.path().join("audit.txt")));
+        assert!(
+            autodd.audit(true).is_err(),
+            "dry-run leaves the advisory open"
+        );
+        assert!(std::fs::read_to_string(&manifest)?.contains("\"0.6.2\""));
+
+        autodd.dry_run = false;
+        autodd.audit(true)?;
+        assert!(
+            std::fs::read_to_string(&manifest)?
+                .contains("smallvec = { version = \"0.6.10\", features = [\"union\"] }")
+        );
+        assert!(autodd.audit_report()?.fixes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_plan_round_trips_without_applying() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let manifest = temp_dir.path().join("Cargo.toml");
+        // regex and rayon are declared but unused, so nothing needs the network
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"test-package\"\nversion = \"0.1.0\"\n\n[dependencies]\nregex = \"1\"\nrayon = \"1\"\n",
+        )?;
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n")?;
+        let before = std::fs::read_to_string(&manifest)?;
+        let dump = temp_dir.path().join("plan.json");
+
+        let mut autodd = CargoAutodd::with_options(
+            temp_dir.path().to_path_buf(),
+            false,
+            false,
+            Config::default(),
+        );
+        autodd.set_verbosity(QUIET);
+        autodd.set_dump_plan(Some(dump.clone()));
+        autodd.sync()?;
+        assert_eq!(std::fs::read_to_string(&manifest)?, before);
+
+        let dumped: models::UpdatePlan = serde_json::from_str(&std::fs::read_to_string(&dump)?)?;
+        let change = |name: &str| models::PlannedChange {
+            table: "dependencies".to_string(),
+            name: name.to_string(),
+            version: None,
+            path: None,
+        };
+        assert_eq!(
+            dumped,
+            models::UpdatePlan {
+                removals: vec![change("rayon"), change("regex")],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            serde_json::to_string_pretty(&dumped)? + "\n",
+            std::fs::read_to_string(&dump)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_broken_member_manifest_is_reported_after_analysis() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"good\", \"broken\"]\n",
+        )?;
+        for (member, manifest, code) in [
+            (
+                "app",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\n",
+                "use good::run;\n\nfn main() {\n    run();\n}\n",
+            ),
+            (
+                "good",
+                "[package]\nname = \"good\"\nversion = \"0.1.0\"\n",
+                "pub fn run() {}\n",
+            ),
+            (
+                "broken",
+                "[package\nname = \"broken\"\n",
+                "pub fn run() {}\n",
+            ),
+        ] {
+            std::fs::create_dir_all(root.join(member).join("src"))?;
+            std::fs::write(root.join(member).join("Cargo.toml"), manifest)?;
+            std::fs::write(root.join(member).join("src/lib.rs"), code)?;
+        }
+
+        let app = root.join("app");
+        let autodd = CargoAutodd::with_options(app.clone(), false, true, Config::default());
+
+        // The healthy members are still resolved
+        let crate_refs = autodd.analyzer.analyze_dependencies()?;
+        assert!(crate_refs["good"].is_path_dependency);
+        let broken = autodd.analyzer.broken_members();
+        assert_eq!(broken.len(), 1);
+        assert!(broken[0].0.ends_with("broken/Cargo.toml"));
+
+        let err = autodd.analyze_and_update().unwrap_err().to_string();
+        assert!(err.contains("Skipped 1 workspace member(s)"), "{}", err);
+        assert!(err.contains("broken/Cargo.toml"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_reports_out_of_date_manifest() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        let before = std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?;
+
+        // tokio is used but not declared
+        let err = autodd.check().unwrap_err();
+        assert!(err.to_string().contains("tokio"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("Cargo.toml"))?,
+            before
+        );
+
+        std::fs::write(temp_dir.path().join("src/main.rs"), "use serde;\n")?;
+        autodd.check()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_suggests_essential_and_dev_only() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1.0\"\nserde_derive = \"1.0\"\n",
+        )?;
+        std::fs::create_dir(root.join("tests"))?;
+        std::fs::write(
+            root.join("tests/it.rs"),
+            "use rstest::rstest;\nuse pretty_assertions::assert_eq;\n",
+        )?;
+
+        let autodd = CargoAutodd::new(root.to_path_buf());
+        let config = autodd.suggested_config()?;
+        assert_eq!(config.essential, ["serde_derive".to_string()].into());
+        assert_eq!(
+            config.dev_only,
+            ["pretty_assertions".to_string(), "rstest".to_string()].into()
+        );
+
+        autodd.init()?;
+        let written = Config::load_default(root)?;
+        assert!(written.is_essential("serde_derive"));
+        assert!(written.is_dev_only("rstest"));
+
+        // An existing config is never overwritten
+        let err = autodd.init().unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_moved_dependency_keeps_version_and_features() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1.0\"\nregex = { version = \"1.5\", features = [\"unicode\"] }\n",
+        )?;
+        std::fs::write(root.join("src/main.rs"), "use serde;\nfn main() {}\n")?;
+        std::fs::create_dir(root.join("tests"))?;
+        std::fs::write(root.join("tests/it.rs"), "use regex::Regex;\n")?;
+
+        let mut autodd = CargoAutodd::new(root.to_path_buf());
+        // The declared requirement is reused, so no registry lookup is needed
+        autodd.set_offline(true);
+        autodd.analyze_and_update()?;
+
+        let doc =
+            std::fs::read_to_string(root.join("Cargo.toml"))?.parse::<toml_edit::DocumentMut>()?;
+        assert!(doc["dependencies"].get("regex").is_none());
+        let regex = &doc["dev-dependencies"]["regex"];
+        assert_eq!(regex["version"].as_str(), Some("1.5"));
+        assert_eq!(
+            regex["features"]
+                .as_array()
+                .unwrap()
+                .get(0)
+                .and_then(|f| f.as_str()),
+            Some("unicode")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verbosity_levels() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        assert_eq!(autodd.verbosity, NORMAL);
+
+        autodd.set_verbosity(DEBUG);
+        assert!(autodd.debug);
+
+        autodd.set_verbosity(QUIET);
+        assert!(!autodd.debug);
+        // Quiet runs still fail loudly when something is wrong
+        std::fs::write(temp_dir.path().join("src/main.rs"), "use regex::Regex;\n")?;
+        assert!(autodd.check().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_confirm_with_assume_yes() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.set_assume_yes(true);
+        assert!(autodd.confirm("Remove 3 unused dependencies?")?);
+        Ok(())
+    }
+
     #[test]
     fn test_generate_report() -> Result<()> {
         let temp_dir = create_test_environment()?;
@@ -221,6 +1926,19 @@ use tokio;
         Ok(())
     }
 
+    #[test]
+    fn test_generate_report_to_json_file() -> Result<()> {
+        let temp_dir = create_test_environment()?;
+        let output = temp_dir.path().join("report.json");
+        let mut autodd = CargoAutodd::new(temp_dir.path().to_path_buf());
+        autodd.set_output(Some(output.clone()));
+        autodd.generate_report()?;
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(output)?)?;
+        assert_eq!(json["dependencies"][0]["name"], "serde");
+        Ok(())
+    }
+
     #[test]
     fn test_check_security() -> Result<()> {
         let temp_dir = create_test_environment()?;
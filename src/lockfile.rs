@@ -0,0 +1,339 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use semver::Version;
+use serde::{Deserialize, Deserializer};
+
+/// A package in the resolved dependency graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub version: Version,
+    /// `registry+...`/`sparse+...` for registry crates, `git+...` for git checkouts and
+    /// `None` for path packages (workspace members included)
+    pub source: Option<String>,
+    /// Indices (into `ResolvedGraph::packages`) of the packages this one depends on
+    pub dependencies: Vec<usize>,
+}
+
+impl ResolvedPackage {
+    /// Whether the package comes from a registry (the only kind advisories cover)
+    pub fn is_registry(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+    }
+
+    /// Whether the package comes from crates.io
+    pub fn is_crates_io(&self) -> bool {
+        self.source.as_deref().is_some_and(|source| {
+            source.contains("github.com/rust-lang/crates.io-index")
+                || source.contains("index.crates.io")
+        })
+    }
+}
+
+/// The resolved dependency graph of a workspace, with one entry per `(name, version,
+/// source)` as in Cargo.lock
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedGraph {
+    pub packages: Vec<ResolvedPackage>,
+}
+
+/// `[[package]]` entry of Cargo.lock
+#[derive(Debug, Deserialize)]
+struct LockPackage {
+    name: String,
+    #[serde(deserialize_with = "version")]
+    version: Version,
+    source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockFile {
+    #[serde(default)]
+    package: Vec<LockPackage>,
+}
+
+/// The subset of `cargo metadata --format-version 1` output needed for the resolve graph
+#[derive(Debug, Deserialize)]
+struct ResolveMetadata {
+    packages: Vec<ResolvePackage>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolvePackage {
+    id: String,
+    name: String,
+    #[serde(deserialize_with = "version")]
+    version: Version,
+    source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Resolve {
+    nodes: Vec<ResolveNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolveNode {
+    id: String,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+fn version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+    Version::parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+impl ResolvedGraph {
+    /// Load the resolved graph of the workspace rooted at `workspace_root`.
+    ///
+    /// Reads Cargo.lock when there is one; otherwise asks `cargo metadata` to resolve the
+    /// workspace.
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let lock_path = workspace_root.join("Cargo.lock");
+        if lock_path.is_file() {
+            let content = fs::read_to_string(&lock_path)
+                .with_context(|| format!("Failed to read {}", lock_path.display()))?;
+            return Self::from_lockfile(&content)
+                .with_context(|| format!("Failed to parse {}", lock_path.display()));
+        }
+        Self::from_cargo(workspace_root)
+    }
+
+    /// Parse Cargo.lock. Dependencies are written as `name`, `name version` or
+    /// `name version (source)`, with only as much as is needed to be unambiguous.
+    pub fn from_lockfile(content: &str) -> Result<Self> {
+        let lock: LockFile = toml::from_str(content)?;
+        let find = |spec: &str| {
+            let mut parts = spec.splitn(3, ' ');
+            let name = parts.next().unwrap_or_default();
+            let version = parts.next();
+            let source = parts
+                .next()
+                .map(|source| source.trim_start_matches('(').trim_end_matches(')'));
+            lock.package.iter().position(|package| {
+                package.name == name
+                    && version.is_none_or(|version| package.version.to_string() == version)
+                    && source.is_none_or(|source| package.source.as_deref() == Some(source))
+            })
+        };
+
+        let packages = lock
+            .package
+            .iter()
+            .map(|package| {
+                let dependencies = package
+                    .dependencies
+                    .iter()
+                    .map(|spec| {
+                        find(spec).with_context(|| {
+                            format!("{} depends on unknown package {}", package.name, spec)
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(ResolvedPackage {
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    source: package.source.clone(),
+                    dependencies,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { packages })
+    }
+
+    /// Run `cargo metadata --format-version 1` for the workspace and read its resolve graph
+    pub fn from_cargo(workspace_root: &Path) -> Result<Self> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--format-version", "1", "--manifest-path"])
+            .arg(workspace_root.join("Cargo.toml"))
+            .output()
+            .context("Failed to run cargo metadata")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "cargo metadata failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Self::from_metadata(&output.stdout)
+    }
+
+    /// Build the graph from `cargo metadata` JSON
+    fn from_metadata(json: &[u8]) -> Result<Self> {
+        let metadata: ResolveMetadata =
+            serde_json::from_slice(json).context("Failed to parse cargo metadata output")?;
+        let index = metadata
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(i, package)| (package.id.as_str(), i))
+            .collect::<HashMap<_, _>>();
+        let mut dependencies = vec![Vec::new(); metadata.packages.len()];
+        for node in metadata
+            .resolve
+            .map(|resolve| resolve.nodes)
+            .unwrap_or_default()
+        {
+            if let Some(&i) = index.get(node.id.as_str()) {
+                dependencies[i] = node
+                    .dependencies
+                    .iter()
+                    .filter_map(|id| index.get(id.as_str()).copied())
+                    .collect();
+            }
+        }
+
+        let packages = metadata
+            .packages
+            .iter()
+            .zip(dependencies)
+            .map(|(package, dependencies)| ResolvedPackage {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                source: package.source.clone(),
+                dependencies,
+            })
+            .collect();
+        Ok(Self { packages })
+    }
+
+    /// Path packages named in `members` (workspace members are never from a registry)
+    pub fn member_indices(&self, members: &[String]) -> Vec<usize> {
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(|(_, package)| package.source.is_none() && members.contains(&package.name))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// For every package, the members that depend on it directly or transitively
+    pub fn dependent_members(&self, members: &[usize]) -> Vec<BTreeSet<String>> {
+        let mut dependents = vec![BTreeSet::new(); self.packages.len()];
+        for &member in members {
+            let name = &self.packages[member].name;
+            let mut stack = self.packages[member].dependencies.clone();
+            let mut seen = vec![false; self.packages.len()];
+            while let Some(i) = stack.pop() {
+                if std::mem::replace(&mut seen[i], true) {
+                    continue;
+                }
+                dependents[i].insert(name.clone());
+                stack.extend(&self.packages[i].dependencies);
+            }
+        }
+        dependents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    fn lockfile() -> String {
+        format!(
+            r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "core",
+ "rand 0.7.3",
+]
+
+[[package]]
+name = "core"
+version = "0.1.0"
+dependencies = [
+ "rand 0.8.5",
+]
+
+[[package]]
+name = "getrandom"
+version = "0.2.15"
+source = "{0}"
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "{0}"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "{0}"
+dependencies = [
+ "getrandom",
+]
+"#,
+            CRATES_IO
+        )
+    }
+
+    #[test]
+    fn test_from_lockfile_resolves_dependency_specs() -> Result<()> {
+        let graph = ResolvedGraph::from_lockfile(&lockfile())?;
+        assert_eq!(graph.packages.len(), 5);
+        let app = &graph.packages[0];
+        let deps = app
+            .dependencies
+            .iter()
+            .map(|&i| format!("{} {}", graph.packages[i].name, graph.packages[i].version))
+            .collect::<Vec<_>>();
+        assert_eq!(deps, ["core 0.1.0", "rand 0.7.3"]);
+        assert!(!app.is_registry());
+        assert!(graph.packages[2].is_registry() && graph.packages[2].is_crates_io());
+
+        assert!(
+            ResolvedGraph::from_lockfile(
+                "[[package]]\nname = \"a\"\nversion = \"1.0.0\"\ndependencies = [\"b\"]\n"
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependent_members_is_transitive() -> Result<()> {
+        let graph = ResolvedGraph::from_lockfile(&lockfile())?;
+        let members = graph.member_indices(&["app".to_string(), "core".to_string()]);
+        assert_eq!(members, [0, 1]);
+        let dependents = graph.dependent_members(&members);
+
+        let names = |i: usize| dependents[i].iter().cloned().collect::<Vec<_>>();
+        assert_eq!(names(1), ["app"]);
+        assert_eq!(names(2), ["app", "core"]); // getrandom, via core's rand 0.8.5
+        assert_eq!(names(3), ["app"]); // rand 0.7.3
+        assert_eq!(names(4), ["app", "core"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_metadata_reads_resolve_graph() -> Result<()> {
+        let json = br#"{
+            "packages": [
+                {"id": "app 0.1.0 (path+file:///ws/app)", "name": "app", "version": "0.1.0", "source": null},
+                {"id": "log 0.4.22 (registry+https://github.com/rust-lang/crates.io-index)", "name": "log", "version": "0.4.22", "source": "registry+https://github.com/rust-lang/crates.io-index"}
+            ],
+            "resolve": {"nodes": [
+                {"id": "app 0.1.0 (path+file:///ws/app)", "dependencies": ["log 0.4.22 (registry+https://github.com/rust-lang/crates.io-index)"]},
+                {"id": "log 0.4.22 (registry+https://github.com/rust-lang/crates.io-index)", "dependencies": []}
+            ]}
+        }"#;
+        let graph = ResolvedGraph::from_metadata(json)?;
+        assert_eq!(graph.packages[0].dependencies, [1]);
+        assert!(graph.packages[1].is_registry());
+        Ok(())
+    }
+}
@@ -1,24 +1,240 @@
 use anyhow::Result;
-use cargo_autodd::CargoAutodd;
-use clap::{App, Arg, SubCommand};
+use cargo_autodd::{CargoAutodd, Config, OutputFormat};
+use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 use std::env;
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
-    let matches = App::new("cargo-autodd")
+    let matches = cli().get_matches_from(cargo_args(env::args_os()));
+
+    let autodd_matches = matches
+        .subcommand_matches("autodd")
+        .expect("cargo_args always provides the autodd subcommand");
+
+    if let Some(threads) = global_value(autodd_matches, "threads") {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.parse()?)
+            .build_global()?;
+    }
+
+    let debug = autodd_matches.is_present("debug");
+    let verbosity = if global_flag(autodd_matches, "quiet") {
+        cargo_autodd::QUIET
+    } else if debug {
+        cargo_autodd::DEBUG
+    } else {
+        let extra = global_occurrences(autodd_matches, "verbose").min(2) as u8;
+        cargo_autodd::NORMAL + extra
+    };
+    let dry_run = global_flag(autodd_matches, "dry-run");
+    let current_dir = env::current_dir()?;
+
+    // Load config, with inline config taking precedence over the file
+    let config_path = autodd_matches
+        .value_of("config")
+        .map(PathBuf::from)
+        .or_else(|| cargo_autodd::config::find_config_upward(&current_dir))
+        .unwrap_or_else(|| current_dir.join(cargo_autodd::config::CONFIG_FILE));
+    let inline_config = if global_flag(autodd_matches, "config-stdin") {
+        if autodd_matches
+            .subcommand_matches("analyze")
+            .is_some_and(|m| m.is_present("stdin"))
+        {
+            anyhow::bail!("--config-stdin cannot be combined with `analyze --stdin`");
+        }
+        let mut inline = String::new();
+        io::stdin().read_to_string(&mut inline)?;
+        Some(inline)
+    } else {
+        global_value(autodd_matches, "config-inline").map(str::to_string)
+    };
+    let mut config = match inline_config {
+        Some(inline) => Config::load_with_inline(&config_path, &inline)?,
+        None => Config::load(&config_path)?,
+    };
+
+    apply_config_flags(&mut config, autodd_matches);
+
+    let mut autodd = CargoAutodd::with_options(current_dir, debug, dry_run, config);
+    autodd.set_output(global_value(autodd_matches, "output").map(PathBuf::from));
+    autodd.set_verbosity(verbosity);
+    autodd.set_changelog(global_value(autodd_matches, "changelog").map(PathBuf::from));
+    autodd.set_dump_plan(global_value(autodd_matches, "dump-plan").map(PathBuf::from));
+    autodd.set_assume_yes(global_flag(autodd_matches, "yes"));
+    autodd.set_locked(global_flag(autodd_matches, "locked"));
+    autodd.set_allow_dirty(global_flag(autodd_matches, "allow-dirty"));
+    autodd.set_allow_prerelease(global_flag(autodd_matches, "allow-prerelease"));
+    autodd.set_only(global_value(autodd_matches, "only").map(|names| {
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect()
+    }));
+    if let Some(list) = global_value(autodd_matches, "files-from") {
+        autodd.set_files_from(Path::new(list))?;
+    }
+    autodd.set_offline(global_flag(autodd_matches, "offline"));
+    autodd.set_explain_network(global_flag(autodd_matches, "explain-network"));
+    autodd.set_rollback(!global_flag(autodd_matches, "no-rollback"));
+    autodd.set_frozen(global_flag(autodd_matches, "frozen"));
+    autodd.set_max_age(global_value(autodd_matches, "max-age").and_then(|m| m.parse().ok()));
+    autodd.set_min_usage(global_value(autodd_matches, "min-usage").and_then(|n| n.parse().ok()));
+    autodd.set_format(
+        global_value(autodd_matches, "format")
+            .map(|f| f.parse::<OutputFormat>())
+            .transpose()?,
+    );
+
+    if autodd_matches.is_present("print-config") {
+        return autodd.print_config();
+    }
+    if autodd_matches.is_present("init") {
+        return autodd.init();
+    }
+    if autodd_matches.is_present("prune-features") {
+        return autodd.prune_features();
+    }
+    if autodd_matches.is_present("restore") {
+        return autodd.restore();
+    }
+
+    // Handle subcommands
+    match autodd_matches.subcommand_name() {
+        Some("update") => {
+            if verbosity >= cargo_autodd::NORMAL {
+                println!("Updating dependencies to latest versions...");
+            }
+            autodd.update_dependencies()?;
+        }
+        Some("sync") => {
+            if verbosity >= cargo_autodd::NORMAL {
+                println!("Syncing dependencies with the code...");
+            }
+            autodd.sync()?;
+        }
+        Some("report") => {
+            if verbosity >= cargo_autodd::NORMAL {
+                eprintln!("Generating dependency usage report...");
+            }
+            autodd.generate_report()?;
+        }
+        Some("security") => {
+            if verbosity >= cargo_autodd::NORMAL {
+                eprintln!("Checking for security vulnerabilities...");
+            }
+            let security_matches = autodd_matches.subcommand_matches("security");
+            if security_matches.is_some_and(|m| m.is_present("workspace")) {
+                autodd.set_advisory_db(
+                    security_matches
+                        .and_then(|m| m.value_of("advisory-db"))
+                        .map(PathBuf::from),
+                );
+                autodd.check_workspace_security()?;
+            } else {
+                autodd.check_security()?;
+            }
+        }
+        Some("check") => {
+            let check_matches = autodd_matches.subcommand_matches("check");
+            if check_matches.is_some_and(|m| m.is_present("changed")) {
+                let base = check_matches
+                    .and_then(|m| m.value_of("base"))
+                    .unwrap_or("origin/main");
+                autodd.check_changed(base)?;
+            } else {
+                autodd.check()?;
+            }
+        }
+        Some("explain") => {
+            let crate_name = autodd_matches
+                .subcommand_matches("explain")
+                .and_then(|m| m.value_of("crate"))
+                .unwrap_or_default();
+            autodd.explain(crate_name)?;
+        }
+        Some("diff") => {
+            let diff_matches = autodd_matches.subcommand_matches("diff");
+            let old = diff_matches
+                .and_then(|m| m.value_of("old"))
+                .unwrap_or_default();
+            autodd.diff(old, diff_matches.and_then(|m| m.value_of("new")))?;
+        }
+        Some("doctor") => {
+            autodd.doctor()?;
+        }
+        Some("analyze") => {
+            let file = autodd_matches
+                .subcommand_matches("analyze")
+                .and_then(|m| m.value_of("file"))
+                .map(PathBuf::from);
+            autodd.analyze(file.as_deref())?;
+        }
+        Some("audit") => {
+            let audit_matches = autodd_matches.subcommand_matches("audit");
+            autodd.set_advisory_db(
+                audit_matches
+                    .and_then(|m| m.value_of("advisory-db"))
+                    .map(PathBuf::from),
+            );
+            autodd.audit(audit_matches.is_some_and(|m| m.is_present("fix")))?;
+        }
+        _ => {
+            // Default behavior: analyze and update
+            autodd.analyze_and_update()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply the CLI flags that override config keys; they take precedence over the config
+/// file and inline config
+fn apply_config_flags(config: &mut Config, matches: &ArgMatches) {
+    config
+        .exclude
+        .extend(global_values(matches, "exclude").map(String::from));
+    config
+        .exclude_dirs
+        .extend(global_values(matches, "exclude-dir").map(PathBuf::from));
+    if global_flag(matches, "include-hidden") {
+        config.include_hidden = true;
+    }
+    if global_flag(matches, "include-generated") {
+        config.include_generated = true;
+    }
+    if global_flag(matches, "backup") {
+        config.backup = true;
+    }
+    if let Some(table) = global_value(matches, "target-table") {
+        config.target_table = Some(table.to_string());
+    }
+}
+
+fn cli() -> App<'static, 'static> {
+    // Every invocation is parsed as `cargo autodd ...` (see `cargo_args`), so help and
+    // `--version` name the binary `cargo-autodd` rather than `cargo-autodd-autodd`
+    App::new("cargo-autodd")
+        .bin_name("cargo")
         .version(env!("CARGO_PKG_VERSION"))
         .about("Automatically manages dependencies in your Rust projects")
         .subcommand(
             SubCommand::with_name("autodd")
+                .version(env!("CARGO_PKG_VERSION"))
                 .about("Analyze and update dependencies")
                 .arg(
                     Arg::with_name("debug")
                         .short("d")
                         .long("debug")
-                        .help("Enable debug output"),
+                        .help("Enable debug output (same as -vv)"),
                 )
                 .arg(
                     Arg::with_name("dry-run")
                         .long("dry-run")
+                        .global(true)
                         .help("Preview changes without modifying files"),
                 )
                 .arg(
@@ -26,64 +242,600 @@ fn main() -> Result<()> {
                         .short("c")
                         .long("config")
                         .value_name("FILE")
-                        .help("Path to config file (default: .cargo-autodd.toml)"),
+                        .help("Path to config file: TOML, or JSON/YAML by extension (default: .cargo-autodd.toml)"),
+                )
+                .arg(
+                    Arg::with_name("config-inline")
+                        .long("config-inline")
+                        .value_name("TOML")
+                        .conflicts_with("config-stdin")
+                        .global(true)
+                        .help("Config given as TOML; its keys override the config file's"),
+                )
+                .arg(
+                    Arg::with_name("config-stdin")
+                        .long("config-stdin")
+                        .global(true)
+                        .help("Read config as TOML from stdin; its keys override the config file's"),
+                )
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .value_name("CRATE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .global(true)
+                        .help("Exclude a crate from analysis, like `exclude` in the config; repeatable"),
+                )
+                .arg(
+                    Arg::with_name("exclude-dir")
+                        .long("exclude-dir")
+                        .value_name("DIR")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .global(true)
+                        .help("Skip a directory (relative to the project root); repeatable"),
+                )
+                .arg(
+                    Arg::with_name("target-table")
+                        .long("target-table")
+                        .value_name("TABLE")
+                        .global(true)
+                        .possible_values(&cargo_autodd::config::DEPENDENCY_TABLES)
+                        .help("Manage every dependency in TABLE instead of the detected one"),
+                )
+                .arg(
+                    Arg::with_name("files-from")
+                        .long("files-from")
+                        .value_name("FILE")
+                        .global(true)
+                        .help(
+                            "Analyze only the files (paths or globs, one per line) listed in \
+                             FILE instead of walking the project",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .long("threads")
+                        .value_name("N")
+                        .global(true)
+                        .validator(|v| match v.parse::<usize>() {
+                            Ok(n) if n > 0 => Ok(()),
+                            _ => Err("expected a positive number of threads".to_string()),
+                        })
+                        .help(
+                            "Analyze files on N threads (default: logical CPUs); \
+                         1 analyzes serially for reproducible debug output",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("init")
+                        .long("init")
+                        .help("Write a starter .cargo-autodd.toml based on the analysis"),
+                )
+                .arg(
+                    Arg::with_name("prune-features")
+                        .long("prune-features")
+                        .conflicts_with("init")
+                        .help(
+                            "Narrow declared feature lists to the features the code needs \
+                             (prints the changes first)",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("restore")
+                        .long("restore")
+                        .conflicts_with_all(&["init", "prune-features"])
+                        .help("Restore Cargo.toml from the backup written by the last rewrite"),
+                )
+                .arg(
+                    Arg::with_name("print-config")
+                        .long("print-config")
+                        .conflicts_with_all(&["init", "prune-features", "restore"])
+                        .help(
+                            "Print the effective configuration (config file, inline config \
+                             and flags merged) as TOML, or JSON with --format json",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("backup")
+                        .long("backup")
+                        .global(true)
+                        .help(
+                            "Copy Cargo.toml to Cargo.toml.autodd.bak before rewriting it \
+                             (undo with --restore)",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("include-hidden")
+                        .long("include-hidden")
+                        .global(true)
+                        .help("Also analyze hidden files and directories (.git, .cargo, ...)"),
+                )
+                .arg(
+                    Arg::with_name("include-generated")
+                        .long("include-generated")
+                        .global(true)
+                        .help("Also analyze files marked @generated in their first lines"),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .short("y")
+                        .long("yes")
+                        .global(true)
+                        .help("Assume \"yes\" to all prompts (non-interactive)"),
+                )
+                .arg(
+                    Arg::with_name("locked")
+                        .long("locked")
+                        .global(true)
+                        .help("Fail instead of modifying Cargo.toml when changes are needed"),
+                )
+                .arg(
+                    Arg::with_name("only")
+                        .long("only")
+                        .alias("dependencies-only")
+                        .value_name("CRATES")
+                        .global(true)
+                        .help(
+                            "Only add, remove, update or report the named crates \
+                             (comma-separated); leave every other dependency alone",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("allow-prerelease")
+                        .long("allow-prerelease")
+                        .global(true)
+                        .help("Consider pre-releases (e.g. 3.0.0-rc.1) when looking up the latest version"),
+                )
+                .arg(
+                    Arg::with_name("allow-dirty")
+                        .long("allow-dirty")
+                        .global(true)
+                        .help("Modify Cargo.toml even if it has uncommitted changes in git"),
+                )
+                .arg(
+                    Arg::with_name("frozen")
+                        .long("frozen")
+                        .global(true)
+                        .help("Like --locked, and also forbid network access"),
+                )
+                .arg(
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .global(true)
+                        .help("Never access the network; crates.io versions are left unresolved"),
+                )
+                .arg(
+                    Arg::with_name("explain-network")
+                        .long("explain-network")
+                        .global(true)
+                        .help(
+                            "Log every registry request (URL, HTTP status, timing) to stderr, \
+                             with credentials redacted",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("no-rollback")
+                        .long("no-rollback")
+                        .global(true)
+                        .help("Keep the modified Cargo.toml when `cargo check` fails afterwards"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .global(true)
+                        .help("Write the report to FILE instead of stdout"),
+                )
+                .arg(
+                    Arg::with_name("changelog")
+                        .long("changelog")
+                        .value_name("FILE")
+                        .global(true)
+                        .help(
+                            "Append a record of the changes made to Cargo.toml to FILE \
+                             (JSON lines for .json/.jsonl, markdown otherwise)",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("dump-plan")
+                        .long("dump-plan")
+                        .value_name("FILE")
+                        .global(true)
+                        .help(
+                            "Write the computed change plan as JSON to FILE and leave \
+                             Cargo.toml unchanged",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("verbose")
+                        .short("v")
+                        .long("verbose")
+                        .multiple(true)
+                        .global(true)
+                        .help(
+                            "Print per-crate detail, including download counts and publish \
+                         dates in reports; repeat (-vv) for debug output",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("quiet")
+                        .short("q")
+                        .long("quiet")
+                        .global(true)
+                        .conflicts_with("verbose")
+                        .help("Only print errors and requested reports"),
+                )
+                .arg(
+                    Arg::with_name("max-age")
+                        .long("max-age")
+                        .value_name("MONTHS")
+                        .validator(|v| {
+                            v.parse::<u32>()
+                                .map(|_| ())
+                                .map_err(|_| "expected a number of months".to_string())
+                        })
+                        .global(true)
+                        .help("Flag dependencies whose latest release is older than MONTHS"),
+                )
+                .arg(
+                    Arg::with_name("min-usage")
+                        .long("min-usage")
+                        .value_name("N")
+                        .validator(|v| {
+                            v.parse::<usize>()
+                                .map(|_| ())
+                                .map_err(|_| "expected a number of files".to_string())
+                        })
+                        .global(true)
+                        .help("List dependencies used in at most N files as low-usage in reports"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["text", "json", "sarif", "markdown"])
+                        .global(true)
+                        .help(
+                            "Report format; sarif is for `check`, markdown for `report` \
+                         (default: inferred from --output, else text)",
+                        ),
                 )
                 .subcommand(
                     SubCommand::with_name("update").about("Update dependencies to latest versions"),
                 )
+                .subcommand(SubCommand::with_name("sync").about(
+                    "Add missing, remove unused and update dependencies to compatible versions",
+                ))
                 .subcommand(
                     SubCommand::with_name("report").about("Generate dependency usage report"),
                 )
                 .subcommand(
-                    SubCommand::with_name("security").about("Check for security vulnerabilities"),
+                    SubCommand::with_name("security")
+                        .about("Check for security vulnerabilities")
+                        .arg(Arg::with_name("workspace").long("workspace").help(
+                            "Scan every member's resolved versions (Cargo.lock) in one pass, \
+                             including RustSec advisories",
+                        ))
+                        .arg(
+                            Arg::with_name("advisory-db")
+                                .long("advisory-db")
+                                .value_name("DIR")
+                                .requires("workspace")
+                                .help(
+                                    "Local advisory-db checkout \
+                                     (default: $CARGO_HOME/advisory-db, as used by cargo audit)",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Verify Cargo.toml matches the code without modifying it")
+                        .arg(Arg::with_name("changed").long("changed").help(
+                            "Only check the workspace members with files changed since --base",
+                        ))
+                        .arg(
+                            Arg::with_name("base")
+                                .long("base")
+                                .value_name("REV")
+                                .requires("changed")
+                                .help(
+                                    "Revision to diff against with --changed, from its merge \
+                                     base with HEAD (default: origin/main)",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("explain")
+                        .about("Explain why a crate would be kept, added or removed")
+                        .arg(
+                            Arg::with_name("crate")
+                                .value_name("CRATE")
+                                .required(true)
+                                .help("Name of the crate to explain"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("diff")
+                        .about("Show dependencies added, removed or re-versioned between two manifests")
+                        .arg(
+                            Arg::with_name("old")
+                                .value_name("OLD")
+                                .required(true)
+                                .help("Old Cargo.toml (or its directory) or git revision"),
+                        )
+                        .arg(
+                            Arg::with_name("new")
+                                .value_name("NEW")
+                                .help("New Cargo.toml or git revision (default: ./Cargo.toml)"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("doctor")
+                        .about("Check the environment (manifest, crates.io, proxy, cache)"),
+                )
+                .subcommand(
+                    SubCommand::with_name("analyze")
+                        .about("Print the crates used by one file or by stdin, without touching Cargo.toml")
+                        .arg(
+                            Arg::with_name("file")
+                                .long("file")
+                                .value_name("FILE")
+                                .help("Rust source file to analyze"),
+                        )
+                        .arg(
+                            Arg::with_name("stdin")
+                                .long("stdin")
+                                .help("Read the Rust source from standard input"),
+                        )
+                        .group(
+                            ArgGroup::with_name("input")
+                                .args(&["file", "stdin"])
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("audit")
+                        .about("Check dependencies against the RustSec advisory database")
+                        .arg(
+                            Arg::with_name("fix").long("fix").help(
+                                "Raise vulnerable dependencies to their lowest patched versions",
+                            ),
+                        )
+                        .arg(
+                            Arg::with_name("advisory-db")
+                                .long("advisory-db")
+                                .value_name("DIR")
+                                .help(
+                                    "Local advisory-db checkout \
+                                     (default: $CARGO_HOME/advisory-db, as used by cargo audit)",
+                                ),
+                        ),
                 ),
         )
-        .get_matches();
+}
 
-    // When cargo-autodd is called directly (not as a cargo subcommand)
-    if env::args().nth(1) != Some("autodd".to_string()) {
-        println!("This command should be run as 'cargo autodd'");
-        std::process::exit(1);
+/// Normalize argv to the shape cargo uses for custom subcommands.
+///
+/// `cargo autodd update` runs `cargo-autodd autodd update`, so the subcommand name arrives
+/// as the first argument; running the binary directly (`cargo-autodd update`) lacks it and
+/// gets it inserted.
+fn cargo_args(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    if args.get(1).is_none_or(|arg| arg != "autodd") {
+        args.insert(args.len().min(1), OsString::from("autodd"));
     }
+    args
+}
 
-    // Get the autodd subcommand matches
-    let autodd_matches = matches.subcommand_matches("autodd").unwrap_or_else(|| {
-        println!("Missing 'autodd' subcommand. Run 'cargo autodd --help' for usage information.");
-        std::process::exit(1);
-    });
+/// Read a global argument that may have been given before or after the subcommand
+fn global_value<'a>(matches: &'a ArgMatches, name: &str) -> Option<&'a str> {
+    matches
+        .subcommand()
+        .1
+        .and_then(|sub| sub.value_of(name))
+        .or_else(|| matches.value_of(name))
+}
 
-    let debug = autodd_matches.is_present("debug");
-    let dry_run = autodd_matches.is_present("dry-run");
-    let current_dir = env::current_dir()?;
+/// Values of a repeatable global option, whether given before or after the subcommand
+fn global_values<'a>(matches: &'a ArgMatches, name: &str) -> impl Iterator<Item = &'a str> {
+    matches
+        .subcommand()
+        .1
+        .and_then(|sub| sub.values_of(name))
+        .or_else(|| matches.values_of(name))
+        .into_iter()
+        .flatten()
+}
 
-    // Load config
-    let config = if let Some(config_path) = autodd_matches.value_of("config") {
-        cargo_autodd::Config::load(std::path::Path::new(config_path))?
-    } else {
-        cargo_autodd::Config::load_default(&current_dir)?
-    };
+/// Check a global flag that may have been given before or after the subcommand
+fn global_flag(matches: &ArgMatches, name: &str) -> bool {
+    matches.is_present(name)
+        || matches
+            .subcommand()
+            .1
+            .is_some_and(|sub| sub.is_present(name))
+}
 
-    let autodd = CargoAutodd::with_options(current_dir, debug, dry_run, config);
+/// Count a repeatable global flag, whether given before or after the subcommand
+fn global_occurrences(matches: &ArgMatches, name: &str) -> u64 {
+    let sub = matches
+        .subcommand()
+        .1
+        .map_or(0, |sub| sub.occurrences_of(name));
+    matches.occurrences_of(name).max(sub)
+}
 
-    // Handle subcommands
-    match autodd_matches.subcommand_name() {
-        Some("update") => {
-            println!("Updating dependencies to latest versions...");
-            autodd.update_dependencies()?;
-        }
-        Some("report") => {
-            println!("Generating dependency usage report...");
-            autodd.generate_report()?;
-        }
-        Some("security") => {
-            println!("Checking for security vulnerabilities...");
-            autodd.check_security()?;
-        }
-        _ => {
-            // Default behavior: analyze and update
-            autodd.analyze_and_update()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn parse(args: &[&str]) -> ArgMatches<'static> {
+        cli().get_matches_from(cargo_args(args.iter().map(OsString::from)))
+    }
+
+    #[test]
+    fn test_cargo_subcommand_invocation() {
+        let matches = parse(&["cargo-autodd", "autodd", "update", "--dry-run"]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        assert_eq!(autodd.subcommand_name(), Some("update"));
+        assert!(global_flag(autodd, "dry-run"));
+    }
+
+    #[test]
+    fn test_direct_invocation() {
+        let matches = parse(&["cargo-autodd", "update", "--dry-run"]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        assert_eq!(autodd.subcommand_name(), Some("update"));
+        assert!(global_flag(autodd, "dry-run"));
+
+        let matches = parse(&["cargo-autodd"]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        assert_eq!(autodd.subcommand_name(), None);
+
+        // `--version` is answered for both shapes instead of being rejected
+        for args in [
+            &["cargo-autodd", "--version"][..],
+            &["cargo-autodd", "autodd", "--version"],
+        ] {
+            let err = cli()
+                .get_matches_from_safe(cargo_args(args.iter().map(OsString::from)))
+                .unwrap_err();
+            assert_eq!(err.kind, clap::ErrorKind::VersionDisplayed);
         }
+
+        let matches = parse(&["cargo-autodd", "--debug"]);
+        assert!(
+            matches
+                .subcommand_matches("autodd")
+                .unwrap()
+                .is_present("debug")
+        );
     }
 
-    Ok(())
+    #[test]
+    fn test_analyze_requires_one_input() {
+        let matches = parse(&["cargo-autodd", "analyze", "--file", "src/lib.rs"]);
+        let analyze = matches
+            .subcommand_matches("autodd")
+            .and_then(|m| m.subcommand_matches("analyze"))
+            .unwrap();
+        assert_eq!(analyze.value_of("file"), Some("src/lib.rs"));
+
+        let args = |args: &[&str]| cargo_args(args.iter().map(OsString::from));
+        assert!(
+            cli()
+                .get_matches_from_safe(args(&["cargo-autodd", "analyze"]))
+                .is_err()
+        );
+        assert!(
+            cli()
+                .get_matches_from_safe(args(&[
+                    "cargo-autodd",
+                    "analyze",
+                    "--stdin",
+                    "--file",
+                    "a.rs"
+                ]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_print_config_includes_cli_overrides() -> Result<()> {
+        let matches = parse(&[
+            "cargo-autodd",
+            "--print-config",
+            "--exclude",
+            "internal_sdk",
+            "--exclude-dir",
+            "generated",
+        ]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        assert!(autodd.is_present("print-config"));
+
+        let mut config = Config::load_with_inline(
+            Path::new("missing.toml"),
+            "exclude = [\"legacy\"]\nversion_format = \"minor\"\n",
+        )?;
+        apply_config_flags(&mut config, autodd);
+        let printed = config.to_toml()?;
+        assert!(
+            printed.contains("exclude = [\"internal_sdk\", \"legacy\"]"),
+            "{}",
+            printed
+        );
+        assert!(printed.contains("exclude_dirs = [\"generated\"]"));
+        assert!(printed.contains("version_format = \"minor\""));
+        assert!(printed.contains("test_deps_as_dev = true"));
+
+        // The printed config reads back as the same configuration
+        let parsed: Config = toml::from_str(&printed)?;
+        assert!(parsed.should_exclude("internal_sdk"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanning_flags_after_subcommand() {
+        let matches = parse(&[
+            "cargo-autodd",
+            "check",
+            "--exclude",
+            "serde",
+            "--exclude-dir",
+            "generated",
+            "--include-hidden",
+            "--include-generated",
+            "--target-table",
+            "dev-dependencies",
+        ]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        let mut config = Config::default();
+        apply_config_flags(&mut config, autodd);
+        assert!(config.should_exclude("serde"));
+        assert_eq!(config.exclude_dirs, [PathBuf::from("generated")]);
+        assert!(config.include_hidden && config.include_generated);
+        assert_eq!(config.target_table.as_deref(), Some("dev-dependencies"));
+    }
+
+    #[test]
+    fn test_inline_config_after_subcommand() {
+        let matches = parse(&[
+            "cargo-autodd",
+            "check",
+            "--config-inline",
+            "exclude = [\"x\"]",
+        ]);
+        let autodd = matches.subcommand_matches("autodd").unwrap();
+        assert_eq!(
+            global_value(autodd, "config-inline"),
+            Some("exclude = [\"x\"]")
+        );
+
+        let matches = parse(&["cargo-autodd", "check", "--config-stdin"]);
+        assert!(global_flag(
+            matches.subcommand_matches("autodd").unwrap(),
+            "config-stdin"
+        ));
+
+        // Still mutually exclusive
+        assert!(
+            cli()
+                .get_matches_from_safe(cargo_args(
+                    [
+                        "cargo-autodd",
+                        "check",
+                        "--config-stdin",
+                        "--config-inline",
+                        "a = 1"
+                    ]
+                    .iter()
+                    .map(OsString::from)
+                ))
+                .is_err()
+        );
+    }
 }
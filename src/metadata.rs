@@ -0,0 +1,522 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use toml_edit::DocumentMut;
+
+use crate::config::CONFIG_FILE;
+use crate::utils::normalize_package_name;
+
+/// Workspace layout: the root directory and its member packages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceMetadata {
+    /// Directory containing the workspace root manifest
+    pub workspace_root: PathBuf,
+    /// Packages that are members of the workspace
+    pub members: Vec<WorkspaceMember>,
+    /// Member manifests that couldn't be read or parsed, with the error; the other
+    /// members are still resolved
+    pub broken_members: Vec<(PathBuf, String)>,
+}
+
+/// A package that belongs to the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMember {
+    /// Package name as declared in `[package] name`
+    pub name: String,
+    /// Path to the member's Cargo.toml
+    pub manifest_path: PathBuf,
+    /// Library target name when renamed with `[lib] name = "..."`
+    pub lib_name: Option<String>,
+    /// Binary target names (`[[bin]] name = "..."`)
+    pub bin_names: Vec<String>,
+}
+
+impl WorkspaceMetadata {
+    /// Members owning any of `files` (absolute paths), in member order. A file belongs to
+    /// the member with the deepest directory containing it; a change to the workspace
+    /// root's Cargo.toml or config file can affect every member, so it selects them all.
+    pub fn members_owning(&self, files: &[PathBuf]) -> Vec<&WorkspaceMember> {
+        let root_files = [
+            self.workspace_root.join("Cargo.toml"),
+            self.workspace_root.join(CONFIG_FILE),
+        ];
+        if files.iter().any(|file| root_files.contains(file)) {
+            return self.members.iter().collect();
+        }
+        let mut owned = vec![false; self.members.len()];
+        for file in files {
+            let owner = self
+                .members
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| file.starts_with(member.dir()))
+                .max_by_key(|(_, member)| member.dir().components().count());
+            if let Some((i, _)) = owner {
+                owned[i] = true;
+            }
+        }
+        self.members
+            .iter()
+            .zip(owned)
+            .filter(|(_, owned)| *owned)
+            .map(|(member, _)| member)
+            .collect()
+    }
+}
+
+impl WorkspaceMember {
+    /// Directory containing the member's Cargo.toml
+    pub fn dir(&self) -> &Path {
+        self.manifest_path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Name other packages use in `use` paths: the library target name, which defaults
+    /// to the package name with `-` replaced by `_`
+    pub fn import_name(&self) -> String {
+        normalize_package_name(self.lib_name.as_deref().unwrap_or(&self.name))
+    }
+
+    /// Every crate name this package's targets are compiled as (library and binaries)
+    pub fn target_names(&self) -> Vec<String> {
+        std::iter::once(self.import_name())
+            .chain(
+                self.bin_names
+                    .iter()
+                    .map(|name| normalize_package_name(name)),
+            )
+            .collect()
+    }
+}
+
+/// The subset of `cargo metadata --format-version 1` output cargo-autodd needs
+#[derive(Debug, Deserialize)]
+struct CargoMetadata {
+    packages: Vec<MetadataPackage>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataPackage {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+    #[serde(default)]
+    targets: Vec<MetadataTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataTarget {
+    name: String,
+    kind: Vec<String>,
+}
+
+/// Target kinds that produce a library other packages can import
+const LIB_KINDS: [&str; 5] = ["lib", "rlib", "dylib", "proc-macro", "cdylib"];
+
+/// Resolve the workspace containing `project_root`.
+///
+/// Asks `cargo metadata` first, so member globs and `exclude` are resolved exactly as
+/// cargo does; if cargo isn't available or fails, falls back to reading the manifests.
+pub fn load(project_root: &Path) -> Result<WorkspaceMetadata> {
+    from_cargo(project_root).or_else(|_| from_manifests(project_root))
+}
+
+/// Run `cargo metadata --format-version 1 --no-deps` for the project
+pub fn from_cargo(project_root: &Path) -> Result<WorkspaceMetadata> {
+    let output = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+        ])
+        .arg(project_root.join("Cargo.toml"))
+        .output()
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse(&output.stdout)
+}
+
+/// Deserialize `cargo metadata` JSON, keeping only workspace members
+fn parse(json: &[u8]) -> Result<WorkspaceMetadata> {
+    let metadata: CargoMetadata =
+        serde_json::from_slice(json).context("Failed to parse cargo metadata output")?;
+    let members = metadata
+        .packages
+        .into_iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .map(|package| {
+            let lib_name = package
+                .targets
+                .iter()
+                .find(|target| target.kind.iter().any(|k| LIB_KINDS.contains(&k.as_str())))
+                .map(|target| target.name.clone());
+            let bin_names = package
+                .targets
+                .iter()
+                .filter(|target| target.kind.iter().any(|k| k == "bin"))
+                .map(|target| target.name.clone())
+                .collect();
+            WorkspaceMember {
+                name: package.name,
+                manifest_path: package.manifest_path,
+                lib_name,
+                bin_names,
+            }
+        })
+        .collect();
+    Ok(WorkspaceMetadata {
+        workspace_root: metadata.workspace_root,
+        members,
+        ..Default::default()
+    })
+}
+
+/// Best-effort workspace resolution from the manifests alone.
+///
+/// Finds the nearest ancestor manifest with a `[workspace]` table and reads the members
+/// its `members` globs and `exclude` paths select. Without a workspace, the project's own
+/// package is the only member.
+pub fn from_manifests(project_root: &Path) -> Result<WorkspaceMetadata> {
+    let workspace = project_root.ancestors().find_map(|dir| {
+        let doc = read_manifest(&dir.join("Cargo.toml")).ok()?;
+        doc.get("workspace")
+            .is_some()
+            .then(|| (dir.to_path_buf(), doc))
+    });
+    let Some((workspace_root, doc)) = workspace else {
+        let members = read_member(&project_root.join("Cargo.toml"))?
+            .into_iter()
+            .collect();
+        return Ok(WorkspaceMetadata {
+            workspace_root: project_root.to_path_buf(),
+            members,
+            ..Default::default()
+        });
+    };
+
+    let paths = |key: &str| {
+        doc.get("workspace")
+            .and_then(|w| w.get(key))
+            .and_then(|m| m.as_array())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter_map(|m| m.as_str())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    let member_dirs = expand_members(&workspace_root, &paths("members"), &paths("exclude"));
+
+    // The root manifest is a member too when it declares a package. A broken member
+    // manifest is recorded instead of failing the whole workspace.
+    let mut members = Vec::new();
+    let mut broken_members = Vec::new();
+    for dir in std::iter::once(workspace_root.clone()).chain(member_dirs) {
+        let manifest_path = dir.join("Cargo.toml");
+        match read_member(&manifest_path) {
+            Ok(member) => members.extend(member),
+            Err(e) => broken_members.push((manifest_path, format!("{:#}", e))),
+        }
+    }
+
+    Ok(WorkspaceMetadata {
+        workspace_root,
+        members,
+        broken_members,
+    })
+}
+
+/// Resolve `[workspace] members` patterns (`crates/*`, `tools/gen-?`, ...) to the member
+/// directories they match, relative to `workspace_root`.
+///
+/// Only directories containing a Cargo.toml are kept, and anything inside an `exclude`
+/// path is dropped. The result is sorted and free of duplicates.
+pub fn expand_members(
+    workspace_root: &Path,
+    patterns: &[String],
+    exclude: &[String],
+) -> Vec<PathBuf> {
+    let excluded = exclude
+        .iter()
+        .map(|path| workspace_root.join(path))
+        .collect::<Vec<_>>();
+    let mut dirs = patterns
+        .iter()
+        .flat_map(|pattern| expand_glob(workspace_root, pattern))
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .filter(|dir| !excluded.iter().any(|excluded| dir.starts_with(excluded)))
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Paths matching one glob pattern relative to `root` (or an absolute one), expanded one
+/// path component at a time. Literal components are joined without checking that they
+/// exist.
+pub(crate) fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let root = if pattern.starts_with('/') {
+        Path::new("/")
+    } else {
+        root
+    };
+    let mut paths = vec![root.to_path_buf()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        let Some(regex) = glob_regex(component) else {
+            paths = paths.into_iter().map(|path| path.join(component)).collect();
+            continue;
+        };
+        let mut matches = paths
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| regex.is_match(&entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        matches.sort();
+        paths = matches;
+    }
+    paths
+}
+
+/// Regex for a path component with glob wildcards (`*`, `?`, `[a-z]`, `[!a]`); `None`
+/// for a literal component
+pub(crate) fn glob_regex(component: &str) -> Option<Regex> {
+    if !component.contains(['*', '?', '[']) {
+        return None;
+    }
+    let mut regex = String::from("^");
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let class = chars.by_ref().take_while(|&c| c != ']').collect::<String>();
+                let (negated, class) = match class.strip_prefix('!') {
+                    Some(class) => ("^", class),
+                    None => ("", class.as_str()),
+                };
+                regex.push_str(&format!("[{}{}]", negated, class.replace('\\', "\\\\")));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).ok()
+}
+
+fn read_manifest(path: &Path) -> Result<DocumentMut> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    content
+        .parse::<DocumentMut>()
+        .with_context(|| format!("Failed to parse {:?}", path))
+}
+
+/// Read a package manifest's name and its `[lib]`/`[[bin]]` target names.
+///
+/// Missing manifests and manifests without a package (virtual workspace roots) are
+/// `Ok(None)`; manifests that can't be read or parsed are errors.
+fn read_member(manifest_path: &Path) -> Result<Option<WorkspaceMember>> {
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let doc = read_manifest(manifest_path)?;
+    let Some(name) = doc
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+    else {
+        return Ok(None);
+    };
+    let lib_name = doc
+        .get("lib")
+        .and_then(|lib| lib.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string());
+    let bin_names = doc
+        .get("bin")
+        .and_then(|bins| bins.as_array_of_tables())
+        .map(|bins| {
+            bins.iter()
+                .filter_map(|bin| bin.get("name").and_then(|name| name.as_str()))
+                .map(|name| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Some(WorkspaceMember {
+        name,
+        manifest_path: manifest_path.to_path_buf(),
+        lib_name,
+        bin_names,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_workspace(root: &Path) -> Result<()> {
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n",
+        )?;
+        for name in ["core", "cli", "skipped"] {
+            let dir = root.join("crates").join(name);
+            fs::create_dir_all(dir.join("src"))?;
+            fs::write(
+                dir.join("Cargo.toml"),
+                format!("[package]\nname = \"demo-{}\"\nversion = \"0.1.0\"\n", name),
+            )?;
+            fs::write(dir.join("src/lib.rs"), "")?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cargo_metadata() -> Result<()> {
+        let json = br#"{
+            "packages": [
+                {"id": "a 0.1.0 (path+file:///ws/a)", "name": "a", "manifest_path": "/ws/a/Cargo.toml", "version": "0.1.0",
+                 "targets": [{"name": "a_engine", "kind": ["lib"]}, {"name": "a-cli", "kind": ["bin"]}]},
+                {"id": "b 0.1.0 (path+file:///ws/b)", "name": "b", "manifest_path": "/ws/b/Cargo.toml", "version": "0.1.0"}
+            ],
+            "workspace_members": ["a 0.1.0 (path+file:///ws/a)"],
+            "workspace_root": "/ws",
+            "version": 1
+        }"#;
+        let metadata = parse(json)?;
+        assert_eq!(metadata.workspace_root, PathBuf::from("/ws"));
+        assert_eq!(
+            metadata.members,
+            vec![WorkspaceMember {
+                name: "a".to_string(),
+                manifest_path: PathBuf::from("/ws/a/Cargo.toml"),
+                lib_name: Some("a_engine".to_string()),
+                bin_names: vec!["a-cli".to_string()],
+            }]
+        );
+        assert_eq!(metadata.members[0].import_name(), "a_engine");
+        assert_eq!(
+            metadata.members[0].target_names(),
+            vec!["a_engine", "a_cli"]
+        );
+        assert_eq!(metadata.members[0].dir(), Path::new("/ws/a"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_members_owning_changed_files() {
+        let member = |name: &str, dir: &str| WorkspaceMember {
+            name: name.to_string(),
+            manifest_path: Path::new(dir).join("Cargo.toml"),
+            lib_name: None,
+            bin_names: Vec::new(),
+        };
+        let metadata = WorkspaceMetadata {
+            workspace_root: PathBuf::from("/ws"),
+            members: vec![
+                member("app", "/ws/app"),
+                member("core", "/ws/core"),
+                member("core-macros", "/ws/core/macros"),
+            ],
+            ..Default::default()
+        };
+        let names = |files: &[&str]| {
+            let files = files.iter().map(PathBuf::from).collect::<Vec<_>>();
+            metadata
+                .members_owning(&files)
+                .iter()
+                .map(|member| member.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&["/ws/core/macros/src/lib.rs", "/ws/README.md"]),
+            ["core-macros"]
+        );
+        assert_eq!(names(&["/ws/core/src/lib.rs"]), ["core"]);
+        assert!(names(&["/ws/docs/guide.md"]).is_empty());
+        assert_eq!(names(&["/ws/Cargo.toml"]), ["app", "core", "core-macros"]);
+    }
+
+    #[test]
+    fn test_load_resolves_globs_and_excludes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_workspace(temp_dir.path())?;
+
+        let metadata = load(&temp_dir.path().join("crates/cli"))?;
+        let mut names: Vec<_> = metadata.members.iter().map(|m| m.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["demo-cli", "demo-core"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_members() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        create_workspace(root)?;
+        // Matched by the glob but not a package
+        fs::create_dir_all(root.join("crates/notes"))?;
+
+        let members = expand_members(
+            root,
+            &["crates/*".to_string()],
+            &["crates/skipped".to_string()],
+        );
+        assert_eq!(
+            members,
+            vec![root.join("crates/cli"), root.join("crates/core")]
+        );
+
+        let members = expand_members(root, &["crates/c[!l]*".to_string()], &[]);
+        assert_eq!(members, vec![root.join("crates/core")]);
+
+        // from_manifests resolves the same members as cargo metadata
+        let metadata = from_manifests(&root.join("crates/cli"))?;
+        let names: Vec<_> = metadata.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["demo-cli", "demo-core"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_manifests_reads_literal_members() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[workspace]\nmembers = [\"lib\"]\n",
+        )?;
+        fs::create_dir_all(root.join("lib"))?;
+        fs::write(
+            root.join("lib/Cargo.toml"),
+            "[package]\nname = \"app-lib\"\nversion = \"0.1.0\"\n\n[lib]\nname = \"engine\"\n",
+        )?;
+
+        let metadata = from_manifests(&root.join("lib"))?;
+        assert_eq!(metadata.workspace_root, root);
+        let names: Vec<_> = metadata.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "app-lib"]);
+        assert_eq!(metadata.members[0].import_name(), "app");
+        assert_eq!(metadata.members[1].import_name(), "engine");
+        Ok(())
+    }
+}
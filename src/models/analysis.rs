@@ -0,0 +1,45 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Crates detected in a single file or in stdin, as produced by `cargo autodd analyze`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceAnalysis {
+    /// The analyzed file, or `<stdin>`
+    pub input: String,
+    /// Detected crates, sorted by name
+    pub crates: Vec<DetectedCrate>,
+}
+
+/// A crate the analyzed source needs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DetectedCrate {
+    /// Name of the crate
+    pub name: String,
+    /// Whether the crate is only used from test code
+    pub dev_only: bool,
+    /// Number of `use`/`extern crate` statements naming the crate
+    pub import_count: usize,
+    /// Features implied by the usage (e.g. `fs` for `tokio::fs`)
+    pub features: Vec<String>,
+}
+
+impl fmt::Display for SourceAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.crates.is_empty() {
+            return writeln!(f, "No external crates used in {}", self.input);
+        }
+        writeln!(f, "Crates used in {}:", self.input)?;
+        for detected in &self.crates {
+            write!(f, "  {}", detected.name)?;
+            if detected.dev_only {
+                write!(f, " (dev)")?;
+            }
+            if !detected.features.is_empty() {
+                write!(f, " [features: {}]", detected.features.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Result of checking the declared dependencies against the RustSec advisories
+/// (`cargo autodd audit`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    /// Vulnerable dependencies that a higher version requirement fixes
+    pub fixes: Vec<AdvisoryFix>,
+    /// Advisories for which no release outside the affected ranges exists
+    pub unfixed: Vec<Advisory>,
+}
+
+/// A RustSec advisory affecting a declared dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Advisory {
+    /// Advisory identifier (e.g. `RUSTSEC-2023-0001`)
+    pub id: String,
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// Version requirement currently declared
+    pub current: String,
+    /// One-line description of the vulnerability
+    pub summary: String,
+}
+
+/// The requirement that moves a dependency out of every affected range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdvisoryFix {
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// Version requirement currently declared
+    pub current: String,
+    /// Lowest patched version, which becomes the new requirement
+    pub fixed: String,
+    /// Whether the current requirement already allows `fixed` (`cargo update` would do)
+    pub compatible: bool,
+    /// Advisories the new requirement resolves
+    pub advisories: Vec<String>,
+}
+
+impl AuditReport {
+    /// Whether any declared dependency is affected by an advisory
+    pub fn is_vulnerable(&self) -> bool {
+        !self.fixes.is_empty() || !self.unfixed.is_empty()
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\nDependency Audit Report")?;
+        writeln!(f, "=======================\n")?;
+        if !self.is_vulnerable() {
+            return writeln!(
+                f,
+                "✅ No known vulnerabilities in the declared dependencies."
+            );
+        }
+
+        if !self.fixes.is_empty() {
+            writeln!(f, "🔧 Fixable by raising the version requirement:")?;
+            for fix in &self.fixes {
+                writeln!(
+                    f,
+                    "  ~ [{}] {}: {} -> {} ({})",
+                    fix.table,
+                    fix.name,
+                    fix.current,
+                    fix.fixed,
+                    fix.advisories.join(", ")
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.unfixed.is_empty() {
+            writeln!(f, "⚠️ No patched version available:")?;
+            for advisory in &self.unfixed {
+                writeln!(
+                    f,
+                    "  ! [{}] {} {}: {} {}",
+                    advisory.table, advisory.name, advisory.current, advisory.id, advisory.summary
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -10,6 +10,12 @@ pub struct CrateReference {
     pub features: HashSet<String>,
     /// Set of file paths where this crate is used
     pub used_in: HashSet<PathBuf>,
+    /// Number of `use`/`extern crate` statements naming this crate, across all files
+    pub import_count: usize,
+    /// Files that name a declared but otherwise unused crate outside any import or path
+    /// the analysis recognizes (helper attributes, macro calls). Not counted as usage, but
+    /// enough to keep the crate from being removed.
+    pub mentioned_in: HashSet<PathBuf>,
     /// Whether this crate is a path dependency (internal crate)
     pub is_path_dependency: bool,
     /// Path to the internal crate if it's a path dependency
@@ -18,6 +24,19 @@ pub struct CrateReference {
     pub publish: Option<bool>,
     /// Whether this crate is a dev-dependency (used only in tests)
     pub is_dev_dependency: bool,
+    /// Whether this crate is redirected by `[patch]` or `[replace]`
+    pub is_patched: bool,
+    /// Features gating every usage of this crate (empty if used unconditionally)
+    pub gated_by_features: HashSet<String>,
+    /// Platform `cfg` predicate gating every usage of this crate, such as `windows` or
+    /// `target_os = "linux"` (`None` if used unconditionally or under different ones)
+    pub target_cfg: Option<String>,
+    /// Version requirement declared in Cargo.toml, if the crate is already declared
+    pub version: Option<String>,
+    /// Alternative registry the crate is declared from (`registry = "<name>"`)
+    pub registry: Option<String>,
+    /// Index URL the crate is declared from (`registry-index = "<url>"`)
+    pub registry_index: Option<String>,
 }
 
 impl CrateReference {
@@ -26,10 +45,18 @@ impl CrateReference {
             name,
             features: HashSet::new(),
             used_in: HashSet::new(),
+            import_count: 0,
+            mentioned_in: HashSet::new(),
             is_path_dependency: false,
             path: None,
             publish: None,
             is_dev_dependency: false,
+            is_patched: false,
+            gated_by_features: HashSet::new(),
+            target_cfg: None,
+            version: None,
+            registry: None,
+            registry_index: None,
         }
     }
 
@@ -38,10 +65,18 @@ impl CrateReference {
             name,
             features: HashSet::new(),
             used_in: HashSet::new(),
+            import_count: 0,
+            mentioned_in: HashSet::new(),
             is_path_dependency: true,
             path: Some(path),
             publish: None,
             is_dev_dependency: false,
+            is_patched: false,
+            gated_by_features: HashSet::new(),
+            target_cfg: None,
+            version: None,
+            registry: None,
+            registry_index: None,
         }
     }
 
@@ -50,10 +85,18 @@ impl CrateReference {
             name,
             features: HashSet::new(),
             used_in: HashSet::new(),
+            import_count: 0,
+            mentioned_in: HashSet::new(),
             is_path_dependency: false,
             path: None,
             publish: None,
             is_dev_dependency: true,
+            is_patched: false,
+            gated_by_features: HashSet::new(),
+            target_cfg: None,
+            version: None,
+            registry: None,
+            registry_index: None,
         }
     }
 
@@ -65,10 +108,46 @@ impl CrateReference {
         self.features.insert(feature);
     }
 
+    /// Take over the version, features and registry of the crate's existing manifest entry
+    pub fn inherit_declaration(&mut self, declared: &CrateReference) {
+        if self.version.is_none() {
+            self.version = declared.version.clone();
+        }
+        if self.registry.is_none() && self.registry_index.is_none() {
+            self.registry = declared.registry.clone();
+            self.registry_index = declared.registry_index.clone();
+        }
+        self.features.extend(declared.features.iter().cloned());
+    }
+
+    /// Add the usages and features found in another analysis of the same crate. The crate
+    /// stays platform-specific only while every usage is under the same `cfg`.
+    pub fn merge_usages(&mut self, other: CrateReference) {
+        if !self.used_in.is_empty() && self.target_cfg != other.target_cfg {
+            self.target_cfg = None;
+        } else {
+            self.target_cfg = other.target_cfg;
+        }
+        self.used_in.extend(other.used_in);
+        self.import_count += other.import_count;
+        self.features.extend(other.features);
+    }
+
+    /// Registry versions are looked up on: a registry name or an index URL (`None` for
+    /// crates.io)
+    pub fn registry_source(&self) -> Option<&str> {
+        self.registry.as_deref().or(self.registry_index.as_deref())
+    }
+
     pub fn usage_count(&self) -> usize {
         self.used_in.len()
     }
 
+    /// Whether the crate is used, or at least mentioned, anywhere in the code
+    pub fn is_referenced(&self) -> bool {
+        !self.used_in.is_empty() || !self.mentioned_in.is_empty()
+    }
+
     pub fn set_as_path_dependency(&mut self, path: String) {
         self.is_path_dependency = true;
         self.path = Some(path);
@@ -81,6 +160,22 @@ impl CrateReference {
     pub fn set_dev_dependency(&mut self, is_dev: bool) {
         self.is_dev_dependency = is_dev;
     }
+
+    pub fn set_patched(&mut self, is_patched: bool) {
+        self.is_patched = is_patched;
+    }
+
+    /// Whether the crate is only used behind feature gates and should be `optional`
+    pub fn is_optional(&self) -> bool {
+        !self.gated_by_features.is_empty()
+    }
+
+    /// The `[target.'cfg(...)'.dependencies]` table path for a platform-specific crate
+    pub fn target_table(&self) -> Option<String> {
+        self.target_cfg
+            .as_ref()
+            .map(|cfg| format!("target.cfg({}).dependencies", cfg))
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +193,8 @@ mod tests {
         assert!(crate_ref.path.is_none());
         assert!(crate_ref.publish.is_none());
         assert!(!crate_ref.is_dev_dependency);
+        assert!(!crate_ref.is_patched);
+        assert!(!crate_ref.is_optional());
     }
 
     #[test]
@@ -143,6 +240,43 @@ mod tests {
         assert_eq!(crate_ref.publish, Some(false));
     }
 
+    #[test]
+    fn test_inherit_declaration() {
+        let mut declared = CrateReference::new("regex".to_string());
+        declared.version = Some("1.5".to_string());
+        declared.add_feature("unicode".to_string());
+        declared.registry_index = Some("sparse+https://example.com/".to_string());
+
+        let mut used = CrateReference::new_dev("regex".to_string());
+        used.add_feature("std".to_string());
+        used.inherit_declaration(&declared);
+        assert_eq!(used.version.as_deref(), Some("1.5"));
+        assert_eq!(used.features.len(), 2);
+        assert_eq!(used.registry_source(), Some("sparse+https://example.com/"));
+        assert!(used.is_dev_dependency);
+    }
+
+    #[test]
+    fn test_merge_usages_keeps_common_target_cfg() {
+        let usage = |file: &str, cfg: Option<&str>| {
+            let mut crate_ref = CrateReference::new("winapi".to_string());
+            crate_ref.add_usage(Path::new(file).to_path_buf());
+            crate_ref.target_cfg = cfg.map(str::to_string);
+            crate_ref
+        };
+
+        let mut merged = CrateReference::new("winapi".to_string());
+        merged.merge_usages(usage("a.rs", Some("windows")));
+        merged.merge_usages(usage("b.rs", Some("windows")));
+        assert_eq!(
+            merged.target_table().as_deref(),
+            Some("target.cfg(windows).dependencies")
+        );
+
+        merged.merge_usages(usage("c.rs", None));
+        assert_eq!(merged.target_cfg, None);
+    }
+
     #[test]
     fn test_new_dev() {
         let crate_ref = CrateReference::new_dev("test_crate".to_string());
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::models::PlannedChange;
+
+/// Dependency changes between two manifests, as produced by `cargo autodd diff`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestDiff {
+    /// Where the old manifest was read from (a path or `<ref>:<path>`)
+    pub old: String,
+    /// Where the new manifest was read from
+    pub new: String,
+    /// Dependencies only the new manifest declares
+    pub added: Vec<PlannedChange>,
+    /// Dependencies only the old manifest declares
+    pub removed: Vec<PlannedChange>,
+    /// Dependencies declared in both with a different version requirement
+    pub changed: Vec<VersionChange>,
+}
+
+/// A dependency whose version requirement differs between the two manifests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionChange {
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// Requirement in the old manifest (`None` for path/git dependencies without one)
+    pub old: Option<String>,
+    /// Requirement in the new manifest
+    pub new: Option<String>,
+    /// How the new requirement relates to the old one
+    pub kind: ChangeKind,
+}
+
+/// How a changed version requirement relates to the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// The new minimum is newer but still allowed by the old requirement
+    Compatible,
+    /// The new minimum is outside the old requirement (e.g. a major bump)
+    Breaking,
+    /// The new minimum is older than the old one
+    Downgrade,
+    /// Anything else: equivalent spellings, added or dropped versions, unparsable requirements
+    Other,
+}
+
+impl ManifestDiff {
+    /// Whether both manifests declare the same dependencies
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for ManifestDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Dependency changes from {} to {}:", self.old, self.new)?;
+        if self.is_empty() {
+            return writeln!(f, "  (none)");
+        }
+        for change in &self.added {
+            match &change.version {
+                Some(version) => writeln!(
+                    f,
+                    "  + [{}] {} = \"{}\"",
+                    change.table, change.name, version
+                )?,
+                None => writeln!(f, "  + [{}] {}", change.table, change.name)?,
+            }
+        }
+        for change in &self.removed {
+            writeln!(f, "  - [{}] {}", change.table, change.name)?;
+        }
+        for change in &self.changed {
+            let version = |version: &Option<String>| version.as_deref().unwrap_or("-").to_string();
+            write!(
+                f,
+                "  ~ [{}] {}: {} -> {}",
+                change.table,
+                change.name,
+                version(&change.old),
+                version(&change.new)
+            )?;
+            match change.kind {
+                ChangeKind::Compatible => writeln!(f, " (compatible)")?,
+                ChangeKind::Breaking => writeln!(f, " (breaking)")?,
+                ChangeKind::Downgrade => writeln!(f, " (downgrade)")?,
+                ChangeKind::Other => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
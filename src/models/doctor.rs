@@ -0,0 +1,92 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Results of the environment checks run by `cargo autodd doctor`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DoctorReport {
+    /// Checks in the order they were run
+    pub checks: Vec<DoctorCheck>,
+}
+
+/// Outcome of a single environment check
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// Short name of what was checked
+    pub name: String,
+    /// Whether the check passed
+    pub status: CheckStatus,
+    /// What was found, or how to fix it
+    pub detail: String,
+}
+
+/// Status of a doctor check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Not fatal, but likely to make cargo-autodd skip work
+    Warn,
+    Fail,
+}
+
+impl DoctorCheck {
+    pub fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl DoctorReport {
+    /// Number of failed checks
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+            .count()
+    }
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = match self {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "❌",
+        };
+        f.write_str(marker)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🩺 cargo-autodd doctor")?;
+        for check in &self.checks {
+            writeln!(f, "  {} {}: {}", check.status, check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doctor_report_display() {
+        let report = DoctorReport {
+            checks: vec![
+                DoctorCheck::new("Cargo.toml", CheckStatus::Pass, "found"),
+                DoctorCheck::new("crates.io", CheckStatus::Fail, "connection refused"),
+            ],
+        };
+        assert_eq!(report.failures(), 1);
+        assert_eq!(
+            report.to_string(),
+            "🩺 cargo-autodd doctor\n  ✅ Cargo.toml: found\n  ❌ crates.io: connection refused\n"
+        );
+    }
+}
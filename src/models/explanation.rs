@@ -0,0 +1,161 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Decision trace for a single crate, as produced by `cargo autodd explain`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrateExplanation {
+    /// Name of the crate being explained
+    pub name: String,
+    /// Manifest tables the crate is declared in
+    pub declarations: Vec<Declaration>,
+    /// Whether the crate is on the built-in essential list
+    pub essential_builtin: bool,
+    /// Whether the crate is listed under `essential` in the config
+    pub essential_config: bool,
+    /// Whether the crate is listed under `exclude` in the config
+    pub excluded: bool,
+    /// Files (relative to the project root) the crate is used in
+    pub used_in: Vec<String>,
+    /// Whether the crate is only used from test code
+    pub dev_only: bool,
+    /// What cargo-autodd would do with the crate
+    pub decisions: Vec<Decision>,
+}
+
+/// A dependency entry found in the manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct Declaration {
+    /// Table the entry lives in (e.g. `dev-dependencies`)
+    pub table: String,
+    /// The entry as written in Cargo.toml
+    pub spec: String,
+    /// Where the dependency comes from
+    pub source: DependencySource,
+}
+
+/// Origin of a declared dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencySource {
+    Registry,
+    Path,
+    Git,
+    Workspace,
+}
+
+/// Action cargo-autodd would take for a crate in one table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Decision {
+    Keep { table: String, reason: String },
+    Add { table: String, reason: String },
+    Remove { table: String, reason: String },
+    Uncomment { table: String, reason: String },
+    Ignore { reason: String },
+}
+
+impl fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DependencySource::Registry => "registry",
+            DependencySource::Path => "path",
+            DependencySource::Git => "git",
+            DependencySource::Workspace => "workspace",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Keep { table, reason } => write!(f, "keep in [{}]: {}", table, reason),
+            Decision::Add { table, reason } => write!(f, "add to [{}]: {}", table, reason),
+            Decision::Remove { table, reason } => {
+                write!(f, "remove from [{}]: {}", table, reason)
+            }
+            Decision::Uncomment { table, reason } => {
+                write!(f, "uncomment in [{}]: {}", table, reason)
+            }
+            Decision::Ignore { reason } => write!(f, "nothing to do: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for CrateExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+        writeln!(f, "🔎 {}", self.name)?;
+        if self.declarations.is_empty() {
+            writeln!(f, "  Declared:  no")?;
+        } else {
+            for decl in &self.declarations {
+                writeln!(
+                    f,
+                    "  Declared:  [{}] {} ({})",
+                    decl.table, decl.spec, decl.source
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "  Essential: {} (built-in: {}, config: {})",
+            yes_no(self.essential_builtin || self.essential_config),
+            yes_no(self.essential_builtin),
+            yes_no(self.essential_config)
+        )?;
+        if self.excluded {
+            writeln!(f, "  Excluded:  yes (usages are ignored)")?;
+        }
+        writeln!(
+            f,
+            "  Usages:    {}{}",
+            self.used_in.len(),
+            if self.dev_only && !self.used_in.is_empty() {
+                " (tests only)"
+            } else {
+                ""
+            }
+        )?;
+        for file in &self.used_in {
+            writeln!(f, "    - {}", file)?;
+        }
+        writeln!(f, "  Decision:")?;
+        for decision in &self.decisions {
+            writeln!(f, "    {}", decision)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_explanation() {
+        let explanation = CrateExplanation {
+            name: "serde".to_string(),
+            declarations: vec![Declaration {
+                table: "dependencies".to_string(),
+                spec: "\"1.0\"".to_string(),
+                source: DependencySource::Registry,
+            }],
+            essential_builtin: true,
+            used_in: vec!["src/main.rs".to_string()],
+            decisions: vec![Decision::Keep {
+                table: "dependencies".to_string(),
+                reason: "used in 1 file(s)".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let text = explanation.to_string();
+        assert!(text.contains("[dependencies] \"1.0\" (registry)"));
+        assert!(text.contains("Essential: yes (built-in: yes, config: no)"));
+        assert!(text.contains("- src/main.rs"));
+        assert!(text.contains("keep in [dependencies]: used in 1 file(s)"));
+    }
+}
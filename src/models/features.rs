@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+
+/// Features of a crate the analyzed code needs, as inferred from the feature hints
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureUsage {
+    /// Features implied by the paths used from the crate
+    pub features: BTreeSet<String>,
+    /// Whether every use of the crate maps to a hint; globs (`tokio::*`), aliases and
+    /// items without a hint make the inference uncertain
+    pub certain: bool,
+}
+
+impl FeatureUsage {
+    /// Add what another file's usage implies; uncertainty anywhere is kept
+    pub fn merge(&mut self, other: FeatureUsage) {
+        self.features.extend(other.features);
+        self.certain &= other.certain;
+    }
+}
+
+/// A declared dependency whose feature list `--prune-features` narrows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeaturePruning {
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the dependency entry
+    pub name: String,
+    /// Features currently declared
+    pub current: Vec<String>,
+    /// Features that will be written
+    pub pruned: Vec<String>,
+}
+
+impl fmt::Display for FeaturePruning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "  ~ [{}] {} features: {:?} -> {:?}",
+            self.table, self.name, self.current, self.pruned
+        )
+    }
+}
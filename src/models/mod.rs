@@ -1,3 +1,23 @@
+mod analysis;
+mod audit;
 mod crate_reference;
+mod diff;
+mod doctor;
+mod explanation;
+mod features;
+mod plan;
+mod report;
 
+pub use analysis::{DetectedCrate, SourceAnalysis};
+pub use audit::{Advisory, AdvisoryFix, AuditReport};
 pub use crate_reference::CrateReference;
+pub use diff::{ChangeKind, ManifestDiff, VersionChange};
+pub use doctor::{CheckStatus, DoctorCheck, DoctorReport};
+pub use explanation::{CrateExplanation, Decision, Declaration, DependencySource};
+pub use features::{FeaturePruning, FeatureUsage};
+pub use plan::{CommentedDependency, PlannedChange, RunSummary, UpdatePlan, VersionUpdate};
+pub use report::{
+    BrokenPathDependency, DependencyReport, DependencyStatus, FailedLookup, MissingDependency,
+    OutdatedDependency, ResolvedOutdated, SecurityReport, UpdateKind, WorkspaceAdvisory,
+    WorkspaceSecurityReport,
+};
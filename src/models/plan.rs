@@ -0,0 +1,183 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Manifest changes cargo-autodd would make, computed without touching the network
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdatePlan {
+    /// Dependencies to add, in table order
+    pub additions: Vec<PlannedChange>,
+    /// Dependencies to remove, in table order
+    pub removals: Vec<PlannedChange>,
+    /// Version bumps of declared dependencies (only computed by `cargo autodd update`)
+    pub updates: Vec<VersionUpdate>,
+    /// Used names that are neither std crates nor declared and don't exist on the
+    /// registry, so they may be local modules; never added, listed for review
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ambiguous: Vec<String>,
+    /// Used crates whose declaration is commented out in Cargo.toml; uncommenting it is
+    /// suggested instead of adding a fresh entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commented_out: Vec<CommentedDependency>,
+}
+
+/// A dependency line commented out in a dependency table (`# serde = "1"`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentedDependency {
+    /// Manifest table the line sits in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// 1-based line number in Cargo.toml
+    pub line: usize,
+    /// The line as written, comment marker included
+    pub text: String,
+}
+
+impl fmt::Display for CommentedDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is commented out in [{}] on line {} of Cargo.toml; uncomment `{}`",
+            self.name, self.table, self.line, self.text
+        )
+    }
+}
+
+/// A single dependency entry to add or remove
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedChange {
+    /// Manifest table the change applies to (e.g. `dev-dependencies`)
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// Version that will be written for an addition, once resolved against the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Path an addition of a local crate is written with, instead of a version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// A declared dependency whose version requirement will be raised
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionUpdate {
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// Version requirement currently declared
+    pub current: String,
+    /// Requirement that will be written: the latest version, with the operator of the
+    /// declared requirement (`=`, `~`, ...) kept
+    pub latest: String,
+}
+
+/// Counts of the changes a run made to Cargo.toml, printed when it ends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    /// Dependencies added
+    pub added: usize,
+    /// Dependencies removed
+    pub removed: usize,
+    /// Version requirements raised
+    pub updated: usize,
+    /// Used crates left out because no version could be resolved for them, ambiguous
+    /// names included
+    pub unresolved: usize,
+}
+
+impl RunSummary {
+    /// Summarize an applied plan
+    pub fn new(applied: &UpdatePlan, unresolved: usize) -> Self {
+        Self {
+            added: applied.additions.len(),
+            removed: applied.removals.len(),
+            updated: applied.updates.len(),
+            unresolved,
+        }
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Added {}, removed {}, updated {}, {} unresolved.",
+            self.added, self.removed, self.updated, self.unresolved
+        )
+    }
+}
+
+impl UpdatePlan {
+    /// Whether the manifest is already up to date
+    pub fn is_empty(&self) -> bool {
+        !self.edits_manifest() && self.commented_out.is_empty()
+    }
+
+    /// Whether applying the plan writes to Cargo.toml; commented-out entries are only
+    /// pointed at
+    pub fn edits_manifest(&self) -> bool {
+        !(self.additions.is_empty() && self.removals.is_empty() && self.updates.is_empty())
+    }
+
+    /// The plan as `apply_plan` would write it: additions with neither a resolved version
+    /// nor a path (unresolved registry crates, patched crates) are left out
+    pub fn writable(&self) -> UpdatePlan {
+        UpdatePlan {
+            additions: self
+                .additions
+                .iter()
+                .filter(|change| change.version.is_some() || change.path.is_some())
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Number of used crates left undeclared once the plan was applied as `applied`:
+    /// additions that couldn't be resolved, ambiguous names and commented-out entries
+    pub fn unresolved(&self, applied: &UpdatePlan) -> usize {
+        self.additions.len().saturating_sub(applied.additions.len())
+            + self.ambiguous.len()
+            + self.commented_out.len()
+    }
+}
+
+impl fmt::Display for UpdatePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.additions {
+            match (&change.version, &change.path) {
+                (Some(version), _) => writeln!(
+                    f,
+                    "  + [{}] {} = \"{}\"",
+                    change.table, change.name, version
+                )?,
+                (None, Some(path)) => writeln!(
+                    f,
+                    "  + [{}] {} = {{ path = \"{}\" }}",
+                    change.table, change.name, path
+                )?,
+                (None, None) => writeln!(f, "  + [{}] {}", change.table, change.name)?,
+            }
+        }
+        for change in &self.removals {
+            writeln!(f, "  - [{}] {}", change.table, change.name)?;
+        }
+        for update in &self.updates {
+            writeln!(
+                f,
+                "  ~ [{}] {}: {} -> {}",
+                update.table, update.name, update.current, update.latest
+            )?;
+        }
+        for commented in &self.commented_out {
+            writeln!(
+                f,
+                "  # [{}] {}: uncomment line {}",
+                commented.table, commented.name, commented.line
+            )?;
+        }
+        Ok(())
+    }
+}
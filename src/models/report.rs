@@ -0,0 +1,171 @@
+use serde::Serialize;
+
+use crate::models::CommentedDependency;
+
+/// Structured result of a dependency usage report
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyReport {
+    /// The manifest table that was inspected (e.g. `dependencies`)
+    pub table: String,
+    /// Status of each declared dependency
+    pub dependencies: Vec<DependencyStatus>,
+    /// Declared registry dependencies with no detected usage (essential crates excluded)
+    pub unused: Vec<String>,
+    /// Used dependencies found in no more files than `--min-usage` (empty without it)
+    pub low_usage: Vec<String>,
+    /// Crates used in the code but not declared in any dependency table
+    pub missing: Vec<MissingDependency>,
+    /// Used names that are neither declared nor on the registry (possibly local modules)
+    pub ambiguous: Vec<String>,
+    /// Used crates whose entry is commented out in Cargo.toml
+    pub commented_out: Vec<CommentedDependency>,
+    /// Path dependencies whose directory or Cargo.toml doesn't exist
+    pub broken_paths: Vec<BrokenPathDependency>,
+}
+
+/// A path dependency pointing at a crate that isn't there (moved, renamed or deleted)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrokenPathDependency {
+    /// Manifest table the dependency is declared in
+    pub table: String,
+    /// Name of the crate
+    pub name: String,
+    /// The `path` as written in Cargo.toml
+    pub path: String,
+    /// What is missing
+    pub problem: String,
+}
+
+/// A crate the code uses that Cargo.toml doesn't declare
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingDependency {
+    /// Name of the crate
+    pub name: String,
+    /// Version that would be added (`None` if it couldn't be resolved)
+    pub version: Option<String>,
+}
+
+/// Report entry for a single declared dependency
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyStatus {
+    /// Name of the crate
+    pub name: String,
+    /// Version requirement declared in Cargo.toml
+    pub version: Option<String>,
+    /// Latest version available on the registry
+    pub latest: Option<String>,
+    /// Whether a newer version than the declared one is available
+    pub update_available: bool,
+    /// How the latest version relates to the declared requirement
+    pub update_kind: UpdateKind,
+    /// Error encountered while looking up the latest version
+    pub error: Option<String>,
+    /// Whether the crate is redirected by `[patch]`/`[replace]` (no registry lookup is done)
+    pub patched: bool,
+    /// Number of files the crate is used in
+    pub usage_count: usize,
+    /// Files (relative to the project root) the crate is used in
+    pub used_in: Vec<String>,
+    /// Files that only mention the crate by name (see `CrateReference::mentioned_in`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mentioned_in: Vec<String>,
+    /// Number of `use`/`extern crate` statements naming the crate (verbose reports only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_count: Option<usize>,
+    /// Total downloads (verbose reports only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<u64>,
+    /// Recent downloads (verbose reports only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_downloads: Option<u64>,
+    /// Publish date of the latest version (verbose reports only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
+    /// Months since the latest version was published (only with `--max-age`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub months_since_publish: Option<u32>,
+    /// Whether the latest release is older than `--max-age`
+    pub stale: bool,
+}
+
+/// Structured result of a security check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecurityReport {
+    /// Dependencies with newer versions available
+    pub outdated: Vec<OutdatedDependency>,
+}
+
+/// Structured result of a workspace-wide security check (`security --workspace`), with
+/// each resolved crate version reported once however many members use it
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkspaceSecurityReport {
+    /// Workspace members that were scanned
+    pub members: Vec<String>,
+    /// Resolved crates.io versions the members depend on directly that have newer releases
+    pub outdated: Vec<ResolvedOutdated>,
+    /// Advisories affecting any resolved crate, direct or transitive
+    pub vulnerable: Vec<WorkspaceAdvisory>,
+    /// Whether the RustSec advisory database was available to check against
+    pub advisories_checked: bool,
+    /// Crates whose latest version couldn't be looked up
+    pub failed_lookups: Vec<FailedLookup>,
+}
+
+/// A resolved crate version with a newer release, and the members that depend on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedOutdated {
+    pub name: String,
+    /// Version locked in Cargo.lock
+    pub current: String,
+    pub latest: String,
+    /// Whether `cargo update` picks up the latest version or a requirement has to change
+    pub kind: UpdateKind,
+    /// Members depending on this version directly
+    pub members: Vec<String>,
+}
+
+/// A RustSec advisory affecting a resolved crate version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceAdvisory {
+    /// Advisory identifier (e.g. `RUSTSEC-2023-0001`)
+    pub id: String,
+    pub name: String,
+    /// Version locked in Cargo.lock
+    pub version: String,
+    /// One-line description of the vulnerability
+    pub summary: String,
+    /// Version requirements that contain the fix
+    pub patched: Vec<String>,
+    /// Members depending on this version, directly or transitively
+    pub members: Vec<String>,
+}
+
+/// A registry lookup that failed even after retrying
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedLookup {
+    pub name: String,
+    pub error: String,
+}
+
+/// A dependency with a newer version available
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedDependency {
+    pub name: String,
+    pub current: String,
+    pub latest: String,
+    /// Whether the latest version is allowed by `current` or needs a requirement change
+    pub kind: UpdateKind,
+}
+
+/// How the latest registry version relates to a declared version requirement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    /// Nothing newer than the requirement's minimum exists
+    #[default]
+    UpToDate,
+    /// Newer, but still allowed by the requirement (`cargo update` picks it up)
+    Compatible,
+    /// Outside the requirement; the manifest has to change to use it
+    Breaking,
+}
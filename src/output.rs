@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+
+use crate::utils::write_atomic;
+
+/// Format used when rendering reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable text (default)
+    #[default]
+    Text,
+    /// Machine-readable JSON
+    Json,
+    /// SARIF 2.1.0 for code scanning (`check` only)
+    Sarif,
+    /// GitHub-flavored markdown for PR comments (`report` only)
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Infer the format from a file extension (`.json` => JSON, `.sarif` => SARIF, `.md` =>
+    /// markdown, anything else => text)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => OutputFormat::Json,
+            Some(ext) if ext.eq_ignore_ascii_case("sarif") => OutputFormat::Sarif,
+            Some(ext) if ext.eq_ignore_ascii_case("md") => OutputFormat::Markdown,
+            _ => OutputFormat::Text,
+        }
+    }
+
+    /// Resolve the effective format: an explicit `--format` wins over the output extension
+    pub fn resolve(explicit: Option<OutputFormat>, output: Option<&Path>) -> Self {
+        explicit
+            .or_else(|| output.map(Self::from_path))
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            other => Err(anyhow!("Unknown output format: {}", other)),
+        }
+    }
+}
+
+/// Destination for report output.
+///
+/// Reports are either streamed to stdout or buffered and written to a file
+/// atomically once rendering has finished.
+pub enum ReportWriter {
+    Stdout(io::Stdout),
+    File { path: PathBuf, buffer: Vec<u8> },
+}
+
+impl ReportWriter {
+    pub fn stdout() -> Self {
+        ReportWriter::Stdout(io::stdout())
+    }
+
+    pub fn file(path: PathBuf) -> Self {
+        ReportWriter::File {
+            path,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Create a writer for the given output path, falling back to stdout
+    pub fn for_output(output: Option<&Path>) -> Self {
+        match output {
+            Some(path) => Self::file(path.to_path_buf()),
+            None => Self::stdout(),
+        }
+    }
+
+    /// Flush buffered output, writing the destination file atomically
+    pub fn finish(self) -> Result<()> {
+        match self {
+            ReportWriter::Stdout(mut stdout) => {
+                stdout.flush()?;
+                Ok(())
+            }
+            ReportWriter::File { path, buffer } => {
+                write_atomic(&path, &buffer)?;
+                eprintln!("📄 Report written to {}", path.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Write for ReportWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            ReportWriter::Stdout(stdout) => stdout.write(buf),
+            ReportWriter::File { buffer, .. } => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ReportWriter::Stdout(stdout) => stdout.flush(),
+            ReportWriter::File { .. } => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            OutputFormat::from_path(Path::new("report.json")),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("report.txt")),
+            OutputFormat::Text
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("report")),
+            OutputFormat::Text
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("autodd.sarif")),
+            OutputFormat::Sarif
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("autodd.md")),
+            OutputFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn test_explicit_format_overrides_extension() {
+        let path = Path::new("report.txt");
+        assert_eq!(
+            OutputFormat::resolve(Some(OutputFormat::Json), Some(path)),
+            OutputFormat::Json
+        );
+        assert_eq!(OutputFormat::resolve(None, Some(path)), OutputFormat::Text);
+        assert_eq!(OutputFormat::resolve(None, None), OutputFormat::Text);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("TEXT".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
+        assert_eq!(
+            "sarif".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Sarif
+        );
+        assert_eq!(
+            "markdown".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Markdown
+        );
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_file_writer_writes_on_finish() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("report.txt");
+
+        let mut writer = ReportWriter::file(path.clone());
+        writeln!(writer, "hello")?;
+        assert!(!path.exists(), "file should not exist before finish");
+
+        writer.finish()?;
+        assert_eq!(fs::read_to_string(&path)?, "hello\n");
+        Ok(())
+    }
+}
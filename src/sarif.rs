@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::models::{CrateReference, UpdatePlan};
+use crate::utils::{normalize_package_name, slash_path};
+
+const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule for a crate the code uses but Cargo.toml doesn't declare
+pub const MISSING_DEPENDENCY: &str = "missing-dependency";
+/// Rule for a declared crate the code never uses
+pub const UNUSED_DEPENDENCY: &str = "unused-dependency";
+/// Rule for a declared crate with a newer release on crates.io
+pub const OUTDATED_DEPENDENCY: &str = "outdated-dependency";
+
+/// `(id, description)` of every rule results may refer to
+const RULES: [(&str, &str); 3] = [
+    (
+        MISSING_DEPENDENCY,
+        "Crate is used in the code but not declared in Cargo.toml",
+    ),
+    (
+        UNUSED_DEPENDENCY,
+        "Crate is declared in Cargo.toml but never used",
+    ),
+    (
+        OUTDATED_DEPENDENCY,
+        "A newer version of the crate is available on crates.io",
+    ),
+];
+
+/// A SARIF 2.1.0 log with a single run, limited to the properties cargo-autodd fills in
+#[derive(Debug, Clone, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub information_uri: &'static str,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: &'static str,
+    pub short_description: Message,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: &'static str,
+    pub level: &'static str,
+    pub message: Message,
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactLocation {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: usize,
+}
+
+impl SarifLog {
+    /// Turn the findings of `cargo autodd check` into SARIF results.
+    ///
+    /// Every result points at Cargo.toml (the dependency's line, or its table header for
+    /// missing crates); missing crates also point at their first usage site.
+    pub fn from_plan(
+        project_root: &Path,
+        plan: &UpdatePlan,
+        crate_refs: &HashMap<String, CrateReference>,
+    ) -> Self {
+        let manifest = fs::read_to_string(project_root.join("Cargo.toml")).unwrap_or_default();
+        let manifest_location =
+            |line: Option<usize>| Self::location("Cargo.toml".to_string(), line);
+
+        let mut results = Vec::new();
+        for change in &plan.additions {
+            let mut locations = vec![manifest_location(table_line(&manifest, &change.table))];
+            if let Some((path, line)) = crate_refs
+                .get(&change.name)
+                .and_then(|crate_ref| first_usage(&crate_ref.used_in, &change.name))
+            {
+                let uri = slash_path(path.strip_prefix(project_root).unwrap_or(&path));
+                locations.push(Self::location(uri, line));
+            }
+            results.push(SarifResult {
+                rule_id: MISSING_DEPENDENCY,
+                level: "error",
+                message: Message {
+                    text: format!(
+                        "`{}` is used but not declared in [{}]",
+                        change.name, change.table
+                    ),
+                },
+                locations,
+            });
+        }
+        for change in &plan.removals {
+            results.push(SarifResult {
+                rule_id: UNUSED_DEPENDENCY,
+                level: "warning",
+                message: Message {
+                    text: format!(
+                        "`{}` is declared in [{}] but never used",
+                        change.name, change.table
+                    ),
+                },
+                locations: vec![manifest_location(dependency_line(
+                    &manifest,
+                    &change.table,
+                    &change.name,
+                ))],
+            });
+        }
+        for update in &plan.updates {
+            results.push(SarifResult {
+                rule_id: OUTDATED_DEPENDENCY,
+                level: "note",
+                message: Message {
+                    text: format!(
+                        "`{}` {} can be updated to {}",
+                        update.name, update.current, update.latest
+                    ),
+                },
+                locations: vec![manifest_location(dependency_line(
+                    &manifest,
+                    &update.table,
+                    &update.name,
+                ))],
+            });
+        }
+
+        SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "cargo-autodd",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules: RULES
+                            .iter()
+                            .map(|(id, description)| Rule {
+                                id,
+                                short_description: Message {
+                                    text: description.to_string(),
+                                },
+                            })
+                            .collect(),
+                    },
+                },
+                results,
+            }],
+        }
+    }
+
+    fn location(uri: String, line: Option<usize>) -> Location {
+        Location {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation { uri },
+                region: line.map(|start_line| Region { start_line }),
+            },
+        }
+    }
+}
+
+/// 1-based line of the `[table]` header in a manifest
+fn table_line(manifest: &str, table: &str) -> Option<usize> {
+    manifest
+        .lines()
+        .position(|line| header(line) == Some(table))
+        .map(|index| index + 1)
+}
+
+/// 1-based line declaring `name` in `table`, as `name = ...` or `[table.name]`
+fn dependency_line(manifest: &str, table: &str, name: &str) -> Option<usize> {
+    let dotted = format!("{}.{}", table, name);
+    let mut current = None;
+    for (index, line) in manifest.lines().enumerate() {
+        if let Some(table_name) = header(line) {
+            if table_name == dotted {
+                return Some(index + 1);
+            }
+            current = Some(table_name);
+            continue;
+        }
+        let declares = line
+            .trim_start()
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with(['=', '.']));
+        if current == Some(table) && declares {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// Name of the table a `[header]` line opens
+fn header(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let name = line.strip_prefix('[')?.split(']').next()?;
+    (!line.starts_with("[[")).then(|| name.trim())
+}
+
+/// First file (in path order) and line that mention `crate_name`
+fn first_usage(used_in: &HashSet<PathBuf>, crate_name: &str) -> Option<(PathBuf, Option<usize>)> {
+    let path = used_in.iter().min()?.clone();
+    let pattern = Regex::new(&format!(
+        r"\b{}\b",
+        regex::escape(&normalize_package_name(crate_name))
+    ))
+    .ok()?;
+    let line = fs::read_to_string(&path).ok().and_then(|content| {
+        content
+            .lines()
+            .position(|line| pattern.is_match(line))
+            .map(|index| index + 1)
+    });
+    Some((path, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PlannedChange, VersionUpdate};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_lines() {
+        let manifest = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"\nregex.workspace = true\n\n[dependencies.tokio]\nversion = \"1\"\n\n[dev-dependencies]\nserde = \"1\"\n";
+        assert_eq!(table_line(manifest, "dependencies"), Some(4));
+        assert_eq!(dependency_line(manifest, "dependencies", "serde"), Some(5));
+        assert_eq!(dependency_line(manifest, "dependencies", "regex"), Some(6));
+        assert_eq!(dependency_line(manifest, "dependencies", "tokio"), Some(8));
+        assert_eq!(
+            dependency_line(manifest, "dev-dependencies", "serde"),
+            Some(12)
+        );
+        assert_eq!(dependency_line(manifest, "dependencies", "anyhow"), None);
+    }
+
+    #[test]
+    fn test_sarif_from_plan() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1.0.0\"\nregex = \"1\"\n",
+        )?;
+        fs::create_dir_all(root.join("src"))?;
+        let main_rs = root.join("src/main.rs");
+        fs::write(&main_rs, "fn main() {}\n\nuse serde_json::Value;\n")?;
+
+        let mut crate_ref = CrateReference::new("serde_json".to_string());
+        crate_ref.add_usage(main_rs);
+        let crate_refs = HashMap::from([("serde_json".to_string(), crate_ref)]);
+        let plan = UpdatePlan {
+            additions: vec![PlannedChange {
+                table: "dependencies".to_string(),
+                name: "serde_json".to_string(),
+                version: None,
+                path: None,
+            }],
+            removals: vec![PlannedChange {
+                table: "dependencies".to_string(),
+                name: "regex".to_string(),
+                version: None,
+                path: None,
+            }],
+            updates: vec![VersionUpdate {
+                table: "dependencies".to_string(),
+                name: "serde".to_string(),
+                current: "1.0.0".to_string(),
+                latest: "1.0.210".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let log = serde_json::to_value(SarifLog::from_plan(root, &plan, &crate_refs))?;
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "cargo-autodd");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+
+        let results = run["results"].as_array().unwrap();
+        let rule_ids: Vec<_> = results.iter().map(|r| r["ruleId"].clone()).collect();
+        assert_eq!(
+            rule_ids,
+            [MISSING_DEPENDENCY, UNUSED_DEPENDENCY, OUTDATED_DEPENDENCY]
+        );
+
+        let missing = &results[0]["locations"];
+        assert_eq!(
+            missing[0]["physicalLocation"]["artifactLocation"]["uri"],
+            "Cargo.toml"
+        );
+        assert_eq!(missing[0]["physicalLocation"]["region"]["startLine"], 4);
+        assert_eq!(
+            missing[1]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/main.rs"
+        );
+        assert_eq!(missing[1]["physicalLocation"]["region"]["startLine"], 3);
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            6
+        );
+        assert_eq!(
+            results[2]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            5
+        );
+        Ok(())
+    }
+}
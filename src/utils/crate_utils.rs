@@ -1,9 +1,11 @@
-use std::path::Path;
+use std::path::{Component, Path};
 
-/// Checks if a path represents a hidden file or directory
+/// Checks if a path represents a hidden file or directory (`.` and `..` don't count)
 pub fn is_hidden(path: &Path) -> bool {
-    path.components()
-        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    path.components().any(|c| match c {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
 }
 
 /// Checks if a crate name represents a standard library crate or type
@@ -54,6 +56,143 @@ pub fn is_essential_dep(name: &str) -> bool {
     essential_deps.contains(&name)
 }
 
+/// Checks if a crate is a well-known test, mocking or benchmark crate that belongs in
+/// dev-dependencies
+pub fn is_likely_dev_dep(name: &str) -> bool {
+    let dev_deps = [
+        "proptest",
+        "quickcheck",
+        "criterion",
+        "rstest",
+        "mockall",
+        "mockito",
+        "insta",
+        "pretty_assertions",
+        "assert_cmd",
+        "assert_fs",
+        "predicates",
+        "tempfile",
+        "serial_test",
+        "test_case",
+        "wiremock",
+        "httpmock",
+        "fake",
+    ];
+    dev_deps.contains(&name.replace('-', "_").as_str())
+}
+
+/// Checks if a crate provides derive or attribute macros that are often used without a
+/// `use` path the analyzer can see (e.g. through a re-export or `#[macro_use]`)
+pub fn is_macro_crate(name: &str) -> bool {
+    let macro_crates = [
+        "serde_derive",
+        "serde_repr",
+        "serde_with",
+        "async_trait",
+        "derive_more",
+        "derive_builder",
+        "derivative",
+        "educe",
+        "strum_macros",
+        "num_enum",
+        "typed_builder",
+        "getset",
+        "pin_project",
+        "pin_project_lite",
+        "enum_dispatch",
+        "lazy_static",
+        "ctor",
+        "tokio_macros",
+        "thiserror_impl",
+    ];
+    macro_crates.contains(&name.replace('-', "_").as_str())
+}
+
+/// Published crates whose library is not named after the package (`[lib] name`), as
+/// `(package, library)`
+const LIB_NAMES: &[(&str, &str)] = &[
+    ("md-5", "md5"),
+    ("sha-1", "sha1"),
+    ("rust-ini", "ini"),
+    ("rust-crypto", "crypto"),
+];
+
+/// Identifier code uses to import `package`: its library name if it is known to differ
+/// (`md5` for `md-5`), otherwise the package name with `-` replaced by `_`
+pub fn import_name(package: &str) -> String {
+    LIB_NAMES
+        .iter()
+        .find(|(name, _)| *name == package)
+        .map_or_else(|| package.replace('-', "_"), |(_, lib)| lib.to_string())
+}
+
+/// Cargo features implied by paths used from a crate, as `(crate, item, features)`
+const FEATURE_HINTS: &[(&str, &str, &[&str])] = &[
+    ("tokio", "main", &["macros", "rt-multi-thread"]),
+    ("tokio", "test", &["macros", "rt"]),
+    ("tokio", "select", &["macros"]),
+    ("tokio", "join", &["macros"]),
+    ("tokio", "try_join", &["macros"]),
+    ("tokio", "spawn", &["rt"]),
+    ("tokio", "task", &["rt"]),
+    ("tokio", "runtime", &["rt"]),
+    ("tokio", "fs", &["fs"]),
+    ("tokio", "net", &["net"]),
+    ("tokio", "sync", &["sync"]),
+    ("tokio", "time", &["time"]),
+    ("tokio", "process", &["process"]),
+    ("tokio", "signal", &["signal"]),
+];
+
+/// Features that only switch on other features (`tokio/full`); `--prune-features` may
+/// replace them with the hinted features the code needs, as `(crate, feature)`
+const UMBRELLA_FEATURES: &[(&str, &str)] = &[("tokio", "full")];
+
+/// Whether any feature hints are known for `crate_name`
+pub fn has_feature_hints(crate_name: &str) -> bool {
+    FEATURE_HINTS.iter().any(|(name, _, _)| *name == crate_name)
+}
+
+/// Crates with feature hints
+pub fn hinted_crates() -> Vec<&'static str> {
+    let mut crates = FEATURE_HINTS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .collect::<Vec<_>>();
+    crates.dedup();
+    crates
+}
+
+/// Whether using the top-level `item` of `crate_name` is covered by a feature hint
+pub fn has_item_hint(crate_name: &str, item: &str) -> bool {
+    FEATURE_HINTS
+        .iter()
+        .any(|(name, hint, _)| *name == crate_name && *hint == item)
+}
+
+/// Whether the hints account for `feature` of `crate_name`: a hinted item needs it, or it
+/// is an umbrella over such features. Other features may be needed in ways the hints
+/// can't see, so they are never pruned.
+pub fn is_hinted_feature(crate_name: &str, feature: &str) -> bool {
+    is_umbrella_feature(crate_name, feature)
+        || FEATURE_HINTS
+            .iter()
+            .any(|(name, _, features)| *name == crate_name && features.contains(&feature))
+}
+
+/// Whether `feature` of `crate_name` only switches on other features (`tokio/full`)
+pub fn is_umbrella_feature(crate_name: &str, feature: &str) -> bool {
+    UMBRELLA_FEATURES.contains(&(crate_name, feature))
+}
+
+/// Features of `crate_name` required to use its top-level `item` (e.g. `tokio::fs`)
+pub fn implied_features(crate_name: &str, item: &str) -> &'static [&'static str] {
+    FEATURE_HINTS
+        .iter()
+        .find(|(name, hint, _)| *name == crate_name && *hint == item)
+        .map_or(&[], |(_, _, features)| features)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,6 +203,8 @@ mod tests {
         assert!(is_hidden(Path::new("/path/to/.hidden")));
         assert!(!is_hidden(Path::new("visible")));
         assert!(!is_hidden(Path::new("/path/to/visible")));
+        assert!(!is_hidden(Path::new("src/../../shared/mod.rs")));
+        assert!(!is_hidden(Path::new("./src")));
     }
 
     #[test]
@@ -99,4 +240,68 @@ mod tests {
         assert!(!is_essential_dep("custom_crate"));
         assert!(!is_essential_dep("std"));
     }
+
+    #[test]
+    fn test_known_macro_crates() {
+        assert!(is_macro_crate("serde_derive"));
+        assert!(is_macro_crate("async-trait"));
+        assert!(!is_macro_crate("serde"));
+    }
+
+    #[test]
+    fn test_is_likely_dev_dep() {
+        for name in [
+            "criterion",
+            "proptest",
+            "quickcheck",
+            "mockall",
+            "rstest",
+            "insta",
+            "tempfile",
+            "pretty_assertions",
+        ] {
+            assert!(is_likely_dev_dep(name), "{}", name);
+        }
+        assert!(is_likely_dev_dep("pretty-assertions"));
+        assert!(is_likely_dev_dep("serial-test"));
+        assert!(!is_likely_dev_dep("tokio"));
+        assert!(!is_likely_dev_dep("regex"));
+    }
+
+    #[test]
+    fn test_implied_features() {
+        assert_eq!(implied_features("tokio", "fs"), &["fs"]);
+        assert_eq!(
+            implied_features("tokio", "main"),
+            &["macros", "rt-multi-thread"]
+        );
+        assert!(implied_features("tokio", "io").is_empty());
+        assert!(implied_features("serde", "fs").is_empty());
+        assert!(has_feature_hints("tokio"));
+        assert!(!has_feature_hints("serde"));
+        assert_eq!(hinted_crates(), ["tokio"]);
+        assert!(has_item_hint("tokio", "sync"));
+        assert!(!has_item_hint("tokio", "io"));
+    }
+
+    #[test]
+    fn test_is_hinted_feature() {
+        assert!(is_hinted_feature("tokio", "rt-multi-thread"));
+        assert!(is_hinted_feature("tokio", "full"));
+        assert!(is_umbrella_feature("tokio", "full"));
+        assert!(!is_umbrella_feature("tokio", "net"));
+        // `io-util` has no hint, so its use can't be ruled out
+        assert!(!is_hinted_feature("tokio", "io-util"));
+        assert!(!is_hinted_feature("serde", "derive"));
+    }
+
+    #[test]
+    fn test_import_name() {
+        assert_eq!(import_name("base64"), "base64");
+        assert_eq!(import_name("sha2"), "sha2");
+        assert_eq!(import_name("tokio-util"), "tokio_util");
+        assert_eq!(import_name("md-5"), "md5");
+        assert_eq!(import_name("sha-1"), "sha1");
+        assert_eq!(import_name("x509-parser"), "x509_parser");
+    }
 }
@@ -0,0 +1,155 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Writes `contents` to `path` atomically.
+///
+/// The data is first written to a temporary file in the same directory and then
+/// renamed over the destination, so readers never observe a partially written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = temp_path_for(path);
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed to write temporary file {:?}", tmp_path))?;
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to move {:?} to {:?}", tmp_path, path));
+    }
+    Ok(())
+}
+
+/// Path of `to` relative to the directory `from` (e.g. `../core`), for writing `path = ...`
+/// dependencies. Both paths should be absolute.
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from = fs::canonicalize(from).unwrap_or_else(|_| from.to_path_buf());
+    let to = fs::canonicalize(to).unwrap_or_else(|_| to.to_path_buf());
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in from.components().skip(common) {
+        relative.push("..");
+    }
+    for component in to.components().skip(common) {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+/// Render a path with `/` separators, so reports and manifests look the same on every
+/// platform (e.g. `tests\\cli.rs` becomes `tests/cli.rs`)
+pub fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Number of leading lines searched for an `@generated` marker
+const GENERATED_MARKER_LINES: usize = 5;
+
+/// Whether a source file is marked as generated (`// @generated` within its first lines,
+/// as written by prost, bindgen wrappers and similar tools). Unreadable files are not.
+pub fn is_generated_file(path: &Path) -> bool {
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    BufReader::new(file)
+        .lines()
+        .take(GENERATED_MARKER_LINES)
+        .map_while(Result::ok)
+        .any(|line| line.contains("@generated"))
+}
+
+/// Returns the sibling temporary path used by `write_atomic`
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_generated_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let generated = temp_dir.path().join("api.rs");
+        fs::write(
+            &generated,
+            "// This file is @generated by prost-build.\nuse prost;\n",
+        )?;
+        assert!(is_generated_file(&generated));
+
+        // Only the first lines are searched
+        let handwritten = temp_dir.path().join("lib.rs");
+        fs::write(
+            &handwritten,
+            format!(
+                "{}// @generated\n",
+                "use serde;\n".repeat(GENERATED_MARKER_LINES)
+            ),
+        )?;
+        assert!(!is_generated_file(&handwritten));
+        assert!(!is_generated_file(&temp_dir.path().join("missing.rs")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_atomic_creates_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("report.json");
+
+        write_atomic(&path, b"{}")?;
+
+        assert_eq!(fs::read_to_string(&path)?, "{}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_and_cleans_up() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("report.txt");
+        fs::write(&path, "old")?;
+
+        write_atomic(&path, b"new")?;
+
+        assert_eq!(fs::read_to_string(&path)?, "new");
+        let leftovers = fs::read_dir(temp_dir.path())?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0, "temporary file should be renamed away");
+        Ok(())
+    }
+
+    #[test]
+    fn test_slash_path() {
+        assert_eq!(slash_path(Path::new("tests\\cli.rs")), "tests/cli.rs");
+        assert_eq!(slash_path(Path::new("src/lib.rs")), "src/lib.rs");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/ws/crates/cli"), Path::new("/ws/crates/core")),
+            PathBuf::from("../core")
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws"), Path::new("/ws/crates/core")),
+            PathBuf::from("crates/core")
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws"), Path::new("/ws")),
+            PathBuf::from(".")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Normalize a package name so `-` and `_` spellings compare equal, as cargo does
+pub fn normalize_package_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Crates redirected by `[patch.<registry>]` or `[replace]`, as normalized names.
+///
+/// `[replace]` keys are package ids (`name:version`); only the name part is kept.
+pub fn patched_crates(doc: &DocumentMut) -> HashSet<String> {
+    let mut patched = HashSet::new();
+
+    if let Some(patch) = doc.get("patch").and_then(|p| p.as_table()) {
+        for (_, registry) in patch.iter() {
+            if let Some(registry) = registry.as_table() {
+                patched.extend(
+                    registry
+                        .iter()
+                        .map(|(name, _)| normalize_package_name(name)),
+                );
+            }
+        }
+    }
+
+    if let Some(replace) = doc.get("replace").and_then(|r| r.as_table()) {
+        patched.extend(replace.iter().map(|(id, _)| {
+            let name = id.split(':').next().unwrap_or(id);
+            normalize_package_name(name)
+        }));
+    }
+
+    patched
+}
+
+/// The package's `publish` setting as a bool, following `publish.workspace = true` into
+/// `[workspace.package]` of `workspace_doc` (which may be the package manifest itself).
+///
+/// `publish = []` counts as not publishable; a non-empty registry list as publishable.
+pub fn package_publish(doc: &DocumentMut, workspace_doc: Option<&DocumentMut>) -> Option<bool> {
+    let publish = doc.get("package")?.get("publish")?;
+    if inherits_from_workspace(publish) {
+        let inherited = workspace_doc?
+            .get("workspace")?
+            .get("package")?
+            .get("publish")?;
+        return publish_value(inherited.as_value()?);
+    }
+    publish_value(publish.as_value()?)
+}
+
+/// The package's `edition`, following `edition.workspace = true` into
+/// `[workspace.package]` of `workspace_doc`. `None` if it isn't set.
+pub fn package_edition(doc: &DocumentMut, workspace_doc: Option<&DocumentMut>) -> Option<String> {
+    let mut edition = doc.get("package")?.get("edition")?;
+    if inherits_from_workspace(edition) {
+        edition = workspace_doc?
+            .get("workspace")?
+            .get("package")?
+            .get("edition")?;
+    }
+    edition.as_str().map(str::to_string)
+}
+
+fn inherits_from_workspace(item: &Item) -> bool {
+    let workspace = match item {
+        Item::Table(t) => t.get("workspace").and_then(|v| v.as_bool()),
+        Item::Value(v) => v
+            .as_inline_table()
+            .and_then(|t| t.get("workspace"))
+            .and_then(|v| v.as_bool()),
+        _ => None,
+    };
+    workspace == Some(true)
+}
+
+fn publish_value(value: &Value) -> Option<bool> {
+    match value {
+        Value::Boolean(b) => Some(*b.value()),
+        Value::Array(registries) => Some(!registries.is_empty()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patched_crates() {
+        let doc = r#"
+[package]
+name = "test-package"
+version = "0.1.0"
+
+[patch.crates-io]
+serde-json = { git = "https://github.com/serde-rs/json" }
+
+[patch."https://github.com/example/repo"]
+example = { path = "../example" }
+
+[replace]
+"regex:1.10.0" = { path = "../regex" }
+"#
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        let patched = patched_crates(&doc);
+        assert_eq!(patched.len(), 3);
+        assert!(patched.contains("serde_json"));
+        assert!(patched.contains("example"));
+        assert!(patched.contains("regex"));
+    }
+
+    #[test]
+    fn test_package_edition() {
+        let parse = |s: &str| s.parse::<DocumentMut>().unwrap();
+        let workspace = parse("[workspace.package]\nedition = \"2015\"\n");
+
+        let explicit = parse("[package]\nname = \"a\"\nedition = \"2021\"\n");
+        assert_eq!(package_edition(&explicit, None).as_deref(), Some("2021"));
+
+        let inherited = parse("[package]\nname = \"a\"\nedition.workspace = true\n");
+        assert_eq!(
+            package_edition(&inherited, Some(&workspace)).as_deref(),
+            Some("2015")
+        );
+        assert_eq!(package_edition(&inherited, None), None);
+        assert_eq!(
+            package_edition(&parse("[package]\nname = \"a\"\n"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_package_publish() {
+        let parse = |s: &str| s.parse::<DocumentMut>().unwrap();
+        let workspace = parse("[workspace.package]\npublish = false\n");
+
+        let explicit = parse("[package]\nname = \"a\"\npublish = false\n");
+        assert_eq!(package_publish(&explicit, None), Some(false));
+
+        let registries = parse("[package]\nname = \"a\"\npublish = []\n");
+        assert_eq!(package_publish(&registries, None), Some(false));
+
+        let inherited = parse("[package]\nname = \"a\"\npublish.workspace = true\n");
+        assert_eq!(package_publish(&inherited, Some(&workspace)), Some(false));
+        assert_eq!(package_publish(&inherited, None), None);
+
+        let unset = parse("[package]\nname = \"a\"\n");
+        assert_eq!(package_publish(&unset, Some(&workspace)), None);
+    }
+}
@@ -1,3 +1,15 @@
 mod crate_utils;
+mod fs_utils;
+mod manifest_utils;
+mod time_utils;
 
-pub use crate_utils::{is_essential_dep, is_hidden, is_std_crate};
+pub use crate_utils::{
+    has_feature_hints, has_item_hint, hinted_crates, implied_features, import_name,
+    is_essential_dep, is_hidden, is_hinted_feature, is_likely_dev_dep, is_macro_crate,
+    is_std_crate, is_umbrella_feature,
+};
+pub use fs_utils::{is_generated_file, relative_path, slash_path, write_atomic};
+pub use manifest_utils::{
+    normalize_package_name, package_edition, package_publish, patched_crates,
+};
+pub use time_utils::{months_since, rfc3339_utc};
@@ -0,0 +1,112 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Average month length in days, good enough for "published N months ago" markers
+const DAYS_PER_MONTH: f64 = 30.436875;
+
+/// Whole months between an RFC 3339 timestamp (e.g. crates.io's `created_at`) and `now`.
+///
+/// Only the date part is used. Returns `None` if the timestamp can't be parsed.
+pub fn months_since(timestamp: &str, now: SystemTime) -> Option<u32> {
+    let published = parse_date(timestamp)?;
+    let now_days = now.duration_since(UNIX_EPOCH).ok()?.as_secs() / 86_400;
+    let elapsed = (now_days as i64 - published).max(0);
+    Some((elapsed as f64 / DAYS_PER_MONTH) as u32)
+}
+
+/// Format `time` as an RFC 3339 UTC timestamp with second precision (`2024-02-29T10:00:00Z`)
+pub fn rfc3339_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let seconds_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Parse the `YYYY-MM-DD` prefix of a timestamp into days since the Unix epoch
+fn parse_date(timestamp: &str) -> Option<i64> {
+    let date = timestamp.get(..10)?;
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<i64>().ok()?;
+    let day = parts.next()?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01, the inverse of
+/// [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+    }
+
+    #[test]
+    fn test_rfc3339_utc() {
+        for days in [0, 11_017, 19_782, -1] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        let time = UNIX_EPOCH + Duration::from_secs(19_782 * 86_400 + 36_000 + 62);
+        assert_eq!(rfc3339_utc(time), "2024-02-29T10:01:02Z");
+    }
+
+    #[test]
+    fn test_months_since() {
+        // 2024-02-29T00:00:00Z
+        let now = UNIX_EPOCH + Duration::from_secs(19_782 * 86_400);
+        assert_eq!(
+            months_since("2024-02-29T10:00:00.000000+00:00", now),
+            Some(0)
+        );
+        assert_eq!(months_since("2022-08-29T10:00:00Z", now), Some(18));
+        assert_eq!(months_since("2025-01-01T00:00:00Z", now), Some(0));
+        assert_eq!(months_since("not a date", now), None);
+    }
+}
@@ -0,0 +1,14 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+}
+
+fn main() -> Result<()> {
+    let re = Regex::new(r"^\w+$")?;
+    println!("{}", re.is_match("entry"));
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use clap::Parser;
+use log::warn;
+
+#[derive(Parser)]
+struct Args {
+    name: String,
+}
+
+fn main() {
+    let args = Args::parse();
+    warn!("hello {}", args.name);
+}
@@ -0,0 +1,9 @@
+use tokio::fs;
+
+#[cfg(feature = "json")]
+mod json;
+
+#[tokio::main]
+async fn main() {
+    let _ = fs::read_to_string("settings.json").await;
+}
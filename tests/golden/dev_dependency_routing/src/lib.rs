@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Config {
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    #[test]
+    fn random() {
+        let _: u8 = rand::thread_rng().r#gen();
+    }
+}
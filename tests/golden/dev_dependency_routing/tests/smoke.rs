@@ -0,0 +1,7 @@
+use tempfile::TempDir;
+
+#[test]
+fn smoke() {
+    let dir = TempDir::new().unwrap();
+    assert!(dir.path().exists());
+}
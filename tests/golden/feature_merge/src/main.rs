@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+#[derive(Serialize, Deserialize)]
+struct Settings {
+    path: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let _ = fs::read_to_string("settings.json").await;
+}
@@ -0,0 +1,8 @@
+use forked::Client;
+use regex::Regex;
+
+fn main() {
+    let re = Regex::new("a+").unwrap();
+    println!("{}", re.is_match("aaa"));
+    Client::default().run();
+}
@@ -0,0 +1,13 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Settings {
+    path: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _ = tokio::fs::read_to_string("settings.json").await?;
+    Ok(())
+}
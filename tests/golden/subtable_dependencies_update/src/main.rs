@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Settings {
+    path: String,
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    log::info!("started");
+}
+
+#[cfg(not(feature = "runtime"))]
+fn main() {
+    log::info!("started");
+}
@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Report {
+    total: usize,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("report failed")]
+struct ReportError;
+
+fn main() -> Result<(), ReportError> {
+    let _ = Report { total: 0 };
+    Ok(())
+}
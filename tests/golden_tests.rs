@@ -0,0 +1,213 @@
+//! Golden tests for Cargo.toml rewriting.
+//!
+//! Each directory under `tests/golden/` is a project: its `Cargo.toml.in` manifests
+//! (named so cargo doesn't pick them up) are copied as `Cargo.toml` together with the
+//! sources, the updater is run against a mock registry in the directory holding
+//! `Cargo.toml.golden` (the project root, or a workspace member), and the rewritten
+//! manifest must match the golden file byte for byte. `*_update` cases also raise declared
+//! requirements, as `cargo autodd update` does. Run with `UPDATE_GOLDEN=1` to rewrite the
+//! golden files after an intended change.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use anyhow::Result;
+use cargo_autodd::Config;
+use cargo_autodd::dependency_manager::{DependencyAnalyzer, DependencyUpdater};
+use tempfile::TempDir;
+
+/// Latest versions served by the mock registry
+const REGISTRY: &[(&str, &str)] = &[
+    ("anyhow", "1.0.95"),
+    ("clap", "4.5.23"),
+    ("log", "0.4.22"),
+    ("rand", "0.8.5"),
+    ("regex", "1.11.1"),
+    ("serde", "1.0.217"),
+    ("tempfile", "3.15.0"),
+    ("thiserror", "2.0.11"),
+    ("tokio", "1.43.0"),
+];
+
+/// Serve `REGISTRY` over HTTP in the background; unknown crates get a 404
+fn spawn_registry() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let versions = REGISTRY.iter().copied().collect::<HashMap<_, _>>();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                    break;
+                }
+            }
+
+            let name = request_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|path| path.strip_prefix("/crates/"))
+                .unwrap_or_default();
+            let (status, body) = match versions.get(name) {
+                Some(version) => (
+                    "200 OK",
+                    format!(r#"{{"versions":[{{"num":"{}","yanked":false}}]}}"#, version),
+                ),
+                None => ("404 Not Found", r#"{"errors":[]}"#.to_string()),
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+/// Copy a fixture into `dest`, turning `Cargo.toml.in` into `Cargo.toml` and leaving out
+/// the golden file
+fn copy_fixture(src: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() {
+            copy_fixture(&entry.path(), &dest.join(&name))?;
+        } else if name == "Cargo.toml.in" {
+            fs::copy(entry.path(), dest.join("Cargo.toml"))?;
+        } else if name != "Cargo.toml.golden" {
+            fs::copy(entry.path(), dest.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Directory (relative to `dir`) holding the fixture's `Cargo.toml.golden`
+fn golden_dir(dir: &Path) -> Option<PathBuf> {
+    if dir.join("Cargo.toml.golden").exists() {
+        return Some(PathBuf::new());
+    }
+    fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let nested = golden_dir(&path).filter(|_| path.is_dir())?;
+        Some(Path::new(&entry.file_name()).join(nested))
+    })
+}
+
+/// Analyze the project at `root` and rewrite its Cargo.toml; with `update`, also raise
+/// declared requirements to the latest versions, as `cargo autodd update` does
+fn run_updater(root: &Path, registry: &str, update: bool) -> Result<()> {
+    let config = Config::load_default(root)?;
+    let mut analyzer = DependencyAnalyzer::new(root.to_path_buf());
+    analyzer.set_config(config.clone());
+    let mut updater = DependencyUpdater::new(root.to_path_buf());
+    updater.set_config(config);
+    updater.set_registry_api(registry);
+
+    let crate_refs = analyzer.analyze_dependencies()?;
+    if update {
+        let mut plan = updater.plan(&crate_refs)?;
+        updater.resolve_plan(&mut plan, &crate_refs);
+        plan.updates = updater.find_version_updates(&plan)?;
+        updater.apply_plan(&plan, &crate_refs)?;
+    } else {
+        updater.update_cargo_toml(&crate_refs)?;
+    }
+    Ok(())
+}
+
+fn check_golden(case: &str) -> Result<()> {
+    check_golden_with(case, false)
+}
+
+/// Like [`check_golden`], also raising declared requirements when `update` is set
+fn check_golden_with(case: &str, update: bool) -> Result<()> {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(case);
+    let member = golden_dir(&fixture).unwrap_or_default();
+    let temp_dir = TempDir::new()?;
+    copy_fixture(&fixture, temp_dir.path())?;
+    let root = temp_dir.path().join(&member);
+    let registry = spawn_registry();
+
+    run_updater(&root, &registry, update)?;
+    let actual = fs::read_to_string(root.join("Cargo.toml"))?;
+
+    let golden = fixture.join(&member).join("Cargo.toml.golden");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual)?;
+    }
+    let expected = fs::read_to_string(&golden)?;
+    assert_eq!(
+        actual, expected,
+        "{}: rewritten Cargo.toml differs from the golden file",
+        case
+    );
+
+    // A second run finds nothing left to change
+    run_updater(&root, &registry, update)?;
+    assert_eq!(
+        fs::read_to_string(root.join("Cargo.toml"))?,
+        expected,
+        "{}: the rewrite is not idempotent",
+        case
+    );
+    Ok(())
+}
+
+#[test]
+fn golden_comments_preserved() -> Result<()> {
+    check_golden("comments_preserved")
+}
+
+#[test]
+fn golden_dev_dependency_routing() -> Result<()> {
+    check_golden("dev_dependency_routing")
+}
+
+#[test]
+fn golden_path_and_git_dependencies() -> Result<()> {
+    check_golden("path_and_git_dependencies")
+}
+
+#[test]
+fn golden_workspace_inheritance() -> Result<()> {
+    check_golden("workspace_inheritance")
+}
+
+#[test]
+fn golden_feature_merge() -> Result<()> {
+    check_golden("feature_merge")
+}
+
+#[test]
+fn golden_config_version_format() -> Result<()> {
+    check_golden("config_version_format")
+}
+
+#[test]
+fn golden_dependency_key_order() -> Result<()> {
+    check_golden("dependency_key_order")
+}
+
+#[test]
+fn golden_subtable_dependencies() -> Result<()> {
+    check_golden("subtable_dependencies")
+}
+
+#[test]
+fn golden_subtable_dependencies_update() -> Result<()> {
+    check_golden_with("subtable_dependencies_update", true)
+}
@@ -99,3 +99,19 @@ fn test_update_dependencies() -> Result<()> {
     autodd.update_dependencies()?;
     Ok(())
 }
+
+#[test]
+fn test_version_output() -> Result<()> {
+    // Run directly and as a cargo subcommand, the binary reports its own name
+    for args in [&["--version"][..], &["autodd", "--version"]] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-autodd"))
+            .args(args)
+            .output()?;
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            format!("cargo-autodd {}\n", env!("CARGO_PKG_VERSION"))
+        );
+    }
+    Ok(())
+}